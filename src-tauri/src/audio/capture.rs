@@ -1,7 +1,13 @@
+use crate::error::AppError;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, Stream, StreamConfig, SupportedStreamConfig};
+use cpal::{
+    BufferSize, SampleFormat, SampleRate, Stream, StreamConfig, SupportedBufferSize,
+    SupportedStreamConfig, SupportedStreamConfigRange,
+};
 use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// 采集线程推送给分析线程的数据块，统一使用单声道浮点样本。
@@ -9,23 +15,228 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub struct CaptureChunk {
     pub timestamp_ms: u64,
     pub samples: Vec<f32>,
+    /// 立体声模式下的左右声道样本（各与 `samples` 等长）；未开启或设备不足两声道时为 `None`。
+    pub stereo: Option<(Vec<f32>, Vec<f32>)>,
+    /// 折叠前各声道中达到削波阈值的样本数，用于提示输入电平过高。
+    pub clipped: u32,
 }
 
-/// 当前采集会话句柄，`stream` 生命周期必须被持有，否则系统采集会停止。
+/// 削波判定阈值：标准化后绝对值不低于该值的样本视为已削波。
+pub const CLIP_THRESHOLD: f32 = 0.999;
+
+/// 当前采集会话句柄，`streams` 生命周期必须被持有，否则系统采集会停止。
 pub struct CaptureRuntime {
-    pub stream: Stream,
+    /// 采集流句柄；混合采集时包含主采集流与附加的默认输入流。
+    pub streams: Vec<Stream>,
     pub device_id: String,
+    pub device_name: String,
+    /// 回环采集不可用而降级为默认输入（麦克风）时的原因，供前端提示。
+    pub fallback_reason: Option<String>,
     pub sample_rate: u32,
     pub channels: u16,
+    /// 各采集流的错误标志，流回调上报错误（如设备被拔出）时置位。
+    failure_flags: Vec<Arc<AtomicBool>>,
+}
+
+impl CaptureRuntime {
+    fn new(
+        stream: Stream,
+        device_id: String,
+        device_name: String,
+        config: &SupportedStreamConfig,
+        stream_failed: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            streams: vec![stream],
+            device_id,
+            device_name,
+            fallback_reason: None,
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            failure_flags: vec![stream_failed],
+        }
+    }
+
+    /// 任一采集流上报错误时返回 `true`，分析线程据此判定采集已断开。
+    pub fn stream_failed(&self) -> bool {
+        self.failure_flags
+            .iter()
+            .any(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// 并入另一采集会话的流句柄与错误标志，使其与本会话同生命周期；设备信息仍以本会话为准。
+    pub fn attach(&mut self, other: CaptureRuntime) {
+        self.streams.extend(other.streams);
+        self.failure_flags.extend(other.failure_flags);
+    }
 }
 
+/// 下混权重最多支持的声道数，覆盖 7.1 环绕声。
+pub const MAX_DOWNMIX_CHANNELS: usize = 8;
+
+/// 多声道折叠为单声道时的逐声道权重，按设备声道顺序排列；定长存储以保持 `CaptureConfig` 可复制。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownmixWeights {
+    weights: [f32; MAX_DOWNMIX_CHANNELS],
+    len: usize,
+    total: f32,
+}
+
+impl DownmixWeights {
+    /// 从设置构建权重；为空、超过声道上限、含非有限值或权重和不为正时返回 `None`，即平均折叠。
+    pub fn from_slice(weights: &[f32]) -> Option<Self> {
+        if weights.is_empty()
+            || weights.len() > MAX_DOWNMIX_CHANNELS
+            || weights.iter().any(|weight| !weight.is_finite())
+        {
+            return None;
+        }
+        let total = weights.iter().sum::<f32>();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut fixed = [0.0; MAX_DOWNMIX_CHANNELS];
+        fixed[..weights.len()].copy_from_slice(weights);
+        Some(Self {
+            weights: fixed,
+            len: weights.len(),
+            total,
+        })
+    }
+
+    /// 权重对应的声道数。
+    pub fn channel_count(&self) -> usize {
+        self.len
+    }
+}
+
+/// 多声道折叠为单声道的方式：`Average` 为各声道（加权）平均，相关的立体声内容电平与单声道一致；
+/// `Sum` 为（加权）求和并夹到 `[-1, 1]`，相关内容约高 6 dB；`Max` 取绝对值最大的声道样本；
+/// `Rms` 取各声道（加权）均方根，保留平均值的符号，避免波形被整流成只有正半周。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownmixMode {
+    #[default]
+    Average,
+    Sum,
+    Max,
+    Rms,
+}
+
+impl DownmixMode {
+    /// 解析设置中的折叠方式名称，非法值回退平均。
+    pub fn from_raw(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "sum" => Self::Sum,
+            "max" => Self::Max,
+            "rms" => Self::Rms,
+            _ => Self::Average,
+        }
+    }
+}
+
+/// 单声道折叠配置：折叠方式与可选的逐声道权重，采集回调按帧调用 [`Downmix::reduce`]。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Downmix {
+    pub mode: DownmixMode,
+    /// 逐声道权重，`None` 时各声道权重为 1。
+    pub weights: Option<DownmixWeights>,
+}
+
+impl Downmix {
+    /// 把一帧已标准化的各声道样本折叠为单个样本；各格式的采集回调共用这一实现。
+    fn reduce(&self, frame: &[f32]) -> f32 {
+        if frame.is_empty() {
+            return 0.0;
+        }
+        let weight = |channel: usize| self.weights.map_or(1.0, |weights| weights.weights[channel]);
+        let total = self
+            .weights
+            .map_or(frame.len() as f32, |weights| weights.total);
+        let weighted = || {
+            frame
+                .iter()
+                .enumerate()
+                .map(move |(channel, sample)| sample * weight(channel))
+        };
+
+        match self.mode {
+            DownmixMode::Average => weighted().sum::<f32>() / total,
+            DownmixMode::Sum => weighted().sum::<f32>().clamp(-1.0, 1.0),
+            DownmixMode::Max => weighted()
+                .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or(0.0),
+            DownmixMode::Rms => {
+                let mean_square = frame
+                    .iter()
+                    .enumerate()
+                    .map(|(channel, sample)| sample * sample * weight(channel))
+                    .sum::<f32>()
+                    / total;
+                let mean = weighted().sum::<f32>() / total;
+                mean_square.max(0.0).sqrt().copysign(mean)
+            }
+        }
+    }
+}
+
+/// 声道模式：`Mono` 只输出折叠后的单声道；`Stereo` 额外输出左右声道频段；
+/// `MidSide` 额外输出中置 `(L+R)/2` 与侧向 `(L-R)/2` 频段，便于观察声像宽度。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChannelMode {
+    #[default]
+    Mono,
+    Stereo,
+    MidSide,
+}
+
+impl ChannelMode {
+    /// 解析设置中的声道模式名称，非法值回退单声道。
+    pub fn from_raw(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "stereo" => Self::Stereo,
+            "midside" | "mid_side" | "ms" => Self::MidSide,
+            _ => Self::Mono,
+        }
+    }
+
+    /// 采集回调是否需要在单声道之外保留左右声道样本。
+    pub fn keeps_channels(self) -> bool {
+        self != Self::Mono
+    }
+}
+
+/// 采集流配置请求：`None` 表示沿用设备默认值。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CaptureConfig {
+    pub sample_rate: Option<u32>,
+    /// 固定回调缓冲帧数，越小延迟越低但越容易爆音。
+    pub buffer_frames: Option<u32>,
+    /// 声道模式，非单声道时在单声道之外额外保留左右声道样本。
+    pub channel_mode: ChannelMode,
+    /// 单声道折叠方式与权重，默认各声道平均。
+    pub downmix: Downmix,
+    /// 是否额外打开默认输入设备（麦克风）并与主采集混合后再分析。
+    pub mix_default_input: bool,
+}
+
+/// WASAPI 回环采集会话的 `device_id` 前缀，对应的可选设备标识为 `"output:{name}"`。
+pub const LOOPBACK_ID_PREFIX: &str = "loopback:";
+
 /// 前端设备选择面板可用的数据结构。
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioDeviceInfo {
     pub id: String,
     pub name: String,
     pub direction: String,
+    /// 是否为系统当前默认设备（按名称与宿主默认设备比对）。
+    pub is_default: bool,
+    /// 设备默认流格式；驱动拒绝查询时为 0。
+    pub default_sample_rate: u32,
+    pub channels: u16,
 }
 
 /// 统一毫秒时间戳，便于计算采样到渲染链路时延。
@@ -36,19 +247,29 @@ fn now_timestamp_ms() -> u64 {
 }
 
 /// 列出输入/输出设备，供前端后续做设备切换。
-pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, AppError> {
     let host = cpal::default_host();
     let mut devices = Vec::new();
+    let default_input_name = host
+        .default_input_device()
+        .and_then(|device| device.name().ok());
+    let default_output_name = host
+        .default_output_device()
+        .and_then(|device| device.name().ok());
 
     if let Ok(input_devices) = host.input_devices() {
         for device in input_devices {
             let name = device
                 .name()
                 .unwrap_or_else(|_| "Unknown Input Device".to_string());
+            let (default_sample_rate, channels) = default_format(device.default_input_config());
             devices.push(AudioDeviceInfo {
                 id: format!("input:{name}"),
+                is_default: default_input_name.as_ref() == Some(&name),
                 name,
                 direction: "input".to_string(),
+                default_sample_rate,
+                channels,
             });
         }
     }
@@ -58,183 +279,689 @@ pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
             let name = device
                 .name()
                 .unwrap_or_else(|_| "Unknown Output Device".to_string());
+            let (default_sample_rate, channels) = default_format(device.default_output_config());
             devices.push(AudioDeviceInfo {
                 id: format!("output:{name}"),
+                is_default: default_output_name.as_ref() == Some(&name),
                 name,
                 direction: "output".to_string(),
+                default_sample_rate,
+                channels,
             });
         }
     }
 
     if devices.is_empty() {
-        return Err("no audio devices found".to_string());
+        return Err(AppError::DeviceNotFound(
+            "no audio devices found".to_string(),
+        ));
     }
 
     Ok(devices)
 }
 
-/// 启动采集流：优先尝试默认输出设备（WASAPI loopback 候选），失败后降级为默认输入设备。
-pub fn start_loopback_capture(sender: Sender<CaptureChunk>) -> Result<CaptureRuntime, String> {
+/// 提取默认流格式的采样率与声道数；设备被独占或驱动异常时返回 `(0, 0)`，不影响列出设备。
+fn default_format(
+    config: Result<SupportedStreamConfig, cpal::DefaultStreamConfigError>,
+) -> (u32, u16) {
+    config.map_or((0, 0), |config| (config.sample_rate().0, config.channels()))
+}
+
+/// 把采集会话的 `device_id` 换算为 `list_audio_devices` 中的设备标识（回环会话对应其输出设备）。
+pub fn selectable_device_id(device_id: &str) -> String {
+    match device_id.strip_prefix(LOOPBACK_ID_PREFIX) {
+        Some(name) => format!("output:{name}"),
+        None => device_id.to_string(),
+    }
+}
+
+/// 按 `"output:{name}"` 标识查找输出设备；标识为空或设备已不存在时回退默认输出设备。
+///
+/// 返回设备及其实际使用的标识，调用方据此判断是否发生了回退。
+pub fn resolve_output_device(device_id: &str) -> Result<(cpal::Device, String), AppError> {
     let host = cpal::default_host();
-    let mut output_attempt_error = String::new();
 
-    if let Some(output_device) = host.default_output_device() {
-        let output_name = output_device
-            .name()
-            .unwrap_or_else(|_| "Default Output".to_string());
-
-        match output_device.default_output_config() {
-            Ok(config) => {
-                match build_input_stream_for_config(&output_device, config.clone(), sender.clone())
-                {
-                    Ok(stream) => {
-                        stream.play().map_err(|err| {
-                            format!("failed to play output loopback stream: {err}")
-                        })?;
-                        return Ok(CaptureRuntime {
-                            stream,
-                            device_id: format!("output:{output_name}"),
-                            sample_rate: config.sample_rate().0,
-                            channels: config.channels(),
-                        });
-                    }
-                    Err(err) => {
-                        output_attempt_error = format!("output loopback failed: {err}");
-                    }
-                }
+    if let Some(wanted_name) = requested_output_name(device_id) {
+        let matched = host
+            .output_devices()
+            .ok()
+            .and_then(|devices| find_device_by_name(devices, wanted_name));
+        match matched {
+            Some(device) => return Ok((device, format!("output:{wanted_name}"))),
+            None => eprintln!("output device {device_id} not found, fallback to default output"),
+        }
+    }
+
+    let device = host.default_output_device().ok_or_else(|| {
+        AppError::DeviceNotFound("no default output device available".to_string())
+    })?;
+    let name = device
+        .name()
+        .unwrap_or_else(|_| "Default Output".to_string());
+    Ok((device, format!("output:{name}")))
+}
+
+/// 从设置中的 `"output:{name}"` 标识取出要使用的输出设备名；为空或不是输出设备标识时返回 `None`，使用默认输出。
+fn requested_output_name(device_id: &str) -> Option<&str> {
+    device_id
+        .trim()
+        .strip_prefix("output:")
+        .filter(|name| !name.is_empty())
+}
+
+/// 启动采集流：`device_id`（`list_audio_devices` 的 `id` 格式）可解析时使用指定设备；
+/// 否则优先回环采集默认输出设备（Windows 下为 WASAPI loopback），失败后降级为默认输入设备。
+pub fn start_loopback_capture(
+    sender: Sender<CaptureChunk>,
+    device_id: Option<&str>,
+    capture_config: CaptureConfig,
+) -> Result<CaptureRuntime, AppError> {
+    let host = cpal::default_host();
+
+    if let Some(device_id) = device_id.map(str::trim).filter(|id| !id.is_empty()) {
+        match start_capture_for_device(device_id, sender.clone(), capture_config) {
+            Ok(runtime) => return Ok(runtime),
+            Err(err) => {
+                eprintln!("capture device {device_id} unavailable, fallback to default: {err}")
             }
+        }
+    }
+
+    let mut output_attempt_error = "no default output device available".to_string();
+    if let Some(output_device) = host.default_output_device() {
+        match start_output_loopback(&host, &output_device, sender.clone(), capture_config) {
+            Ok(runtime) => return Ok(runtime),
             Err(err) => {
-                output_attempt_error = format!("failed to read output config: {err}");
+                // 关键行：降级到麦克风时明确记录原因，避免“看似在采集系统声音”的静默回退。
+                eprintln!("output loopback unavailable, fallback to default input: {err}");
+                output_attempt_error = err.to_string();
             }
         }
     }
 
-    let input_device = host
+    let input_device = host.default_input_device().ok_or_else(|| {
+        AppError::DeviceNotFound(format!(
+            "no default input device available; {output_attempt_error}"
+        ))
+    })?;
+    let mut runtime = start_input_capture(&input_device, sender, capture_config)
+        .map_err(|err| AppError::Capture(format!("{err}; {output_attempt_error}")))?;
+    runtime.fallback_reason = Some(output_attempt_error);
+    Ok(runtime)
+}
+
+/// 打开默认输入设备作为混合采集的附加来源：只需单声道，立体声与下混权重不适用于该设备。
+pub fn start_default_input_capture(
+    sender: Sender<CaptureChunk>,
+    capture_config: CaptureConfig,
+) -> Result<CaptureRuntime, AppError> {
+    let input_device = cpal::default_host()
         .default_input_device()
-        .ok_or_else(|| format!("no default input device available; {output_attempt_error}"))?;
+        .ok_or_else(|| AppError::DeviceNotFound("no default input device available".to_string()))?;
+    let input_config = CaptureConfig {
+        channel_mode: ChannelMode::Mono,
+        downmix: Downmix::default(),
+        ..capture_config
+    };
+    start_input_capture(&input_device, sender, input_config)
+}
+
+/// 按 `list_audio_devices` 产生的 `"output:{name}"` / `"input:{name}"` 标识定位设备并启动采集；
+/// 输出设备走回环采集。设备不存在时返回 `DeviceNotFound`，不做回退。
+pub fn start_capture_for_device(
+    device_id: &str,
+    sender: Sender<CaptureChunk>,
+    capture_config: CaptureConfig,
+) -> Result<CaptureRuntime, AppError> {
+    let host = cpal::default_host();
+    if let Some(name) = device_id.strip_prefix("output:") {
+        let device = host
+            .output_devices()
+            .ok()
+            .and_then(|devices| find_device_by_name(devices, name))
+            .ok_or_else(|| AppError::DeviceNotFound(format!("output device {name} not found")))?;
+        return start_output_loopback(&host, &device, sender, capture_config);
+    }
+    if let Some(name) = device_id.strip_prefix("input:") {
+        let device = host
+            .input_devices()
+            .ok()
+            .and_then(|devices| find_device_by_name(devices, name))
+            .ok_or_else(|| AppError::DeviceNotFound(format!("input device {name} not found")))?;
+        return start_input_capture(&device, sender, capture_config);
+    }
+    Err(AppError::InvalidArgument(format!(
+        "unrecognized device id {device_id}"
+    )))
+}
+
+/// 在设备列表中查找名称完全匹配的设备。
+fn find_device_by_name(
+    mut devices: impl Iterator<Item = cpal::Device>,
+    name: &str,
+) -> Option<cpal::Device> {
+    devices.find(|device| device.name().is_ok_and(|device_name| device_name == name))
+}
+
+/// Windows：以 WASAPI 回环模式打开渲染端点。
+///
+/// cpal 的 WASAPI 后端在输出设备上建立输入流时会自动附加 `AUDCLNT_STREAMFLAGS_LOOPBACK`，
+/// 返回的 `device_id` 使用 `loopback:` 前缀，与普通输入采集明确区分。
+#[cfg(windows)]
+fn start_output_loopback(
+    host: &cpal::Host,
+    output_device: &cpal::Device,
+    sender: Sender<CaptureChunk>,
+    capture_config: CaptureConfig,
+) -> Result<CaptureRuntime, AppError> {
+    if host.id() != cpal::HostId::Wasapi {
+        return Err(AppError::Unsupported(format!(
+            "loopback unsupported: audio host {:?} has no loopback capture",
+            host.id()
+        )));
+    }
+
+    let output_name = output_device
+        .name()
+        .unwrap_or_else(|_| "Default Output".to_string());
+    let config = output_device
+        .default_output_config()
+        .map_err(|err| AppError::Capture(format!("failed to read output config: {err}")))
+        .map(|config| {
+            select_stream_config(
+                config,
+                output_device.supported_output_configs().ok(),
+                capture_config.sample_rate,
+            )
+        })
+        .and_then(|config| validate_stream_config(&config).map(|_| config))
+        .map_err(|err| {
+            AppError::Capture(format!(
+                "loopback failed to start: output device {output_name} rejected: {err}"
+            ))
+        })?;
+    let (stream, stream_failed) =
+        build_input_stream_for_config(output_device, config.clone(), capture_config, sender)
+            .map_err(|err| AppError::Capture(format!("loopback failed to start: {err}")))?;
+    stream
+        .play()
+        .map_err(|err| AppError::Capture(format!("loopback failed to start: {err}")))?;
+
+    Ok(CaptureRuntime::new(
+        stream,
+        format!("{LOOPBACK_ID_PREFIX}{output_name}"),
+        output_name,
+        &config,
+        stream_failed,
+    ))
+}
+
+/// 非 Windows：保持原有行为，尝试直接在输出设备上建立输入流；多数后端不支持回环，失败后由调用方降级。
+#[cfg(not(windows))]
+fn start_output_loopback(
+    _host: &cpal::Host,
+    output_device: &cpal::Device,
+    sender: Sender<CaptureChunk>,
+    capture_config: CaptureConfig,
+) -> Result<CaptureRuntime, AppError> {
+    let output_name = output_device
+        .name()
+        .unwrap_or_else(|_| "Default Output".to_string());
+    let config = output_device
+        .default_output_config()
+        .map_err(|err| AppError::Capture(format!("failed to read output config: {err}")))
+        .map(|config| {
+            select_stream_config(
+                config,
+                output_device.supported_output_configs().ok(),
+                capture_config.sample_rate,
+            )
+        })
+        .and_then(|config| validate_stream_config(&config).map(|_| config))
+        .map_err(|err| AppError::Capture(format!("output device {output_name} rejected: {err}")))?;
+    let (stream, stream_failed) =
+        build_input_stream_for_config(output_device, config.clone(), capture_config, sender)
+            .map_err(|err| {
+                AppError::Unsupported(format!(
+                    "loopback unsupported on this platform; output capture failed: {err}"
+                ))
+            })?;
+    stream.play().map_err(|err| {
+        AppError::Capture(format!("failed to play output loopback stream: {err}"))
+    })?;
+
+    Ok(CaptureRuntime::new(
+        stream,
+        format!("output:{output_name}"),
+        output_name,
+        &config,
+        stream_failed,
+    ))
+}
+
+/// 对输入设备建立普通采集流。
+fn start_input_capture(
+    input_device: &cpal::Device,
+    sender: Sender<CaptureChunk>,
+    capture_config: CaptureConfig,
+) -> Result<CaptureRuntime, AppError> {
     let input_name = input_device
         .name()
         .unwrap_or_else(|_| "Default Input".to_string());
     let input_config = input_device
         .default_input_config()
-        .map_err(|err| format!("failed to read input config: {err}"))?;
-    let stream = build_input_stream_for_config(&input_device, input_config.clone(), sender)?;
+        .map_err(|err| AppError::Capture(format!("failed to read input config: {err}")))
+        .map(|config| {
+            select_stream_config(
+                config,
+                input_device.supported_input_configs().ok(),
+                capture_config.sample_rate,
+            )
+        })?;
+    validate_stream_config(&input_config)
+        .map_err(|err| AppError::Capture(format!("input device {input_name} rejected: {err}")))?;
+    let (stream, stream_failed) =
+        build_input_stream_for_config(input_device, input_config.clone(), capture_config, sender)?;
     stream
         .play()
-        .map_err(|err| format!("failed to play input capture stream: {err}"))?;
+        .map_err(|err| AppError::Capture(format!("failed to play input capture stream: {err}")))?;
 
-    Ok(CaptureRuntime {
+    Ok(CaptureRuntime::new(
         stream,
-        device_id: format!("input:{input_name}"),
-        sample_rate: input_config.sample_rate().0,
-        channels: input_config.channels(),
-    })
+        format!("input:{input_name}"),
+        input_name,
+        &input_config,
+        stream_failed,
+    ))
+}
+
+/// 按请求的采样率从设备支持的配置中挑选流配置，优先保持默认声道数与样本格式；
+/// 设备不支持该采样率时回退默认配置，实际打开的采样率由 `CaptureRuntime::sample_rate` 反映。
+fn select_stream_config(
+    default_config: SupportedStreamConfig,
+    supported: Option<impl Iterator<Item = SupportedStreamConfigRange>>,
+    requested_rate: Option<u32>,
+) -> SupportedStreamConfig {
+    let Some(rate) = requested_rate else {
+        return default_config;
+    };
+    if default_config.sample_rate().0 == rate {
+        return default_config;
+    }
+
+    let matched = supported
+        .into_iter()
+        .flatten()
+        .filter(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&rate))
+        .filter(|range| {
+            matches!(
+                range.sample_format(),
+                SampleFormat::F32
+                    | SampleFormat::F64
+                    | SampleFormat::I16
+                    | SampleFormat::I32
+                    | SampleFormat::U16
+                    | SampleFormat::U8
+            )
+        })
+        .max_by_key(|range| {
+            (
+                range.channels() == default_config.channels(),
+                range.sample_format() == default_config.sample_format(),
+            )
+        });
+    match matched {
+        Some(range) => range.with_sample_rate(SampleRate(rate)),
+        None => {
+            eprintln!(
+                "capture sample rate {rate} Hz unsupported, opened default {} Hz",
+                default_config.sample_rate().0
+            );
+            default_config
+        }
+    }
+}
+
+/// 校验设备上报的流配置：声道数或采样率为 0 时立即拒绝，避免建流后长时间收不到数据。
+fn validate_stream_config(config: &SupportedStreamConfig) -> Result<(), AppError> {
+    if config.channels() == 0 {
+        return Err(AppError::Capture(
+            "device reports zero channels".to_string(),
+        ));
+    }
+    if config.sample_rate().0 == 0 {
+        return Err(AppError::Capture(
+            "device reports zero sample rate".to_string(),
+        ));
+    }
+    Ok(())
 }
 
 /// 基于设备采样格式创建输入流，并把多声道样本折叠为单声道发送到分析线程。
+///
+/// 同时返回流错误标志：设备断开时 cpal 仍持有回调与发送端，通道不会关闭，只能靠该标志感知。
 fn build_input_stream_for_config(
     device: &cpal::Device,
     supported_config: SupportedStreamConfig,
+    capture_config: CaptureConfig,
     sender: Sender<CaptureChunk>,
-) -> Result<Stream, String> {
-    let stream_config: StreamConfig = supported_config.clone().into();
+) -> Result<(Stream, Arc<AtomicBool>), AppError> {
+    let mut stream_config: StreamConfig = supported_config.clone().into();
+    if let Some(frames) = capture_config.buffer_frames {
+        // 关键行：设备上报了缓冲范围时夹到范围内，避免固定缓冲请求直接建流失败。
+        let frames = match supported_config.buffer_size() {
+            SupportedBufferSize::Range { min, max } => frames.clamp(*min, *max),
+            SupportedBufferSize::Unknown => frames,
+        };
+        stream_config.buffer_size = BufferSize::Fixed(frames);
+    }
     let channels = stream_config.channels as usize;
-    let error_callback = |error| eprintln!("audio stream error: {error}");
-
-    match supported_config.sample_format() {
-        SampleFormat::F32 => {
-            let sender_f32 = sender.clone();
-            device
-                .build_input_stream(
-                    &stream_config,
-                    move |data: &[f32], _| push_mono_f32(data, channels, &sender_f32),
-                    error_callback,
-                    None,
-                )
-                .map_err(|err| format!("failed to build f32 input stream: {err}"))
-        }
-        SampleFormat::I16 => {
-            let sender_i16 = sender.clone();
-            device
-                .build_input_stream(
-                    &stream_config,
-                    move |data: &[i16], _| push_mono_i16(data, channels, &sender_i16),
-                    error_callback,
-                    None,
-                )
-                .map_err(|err| format!("failed to build i16 input stream: {err}"))
+    let stereo = capture_config.channel_mode.keeps_channels();
+    // 关键行：只在建流时校验一次声道数，不匹配时整条流忽略权重，避免回调里反复打印。
+    let mut downmix = capture_config.downmix;
+    downmix.weights = downmix.weights.filter(|weights| {
+        let matches = weights.channel_count() == channels;
+        if !matches {
+            eprintln!(
+                "downmix weights expect {} channels but stream has {channels}, ignoring weights",
+                weights.channel_count()
+            );
         }
-        SampleFormat::U16 => device
-            .build_input_stream(
-                &stream_config,
-                move |data: &[u16], _| push_mono_u16(data, channels, &sender),
-                error_callback,
-                None,
-            )
-            .map_err(|err| format!("failed to build u16 input stream: {err}")),
-        _ => Err(format!(
+        matches
+    });
+    let stream_failed = Arc::new(AtomicBool::new(false));
+    let failed_flag = stream_failed.clone();
+    let error_callback = move |error| {
+        eprintln!("audio stream error: {error}");
+        failed_flag.store(true, Ordering::Relaxed);
+    };
+
+    let stream = match supported_config.sample_format() {
+        SampleFormat::F32 => build_typed_stream::<f32>(
+            device,
+            &stream_config,
+            channels,
+            stereo,
+            downmix,
+            sender,
+            error_callback,
+        ),
+        SampleFormat::F64 => build_typed_stream::<f64>(
+            device,
+            &stream_config,
+            channels,
+            stereo,
+            downmix,
+            sender,
+            error_callback,
+        ),
+        SampleFormat::I16 => build_typed_stream::<i16>(
+            device,
+            &stream_config,
+            channels,
+            stereo,
+            downmix,
+            sender,
+            error_callback,
+        ),
+        SampleFormat::I32 => build_typed_stream::<i32>(
+            device,
+            &stream_config,
+            channels,
+            stereo,
+            downmix,
+            sender,
+            error_callback,
+        ),
+        SampleFormat::U16 => build_typed_stream::<u16>(
+            device,
+            &stream_config,
+            channels,
+            stereo,
+            downmix,
+            sender,
+            error_callback,
+        ),
+        SampleFormat::U8 => build_typed_stream::<u8>(
+            device,
+            &stream_config,
+            channels,
+            stereo,
+            downmix,
+            sender,
+            error_callback,
+        ),
+        _ => Err(AppError::Unsupported(format!(
             "unsupported sample format: {:?}",
             supported_config.sample_format()
-        )),
+        ))),
+    }?;
+    Ok((stream, stream_failed))
+}
+
+/// 采集支持的样本格式：定义单个样本到 `[-1, 1]` 浮点的标准化方式，折叠与分块逻辑各格式共用。
+///
+/// 整数格式统一除以 2^(位数-1)：最小值恰为 -1.0，最大值略小于 1.0，有符号与无符号格式刻度一致。
+trait IntoF32Sample: cpal::SizedSample + Send + 'static {
+    /// 建流失败时错误信息中使用的格式名。
+    const FORMAT_NAME: &'static str;
+
+    fn into_f32(self) -> f32;
+}
+
+impl IntoF32Sample for f32 {
+    const FORMAT_NAME: &'static str = "f32";
+
+    fn into_f32(self) -> f32 {
+        self
     }
 }
 
-/// 处理 `f32` 样本并折叠为单声道，减少后续分析计算量。
-fn push_mono_f32(samples: &[f32], channels: usize, sender: &Sender<CaptureChunk>) {
-    if channels == 0 || samples.is_empty() {
-        return;
+/// 部分专业声卡的原生格式，直接转换精度。
+impl IntoF32Sample for f64 {
+    const FORMAT_NAME: &'static str = "f64";
+
+    fn into_f32(self) -> f32 {
+        self as f32
     }
+}
 
-    let mut mono = Vec::with_capacity(samples.len() / channels + 1);
-    for frame in samples.chunks(channels) {
-        let sum = frame.iter().copied().sum::<f32>();
-        mono.push(sum / frame.len() as f32);
+impl IntoF32Sample for i16 {
+    const FORMAT_NAME: &'static str = "i16";
+
+    fn into_f32(self) -> f32 {
+        self as f32 / 32_768.0
     }
+}
 
-    let _ = sender.send(CaptureChunk {
-        timestamp_ms: now_timestamp_ms(),
-        samples: mono,
-    });
+impl IntoF32Sample for i32 {
+    const FORMAT_NAME: &'static str = "i32";
+
+    fn into_f32(self) -> f32 {
+        // 关键行：先转 f64 再相除，避免 f32 尾数不足导致低位样本精度丢失。
+        (self as f64 / 2_147_483_648.0) as f32
+    }
 }
 
-/// 处理 `i16` 样本并标准化到 `[-1, 1]` 区间。
-fn push_mono_i16(samples: &[i16], channels: usize, sender: &Sender<CaptureChunk>) {
-    if channels == 0 || samples.is_empty() {
-        return;
+impl IntoF32Sample for u16 {
+    const FORMAT_NAME: &'static str = "u16";
+
+    fn into_f32(self) -> f32 {
+        // 关键行：无符号格式以中点 32768 为零电平，减去中点再缩放，静音不会带直流偏置。
+        (self as f32 - 32_768.0) / 32_768.0
     }
+}
 
-    let mut mono = Vec::with_capacity(samples.len() / channels + 1);
-    for frame in samples.chunks(channels) {
-        let sum = frame
-            .iter()
-            .map(|sample| *sample as f32 / i16::MAX as f32)
-            .sum::<f32>();
-        mono.push(sum / frame.len() as f32);
+impl IntoF32Sample for u8 {
+    const FORMAT_NAME: &'static str = "u8";
+
+    fn into_f32(self) -> f32 {
+        (self as f32 - 128.0) / 128.0
     }
+}
 
-    let _ = sender.send(CaptureChunk {
-        timestamp_ms: now_timestamp_ms(),
-        samples: mono,
-    });
+/// 按样本类型 `S` 建立输入流，回调里把样本标准化并折叠为单声道发送。
+fn build_typed_stream<S: IntoF32Sample>(
+    device: &cpal::Device,
+    stream_config: &StreamConfig,
+    channels: usize,
+    stereo: bool,
+    downmix: Downmix,
+    sender: Sender<CaptureChunk>,
+    error_callback: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<Stream, AppError> {
+    device
+        .build_input_stream(
+            stream_config,
+            move |data: &[S], _| push_mono(data, channels, stereo, downmix, &sender),
+            error_callback,
+            None,
+        )
+        .map_err(|err| {
+            AppError::Capture(format!(
+                "failed to build {} input stream: {err}",
+                S::FORMAT_NAME
+            ))
+        })
 }
 
-/// 处理 `u16` 样本并映射到 `[-1, 1]` 区间，保持不同格式处理一致性。
-fn push_mono_u16(samples: &[u16], channels: usize, sender: &Sender<CaptureChunk>) {
+/// 把交错样本标准化后按 `downmix` 折叠为单声道发送；立体声模式下同时拆出前两个声道作为左右声道。
+fn push_mono<S: IntoF32Sample>(
+    samples: &[S],
+    channels: usize,
+    stereo: bool,
+    downmix: Downmix,
+    sender: &Sender<CaptureChunk>,
+) {
     if channels == 0 || samples.is_empty() {
         return;
     }
 
-    let mut mono = Vec::with_capacity(samples.len() / channels + 1);
+    let frame_count = samples.len() / channels + 1;
+    let mut mono = Vec::with_capacity(frame_count);
+    // 关键行：单声道设备没有独立的左右声道，立体声模式下也只发送单声道。
+    let mut split = (stereo && channels >= 2).then(|| {
+        (
+            Vec::with_capacity(frame_count),
+            Vec::with_capacity(frame_count),
+        )
+    });
+    let mut clipped = 0u32;
+    let mut normalized = Vec::with_capacity(channels);
     for frame in samples.chunks(channels) {
-        let sum = frame
-            .iter()
-            .map(|sample| (*sample as f32 / u16::MAX as f32) * 2.0 - 1.0)
-            .sum::<f32>();
-        mono.push(sum / frame.len() as f32);
+        // 关键行：在折叠前逐声道统计削波，平均后单个声道的削波会被其他声道掩盖。
+        normalized.clear();
+        normalized.extend(
+            frame
+                .iter()
+                .map(|sample| sample.into_f32())
+                .inspect(|sample| {
+                    if sample.abs() >= CLIP_THRESHOLD {
+                        clipped += 1;
+                    }
+                }),
+        );
+        mono.push(downmix.reduce(&normalized));
+        if let Some((left, right)) = split.as_mut() {
+            let first = frame[0].into_f32();
+            left.push(first);
+            right.push(frame.get(1).map_or(first, |sample| sample.into_f32()));
+        }
     }
 
     let _ = sender.send(CaptureChunk {
         timestamp_ms: now_timestamp_ms(),
         samples: mono,
+        stereo: split,
+        clipped,
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{requested_output_name, validate_stream_config, IntoF32Sample};
+    use crate::error::AppError;
+    use cpal::{SampleFormat, SampleRate, SupportedBufferSize, SupportedStreamConfig};
+
+    fn assert_full_scale(name: &str, min: f32, zero: f32, max: f32) {
+        assert_eq!(min, -1.0, "{name} minimum");
+        assert_eq!(zero, 0.0, "{name} zero level");
+        assert!(
+            (max - 1.0).abs() < 1e-4 && max <= 1.0,
+            "{name} maximum: {max}"
+        );
+    }
+
+    #[test]
+    fn integer_formats_share_one_full_scale() {
+        assert_full_scale(
+            "i16",
+            i16::MIN.into_f32(),
+            0i16.into_f32(),
+            i16::MAX.into_f32(),
+        );
+        assert_full_scale(
+            "i32",
+            i32::MIN.into_f32(),
+            0i32.into_f32(),
+            i32::MAX.into_f32(),
+        );
+        assert_full_scale(
+            "u16",
+            u16::MIN.into_f32(),
+            32_768u16.into_f32(),
+            u16::MAX.into_f32(),
+        );
+        assert_full_scale(
+            "u8",
+            u8::MIN.into_f32(),
+            128u8.into_f32(),
+            u8::MAX.into_f32(),
+        );
+    }
+
+    #[test]
+    fn float_formats_pass_through() {
+        assert_eq!((-1.0f32).into_f32(), -1.0);
+        assert_eq!(1.0f32.into_f32(), 1.0);
+        assert_eq!((-1.0f64).into_f32(), -1.0);
+        assert_eq!(0.5f64.into_f32(), 0.5);
+    }
+
+    fn stream_config(channels: u16, sample_rate: u32) -> SupportedStreamConfig {
+        SupportedStreamConfig::new(
+            channels,
+            SampleRate(sample_rate),
+            SupportedBufferSize::Unknown,
+            SampleFormat::F32,
+        )
+    }
+
+    #[test]
+    fn zero_channel_or_zero_rate_config_is_rejected() {
+        assert!(matches!(
+            validate_stream_config(&stream_config(0, 48_000)),
+            Err(AppError::Capture(_))
+        ));
+        assert!(matches!(
+            validate_stream_config(&stream_config(2, 0)),
+            Err(AppError::Capture(_))
+        ));
+        assert!(validate_stream_config(&stream_config(2, 48_000)).is_ok());
+    }
+
+    #[test]
+    fn test_tone_targets_the_configured_output() {
+        // 关键行：设置了输出设备时按名称定位该设备，延迟探测的测试音播放到这里。
+        assert_eq!(
+            requested_output_name("output:Studio Monitors (USB)"),
+            Some("Studio Monitors (USB)")
+        );
+        assert_eq!(requested_output_name(" output:Speakers "), Some("Speakers"));
+
+        // 未设置、名称为空或不是输出设备标识时回退默认输出。
+        assert_eq!(requested_output_name(""), None);
+        assert_eq!(requested_output_name("output:"), None);
+        assert_eq!(requested_output_name("input:Microphone"), None);
+    }
+}
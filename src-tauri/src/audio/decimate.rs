@@ -0,0 +1,123 @@
+use std::f32::consts::PI;
+
+/// 整数倍抽取器：先做二阶低通抗混叠，再每 `factor` 个样本保留一个。
+///
+/// 滤波器状态与抽取相位跨数据块保留，保证块边界处不产生毛刺。
+pub struct Decimator {
+    factor: usize,
+    phase: usize,
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Decimator {
+    /// 根据设备采样率与分析采样率上限计算抽取倍数，并初始化抗混叠滤波器。
+    pub fn new(device_rate: u32, max_rate: u32) -> Self {
+        let factor = decimation_factor(device_rate, max_rate);
+        let mut decimator = Self {
+            factor,
+            phase: 0,
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        };
+
+        if factor > 1 {
+            // 关键行：截止频率取输出奈奎斯特频率的 80%，给滤波器过渡带留余量。
+            let output_rate = device_rate as f32 / factor as f32;
+            decimator.set_lowpass(output_rate * 0.5 * 0.8, device_rate as f32);
+        }
+        decimator
+    }
+
+    /// 当前抽取倍数，1 表示直通。
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// 处理一个数据块并把抽取后的样本追加到 `output`。
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.factor <= 1 {
+            output.extend_from_slice(input);
+            return;
+        }
+
+        output.reserve(input.len() / self.factor + 1);
+        for sample in input.iter().copied() {
+            let filtered = self.b0 * sample + self.b1 * self.x1 + self.b2 * self.x2
+                - self.a1 * self.y1
+                - self.a2 * self.y2;
+            self.x2 = self.x1;
+            self.x1 = sample;
+            self.y2 = self.y1;
+            self.y1 = filtered;
+
+            if self.phase == 0 {
+                output.push(filtered);
+            }
+            self.phase = (self.phase + 1) % self.factor;
+        }
+    }
+
+    /// RBJ 二阶低通（Q = 1/√2，Butterworth 响应）系数。
+    fn set_lowpass(&mut self, cutoff_hz: f32, sample_rate: f32) {
+        let omega = 2.0 * PI * (cutoff_hz / sample_rate).clamp(0.0001, 0.49);
+        let alpha = omega.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let cos_omega = omega.cos();
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 - cos_omega) / 2.0 / a0;
+        self.b1 = (1.0 - cos_omega) / a0;
+        self.b2 = self.b0;
+        self.a1 = -2.0 * cos_omega / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+}
+
+/// 计算使输出采样率不超过上限的最小整数抽取倍数。
+pub fn decimation_factor(device_rate: u32, max_rate: u32) -> usize {
+    if max_rate == 0 || device_rate <= max_rate {
+        return 1;
+    }
+    device_rate.div_ceil(max_rate) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_rates_are_decimated_to_at_most_the_cap() {
+        for max_rate in [48_000, 44_100, 32_000] {
+            let factor = decimation_factor(192_000, max_rate);
+            assert!(factor > 1);
+            assert!(192_000 / factor as u32 <= max_rate, "cap {max_rate}");
+        }
+        assert_eq!(decimation_factor(48_000, 96_000), 1);
+        assert_eq!(decimation_factor(192_000, 0), 1);
+    }
+
+    #[test]
+    fn decimator_keeps_one_sample_per_factor() {
+        let mut decimator = Decimator::new(192_000, 48_000);
+        let mut output = Vec::new();
+        decimator.process(&[1.0; 1000], &mut output);
+        decimator.process(&[1.0; 1000], &mut output);
+        assert_eq!(output.len(), 2000 / decimator.factor());
+        // 关键行：抗混叠低通的直流增益为 1，稳定后的直流电平不变。
+        assert!((output[output.len() - 1] - 1.0).abs() < 1e-3);
+    }
+}
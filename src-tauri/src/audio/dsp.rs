@@ -1,72 +1,759 @@
+use crate::audio::fft::Fft;
+use serde::Serialize;
+use std::collections::VecDeque;
 use std::f32::consts::PI;
 
-/// 频谱分析结果，会被量化后发送给前端渲染层。
+/// 频段自适应基线的初始值，分析器创建与重置自适应状态时共用。
+const INITIAL_BAND_BASELINE: f32 = 0.02;
+/// 量化频段的满幅值，`u16` 帧格式下频段取值为 0..=1023。
+pub const BIN_FULL_SCALE: f32 = 1023.0;
+/// 邻域扩散的默认中心权重（两侧各 0.18），沿用原先固定的扩散强度。
+pub const DEFAULT_DIFFUSION: f32 = 0.64;
+/// 默认分析频率范围：人耳可闻的 20 Hz..20 kHz。
+pub const DEFAULT_MIN_HZ: f32 = 20.0;
+pub const DEFAULT_MAX_HZ: f32 = 20_000.0;
+/// 频谱倾斜的参考频率：该频率处增益为 0 dB，高于它按倾斜量提升、低于它衰减（倾斜为正时）。
+const TILT_REFERENCE_HZ: f32 = 1_000.0;
+/// 窗口 RMS 低于该值视为静音，频谱质心直接取 0，避免底噪决定“音色亮度”。
+const CENTROID_SILENCE_RMS: f32 = 1e-4;
+/// 音高检测的频率范围（Hz），覆盖低音贝斯到人声与多数旋律乐器的基频。
+const PITCH_MIN_HZ: f32 = 50.0;
+const PITCH_MAX_HZ: f32 = 1_000.0;
+/// 归一化自相关峰值低于该值视为没有稳定音高。
+const PITCH_MIN_CONFIDENCE: f32 = 0.8;
+/// 选峰阈值：取第一个不低于最高峰该比例的峰，避免把二倍周期误判为基频（低八度错误）。
+const PITCH_PEAK_RATIO: f32 = 0.9;
+/// 色度折叠的频率范围（Hz）：更低处 FFT 分辨率不足以区分半音，更高处多为泛音与噪声。
+const CHROMA_MIN_HZ: f32 = 55.0;
+const CHROMA_MAX_HZ: f32 = 5_000.0;
+/// 色度音级以 A4 = 440 Hz 定音，A 在以 C 开头的音级序列中的下标。
+const CHROMA_REFERENCE_HZ: f32 = 440.0;
+const CHROMA_REFERENCE_CLASS: i32 = 9;
+/// 噪声门关闭时频段从满幅渐降到零所用的时长（毫秒），避免硬切造成画面突变。
+const NOISE_GATE_RAMP_MS: f32 = 300.0;
+
+/// 频谱分析结果：频段保持 0..1 浮点，发送前再按帧格式决定是否量化。
 #[derive(Debug, Clone)]
 pub struct SpectrumFrame {
-    pub bins: Vec<u16>,
+    pub levels: Vec<f32>,
     pub rms: f32,
     pub peak: f32,
+    /// 频谱质心（幅度加权平均频率）除以奈奎斯特频率，0..1；窗口近乎静音时为 0。
+    pub centroid: f32,
+    /// 自相关估计的基频（Hz），未开启音高检测或置信度不足时为 `None`。
+    pub pitch_hz: Option<f32>,
+    /// 十二音级能量（C 在前，按最强音级归一化到 0..1），未开启色度输出时为 `None`。
+    pub chroma: Option<[f32; 12]>,
+}
+
+impl SpectrumFrame {
+    /// 将频段量化为 0..=1023 的整数数组。
+    pub fn quantized_bins(&self) -> Vec<u16> {
+        quantize_levels(&self.levels)
+    }
+}
+
+/// 分析窗函数：Hann 为默认折中；Hamming 主瓣略窄；Blackman-Harris 旁瓣最低，适合音调成分强的内容；
+/// Flat-top 主瓣最平，幅度读数最准，适合观察瞬态与电平，代价是频率分辨率最差。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowFunction {
+    #[default]
+    Hann,
+    Hamming,
+    BlackmanHarris,
+    FlatTop,
+}
+
+impl WindowFunction {
+    /// 解析设置中的窗函数名称，非法值回退默认 Hann。
+    pub fn from_raw(raw: &str) -> Self {
+        Self::parse(raw).unwrap_or_default()
+    }
+
+    /// 严格解析窗函数名称，未知名称返回 `None`，供命令层校验入参。
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "hann" => Some(Self::Hann),
+            "hamming" => Some(Self::Hamming),
+            "blackmanharris" | "blackman-harris" => Some(Self::BlackmanHarris),
+            "flattop" | "flat-top" => Some(Self::FlatTop),
+            _ => None,
+        }
+    }
+
+    /// 设置与参数描述中使用的名称。
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hann => "hann",
+            Self::Hamming => "hamming",
+            Self::BlackmanHarris => "blackmanHarris",
+            Self::FlatTop => "flatTop",
+        }
+    }
+
+    /// 长度为 `window_size` 的对称窗系数，分析器在窗函数变化时预计算一次。
+    fn coefficients(self, window_size: usize) -> Vec<f32> {
+        let n = window_size.max(2) as f32;
+        (0..window_size)
+            .map(|i| self.coefficient(i as f32 / (n - 1.0)))
+            .collect()
+    }
+
+    /// 相干增益（窗系数均值），用于把加窗后的幅度还原到满幅刻度。
+    fn coherent_gain(self) -> f32 {
+        match self {
+            Self::Hann => 0.5,
+            Self::Hamming => 0.54,
+            Self::BlackmanHarris => 0.35875,
+            Self::FlatTop => 0.215_578_95,
+        }
+    }
+
+    /// 归一化位置 `phase`（0..=1）处的窗系数。
+    fn coefficient(self, phase: f32) -> f32 {
+        let angle = 2.0 * PI * phase;
+        match self {
+            Self::Hann => 0.5 - 0.5 * angle.cos(),
+            Self::Hamming => 0.54 - 0.46 * angle.cos(),
+            // 关键行：四项 Blackman-Harris，最高旁瓣约 -92 dB，代价是主瓣约为 Hann 的两倍宽。
+            Self::BlackmanHarris => {
+                0.35875 - 0.48829 * angle.cos() + 0.14128 * (2.0 * angle).cos()
+                    - 0.01168 * (3.0 * angle).cos()
+            }
+            // 关键行：五项 flat-top（SRS 系数），主瓣顶部平坦，频点偏移时幅度误差小于 0.01 dB。
+            Self::FlatTop => {
+                0.215_578_95 - 0.416_631_58 * angle.cos() + 0.277_263_16 * (2.0 * angle).cos()
+                    - 0.083_578_95 * (3.0 * angle).cos()
+                    + 0.006_947_368 * (4.0 * angle).cos()
+            }
+        }
+    }
+}
+
+/// 频段幅度刻度：`Adaptive` 为 log 压缩加频段基线自适应均衡（默认）；
+/// `Decibel` 为真实 dBFS，`floor_db`..0 dB 线性映射到 0..1，不做均衡与全局能量注入。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ScaleMode {
+    #[default]
+    Adaptive,
+    Decibel {
+        floor_db: f32,
+    },
+}
+
+impl ScaleMode {
+    /// 解析设置中的刻度名称，非法值回退自适应。
+    pub fn from_raw(raw: &str, floor_db: f32) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "decibel" | "db" => Self::Decibel { floor_db },
+            _ => Self::Adaptive,
+        }
+    }
+
+    /// 设置与参数描述中使用的名称。
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Adaptive => "adaptive",
+            Self::Decibel { .. } => "decibel",
+        }
+    }
+}
+
+/// 频率计权曲线：按频段中心频率乘以增益，使频段高度更接近人耳感知的响度；
+/// 分贝刻度下作用于压缩前的幅度，自适应刻度下作用于白化后的电平。
+/// A 计权明显压低低频与极高频，C 计权只在两端轻微衰减，适合响亮内容；默认不计权。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum Weighting {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "aWeighting")]
+    A,
+    #[serde(rename = "cWeighting")]
+    C,
+}
+
+impl Weighting {
+    /// 解析设置中的计权名称，非法值回退为不计权。
+    pub fn from_raw(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "aweighting" | "a-weighting" | "a" => Self::A,
+            "cweighting" | "c-weighting" | "c" => Self::C,
+            _ => Self::None,
+        }
+    }
+
+    /// `frequency_hz` 处的线性幅度倍数；不计权时恒为 1。
+    fn gain(self, frequency_hz: f32) -> f32 {
+        match self {
+            Self::None => 1.0,
+            Self::A => a_weighting_gain(frequency_hz),
+            Self::C => c_weighting_gain(frequency_hz),
+        }
+    }
+}
+
+/// 频段到频率的映射方式：`MixedLogLinear` 为 0.7 对数 + 0.3 线性的经验混合（默认）；
+/// `Mel` 在 0..奈奎斯特的 Mel 区间上等分，最接近人耳音高感知；`Linear` 与 `Logarithmic` 为纯线性、纯对数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FrequencyScale {
+    #[default]
+    MixedLogLinear,
+    Mel,
+    Linear,
+    Logarithmic,
+}
+
+impl FrequencyScale {
+    /// 解析设置中的映射名称，非法值回退默认混合映射。
+    pub fn from_raw(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "mel" => Self::Mel,
+            "linear" => Self::Linear,
+            "logarithmic" | "log" => Self::Logarithmic,
+            _ => Self::MixedLogLinear,
+        }
+    }
+
+    /// 第 `bin_index` 个频段映射到的频点位置 k（可为小数，由调用方在幅度谱上插值），
+    /// 落在 `bounds`（由 [`FrequencyRange::k_bounds`] 换算）之内。
+    ///
+    /// 不取整：对数与 Mel 映射在低频端相邻频段的 k 相差不到一个频点，取整会让多根柱子读同一个频点。
+    fn mapped_bin(
+        self,
+        bin_index: usize,
+        bin_count: usize,
+        bounds: (f32, f32),
+        hz_per_k: f32,
+    ) -> f32 {
+        let (low_k, high_k) = bounds;
+        if bin_count <= 1 {
+            return low_k;
+        }
+
+        let ratio = bin_index as f32 / (bin_count - 1) as f32;
+        let span = high_k - low_k;
+        let k = match self {
+            Self::MixedLogLinear => {
+                return mixed_mapped_frequency_bin(bin_index, bin_count, low_k, high_k)
+            }
+            Self::Linear => low_k + ratio * span,
+            Self::Logarithmic => low_k * (high_k / low_k).powf(ratio),
+            Self::Mel => {
+                // 关键行：在 Mel 域等分后换回 Hz，再按每个频点的带宽换算为频点序号。
+                let low_mel = hz_to_mel(low_k * hz_per_k);
+                let high_mel = hz_to_mel(high_k * hz_per_k);
+                mel_to_hz(low_mel + ratio * (high_mel - low_mel)) / hz_per_k.max(f32::EPSILON)
+            }
+        };
+        k.clamp(low_k, high_k)
+    }
+}
+
+/// 参与频段映射的频率范围（Hz）：全部频段的中心频率只在该范围内分布（下限不低于第 1 个 FFT 频点）。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyRange {
+    pub min_hz: f32,
+    pub max_hz: f32,
+}
+
+impl Default for FrequencyRange {
+    fn default() -> Self {
+        Self {
+            min_hz: DEFAULT_MIN_HZ,
+            max_hz: DEFAULT_MAX_HZ,
+        }
+    }
+}
+
+impl FrequencyRange {
+    /// 换算为闭区间频点位置 `(low_k, high_k)`（可为小数），限制在 `1..=max_k + 1`（`max_k + 1` 对应奈奎斯特频率）。
+    ///
+    /// 不取整：窄范围（如 20–250 Hz 只覆盖几个频点）下各频段仍能在频点之间插值出不同的位置。
+    /// 下限不低于第 1 个频点，更低处的幅度受直流泄漏影响，没有可用的低频信息。
+    fn k_bounds(self, max_k: usize, hz_per_k: f32) -> (f32, f32) {
+        let nyquist_k = (max_k + 1) as f32;
+        let hz_per_k = hz_per_k.max(f32::EPSILON);
+        let low_k = (self.min_hz / hz_per_k).clamp(1.0, nyquist_k);
+        let high_k = (self.max_hz / hz_per_k).clamp(low_k, nyquist_k);
+        (low_k, high_k)
+    }
+}
+
+/// 图形均衡最多支持的控制点数，控制点在频段轴上等距分布、按频段线性插值。
+pub const MAX_EQ_BANDS: usize = 32;
+
+/// 可视化图形均衡曲线：逐控制点的幅度倍数，只影响画面，不改变系统音频；定长存储以保持 `DspParams` 可复制。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqCurve {
+    gains: [f32; MAX_EQ_BANDS],
+    len: usize,
+}
+
+impl EqCurve {
+    /// 从设置构建曲线；为空、超过控制点上限、含非有限或负值、或全部为 1（平直）时返回 `None`，即不做均衡。
+    pub fn from_slice(gains: &[f32]) -> Option<Self> {
+        if gains.is_empty()
+            || gains.len() > MAX_EQ_BANDS
+            || gains.iter().any(|gain| !gain.is_finite() || *gain < 0.0)
+            || gains.iter().all(|gain| *gain == 1.0)
+        {
+            return None;
+        }
+
+        let mut fixed = [1.0; MAX_EQ_BANDS];
+        fixed[..gains.len()].copy_from_slice(gains);
+        Some(Self {
+            gains: fixed,
+            len: gains.len(),
+        })
+    }
+
+    /// 按频段数线性插值出逐频段倍数。
+    fn bin_gains(&self, bin_count: usize) -> Vec<f32> {
+        resample_levels(&self.gains[..self.len], bin_count)
+    }
 }
 
 /// 分析参数：平滑和增益直接影响视觉响应速度和幅度。
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DspParams {
-    pub smoothing: f32,
+    /// 频段上升（attack）与回落（release）时保留上一帧的比例，越大越平滑、响应越慢。
+    pub attack: f32,
+    pub release: f32,
     pub gain: f32,
+    /// 全局能量注入中 RMS 与峰值的权重。
+    pub global_motion_rms_weight: f32,
+    pub global_motion_peak_weight: f32,
+    /// 全局能量响应曲线：1.0 为线性，大于 1 压低弱信号，小于 1 抬升弱信号。
+    pub global_motion_gamma: f32,
+    /// 频谱层面的平均帧数：对最近 N 个窗口的幅度谱取线性平均后再映射到频段，1 表示不平均。
+    pub fft_averages: usize,
+    /// 补零倍数：FFT 长度取窗长的该倍数（向上取 2 的幂），窗后补零以细化频点间隔；
+    /// 不增加所需采样数，也不提高真实频率分辨率。1 为不补零。
+    pub zero_pad_factor: usize,
+    pub window_function: WindowFunction,
+    pub scale_mode: ScaleMode,
+    /// 按频段中心频率乘以的频率计权增益；分贝刻度下作用于幅度，自适应刻度下作用于白化后的电平。
+    pub weighting: Weighting,
+    /// 频谱倾斜（dB/倍频程）：按 `tilt * log2(f / 1 kHz)` dB 调整各频段，正值提亮高频、负值压暗；0 为不倾斜。
+    /// 与频率计权合并施加，自适应刻度下作用于白化后的电平，不会被频段基线追平。
+    pub tilt_db_per_octave: f32,
+    pub frequency_scale: FrequencyScale,
+    pub frequency_range: FrequencyRange,
+    /// 峰值保持：每个频段记录最近峰值，超过即跳升，否则按 `peak_decay`（每秒下降的电平）线性回落。
+    pub peak_hold: bool,
+    pub peak_decay: f32,
+    /// 噪声门：RMS 持续低于 `noise_gate_threshold` 超过 `noise_gate_hold_ms` 后频段渐降到零；阈值为 0 表示关闭。
+    pub noise_gate_threshold: f32,
+    pub noise_gate_hold_ms: u64,
+    /// 逐频段噪声门：平均后的原始幅度低于该值的频段置零，先于计权、均衡与基线白化；0 表示关闭。
+    pub bin_noise_gate: f32,
+    /// 图形均衡曲线，分贝刻度下乘到压缩前的频段幅度上，自适应刻度下乘到白化后的电平上；`None` 为平直。
+    pub eq: Option<EqCurve>,
+    /// 每帧额外做一次自相关音高检测，约为 FFT 的数十倍开销，默认关闭。
+    pub pitch_detection: bool,
+    /// 每帧把幅度谱折叠为十二音级色度，默认关闭。
+    pub chroma: bool,
+    /// 自适应刻度下邻域扩散的中心权重，两侧各取 `(1 - diffusion) / 2`；1 为不扩散。
+    pub diffusion: f32,
 }
 
 impl Default for DspParams {
     fn default() -> Self {
         Self {
-            smoothing: 0.58,
+            attack: 0.58,
+            release: 0.58,
             gain: 1.8,
+            global_motion_rms_weight: 0.8,
+            global_motion_peak_weight: 0.6,
+            global_motion_gamma: 1.0,
+            fft_averages: 1,
+            zero_pad_factor: 1,
+            window_function: WindowFunction::Hann,
+            scale_mode: ScaleMode::Adaptive,
+            weighting: Weighting::None,
+            tilt_db_per_octave: 0.0,
+            frequency_scale: FrequencyScale::MixedLogLinear,
+            frequency_range: FrequencyRange::default(),
+            peak_hold: false,
+            peak_decay: 0.8,
+            noise_gate_threshold: 0.0,
+            noise_gate_hold_ms: 500,
+            bin_noise_gate: 0.0,
+            eq: None,
+            pitch_detection: false,
+            chroma: false,
+            diffusion: DEFAULT_DIFFUSION,
         }
     }
 }
 
-/// 频谱分析器：窗口化 + DFT + 频段均衡 + 平滑后处理。
+/// 频谱分析器：窗口化 + FFT + 频段均衡 + 平滑后处理。
 pub struct SpectrumAnalyzer {
     bin_count: usize,
     window_size: usize,
     params: DspParams,
     previous_bins: Vec<f32>,
     band_baseline: Vec<f32>,
+    /// 最近若干窗口的频段幅度，最新的在队尾，长度不超过 `fft_averages`。
+    magnitude_history: VecDeque<Vec<f32>>,
+    fft: Fft,
+    /// 最近一次 FFT 的幅度谱（`0..=fft.size()/2`），跨帧复用避免重复分配。
+    spectrum: Vec<f32>,
+    /// 分析样本的采样率（经分析采样率转换后的有效采样率），Mel 映射与频率计权依赖它换算真实频率。
+    sample_rate: u32,
+    /// 各频段的频率计权与频谱倾斜合成的线性增益，随计权曲线、倾斜量、采样率与频段数重算；
+    /// 不计权且不倾斜时为空。
+    weighting_gains: Vec<f32>,
+    /// 图形均衡插值到各频段的倍数，随曲线与频段数重算；未设置曲线时为空。
+    eq_gains: Vec<f32>,
+    /// 预计算的窗系数，窗长固定，仅在窗函数变化时重算。
+    window_coefficients: Vec<f32>,
+    /// 各频段的峰值保持电平，关闭峰值保持时为空。
+    peak_hold: Vec<f32>,
+    /// 相邻两次分析的间隔（毫秒），由调用方按发帧间隔设置，噪声门据此累计低电平时长与渐降量。
+    frame_interval_ms: u64,
+    /// 噪声门状态：RMS 连续低于阈值的累计时长与当前施加在频段上的增益（1 为完全打开）。
+    gate_below_ms: u64,
+    gate_gain: f32,
 }
 
 impl SpectrumAnalyzer {
     /// 创建分析器并初始化平滑缓存与频段基线。
-    pub fn new(bin_count: usize, window_size: usize, params: DspParams) -> Self {
-        Self {
+    pub fn new(bin_count: usize, window_size: usize, sample_rate: u32, params: DspParams) -> Self {
+        let mut analyzer = Self {
             bin_count,
             window_size,
             params,
             previous_bins: vec![0.0; bin_count],
-            band_baseline: vec![0.02; bin_count],
+            band_baseline: vec![INITIAL_BAND_BASELINE; bin_count],
+            magnitude_history: VecDeque::new(),
+            fft: Fft::new(window_size * params.zero_pad_factor.max(1)),
+            spectrum: Vec::new(),
+            sample_rate,
+            weighting_gains: Vec::new(),
+            eq_gains: Vec::new(),
+            window_coefficients: params.window_function.coefficients(window_size),
+            peak_hold: Vec::new(),
+            frame_interval_ms: 0,
+            gate_below_ms: 0,
+            gate_gain: 1.0,
+        };
+        analyzer.update_weighting_gains();
+        analyzer.update_eq_gains();
+        analyzer
+    }
+
+    /// 清空平滑缓存与频段基线，恢复到刚创建时的自适应状态；分析参数保持不变。
+    pub fn reset_state(&mut self) {
+        self.previous_bins.fill(0.0);
+        self.band_baseline.fill(INITIAL_BAND_BASELINE);
+        self.magnitude_history.clear();
+        self.peak_hold.clear();
+        self.gate_below_ms = 0;
+        self.gate_gain = 1.0;
+    }
+
+    /// 设置相邻两次分析的间隔（毫秒），发帧间隔或 IPC 降载导致节奏变化时更新。
+    pub fn set_frame_interval(&mut self, interval_ms: u64) {
+        self.frame_interval_ms = interval_ms;
+    }
+
+    /// 当前频段数。
+    pub fn bin_count(&self) -> usize {
+        self.bin_count
+    }
+
+    /// 运行时调整频段数：平滑缓存与频段基线按新长度线性重采样，柱子不会在切换瞬间归零或闪烁；
+    /// 频谱平均历史的长度无法对应，直接丢弃。
+    pub fn set_bin_count(&mut self, bin_count: usize) {
+        if bin_count == self.bin_count {
+            return;
+        }
+        self.bin_count = bin_count;
+        self.previous_bins = resample_levels(&self.previous_bins, bin_count);
+        self.band_baseline = resample_levels(&self.band_baseline, bin_count);
+        if !self.peak_hold.is_empty() {
+            self.peak_hold = resample_levels(&self.peak_hold, bin_count);
+        }
+        self.magnitude_history.clear();
+        self.update_weighting_gains();
+        self.update_eq_gains();
+    }
+
+    /// 分析采样率转换配置变化时更新有效采样率，并重算各频段的计权增益。
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        if sample_rate == self.sample_rate {
+            return;
         }
+        self.sample_rate = sample_rate;
+        self.update_weighting_gains();
     }
 
-    /// 返回最小样本窗口，调用方据此控制缓冲区长度。
+    fn update_eq_gains(&mut self) {
+        self.eq_gains = self
+            .params
+            .eq
+            .map(|curve| curve.bin_gains(self.bin_count))
+            .unwrap_or_default();
+    }
+
+    fn update_weighting_gains(&mut self) {
+        let weighting = self.params.weighting;
+        let tilt = self.params.tilt_db_per_octave;
+        self.weighting_gains = if weighting == Weighting::None && tilt == 0.0 {
+            Vec::new()
+        } else {
+            self.bin_frequencies(self.sample_rate)
+                .into_iter()
+                .map(|frequency| weighting.gain(frequency) * tilt_gain(frequency, tilt))
+                .collect()
+        };
+    }
+
+    /// 返回最小样本窗口，调用方据此控制缓冲区长度；补零只加长 FFT，不影响该值。
     pub fn required_samples(&self) -> usize {
         self.window_size
     }
 
+    /// 最新一个分析窗口期望的样本数：开启音高检测时加长到能覆盖 `PITCH_MIN_HZ` 两个周期，
+    /// 否则等于窗长。FFT 只取其末尾整窗，多出的历史样本只用于自相关。
+    pub fn latest_samples(&self) -> usize {
+        if self.params.pitch_detection {
+            self.window_size.max(pitch_samples(self.sample_rate))
+        } else {
+            self.window_size
+        }
+    }
+
+    /// 频段映射使用的频点网格长度：窗长乘以补零倍数，频点间隔随补零变细。
+    fn frequency_grid(&self) -> usize {
+        self.window_size * self.params.zero_pad_factor.max(1)
+    }
+
     /// 更新分析参数，供运行时滑块调整立即生效。
     pub fn set_params(&mut self, params: DspParams) {
+        let weighting_changed = params.frequency_scale != self.params.frequency_scale
+            || params.frequency_range != self.params.frequency_range
+            || params.zero_pad_factor != self.params.zero_pad_factor
+            || params.tilt_db_per_octave != self.params.tilt_db_per_octave
+            || params.weighting != self.params.weighting;
+        let eq_changed = params.eq != self.params.eq;
+        if params.window_function != self.params.window_function {
+            self.window_coefficients = params.window_function.coefficients(self.window_size);
+        }
+        if params.zero_pad_factor != self.params.zero_pad_factor {
+            self.fft = Fft::new(self.window_size * params.zero_pad_factor.max(1));
+        }
         self.params = params;
+        if weighting_changed {
+            self.update_weighting_gains();
+        }
+        if eq_changed {
+            self.update_eq_gains();
+        }
+        let keep = params.fft_averages.max(1);
+        while self.magnitude_history.len() > keep {
+            self.magnitude_history.pop_front();
+        }
+    }
+
+    /// 用本帧频段电平更新峰值保持并返回各频段峰值；`elapsed_ms` 为距上次更新的时长，
+    /// 回落量按时长换算，不同发帧频率下下落速度一致。关闭峰值保持时返回 `None` 并清空记录。
+    pub fn update_peak_hold(&mut self, levels: &[f32], elapsed_ms: u64) -> Option<Vec<f32>> {
+        if !self.params.peak_hold {
+            self.peak_hold.clear();
+            return None;
+        }
+        if self.peak_hold.len() != levels.len() {
+            self.peak_hold = levels.to_vec();
+            return Some(self.peak_hold.clone());
+        }
+
+        let drop = self.params.peak_decay.max(0.0) * elapsed_ms as f32 / 1000.0;
+        for (peak, &level) in self.peak_hold.iter_mut().zip(levels) {
+            // 关键行：超过峰值立即跳升，否则线性回落但不低于当前电平，峰值标记始终位于柱顶之上。
+            *peak = (*peak - drop).max(level).clamp(0.0, 1.0);
+        }
+        Some(self.peak_hold.clone())
+    }
+
+    /// 最近一次分析的 FFT 幅度谱（`0..=fft.size()/2`），长度与频段数无关。
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+
+    /// 按当前频段映射计算每个频段的中心频率（Hz），供配色和坐标轴标注使用。
+    pub fn bin_frequencies(&self, sample_rate: u32) -> Vec<f32> {
+        bin_center_frequencies(
+            self.bin_count,
+            self.frequency_grid(),
+            sample_rate,
+            self.params.frequency_scale,
+            self.params.frequency_range,
+        )
     }
 
     /// 对采样窗口做分析并输出量化频谱、RMS、峰值。
     pub fn analyze(&mut self, samples: &[f32]) -> SpectrumFrame {
-        let window = prepare_window(samples, self.window_size);
-        let rms = calculate_rms(&window);
-        let peak = calculate_peak(&window);
+        self.analyze_windows(&[samples])
+    }
+
+    /// 重叠 STFT：对按时间先后排列的多个分析窗口（相邻窗口相隔一个 hop）逐窗做 FFT，
+    /// 频段幅度取各窗平均、峰值取最大、RMS 取能量平均，之后只做一次刻度映射与平滑。
+    ///
+    /// 质心、色度、音高与 [`Self::spectrum`] 取自最后（最新）一个窗口；传入空列表时按单个静音窗口处理。
+    /// 最后一个窗口可以长于窗长（见 [`Self::latest_samples`]），此时音高使用全部样本，其余分析只取末尾整窗。
+    pub fn analyze_windows(&mut self, windows: &[&[f32]]) -> SpectrumFrame {
+        let silent: [&[f32]; 1] = [&[]];
+        let windows = if windows.is_empty() {
+            &silent[..]
+        } else {
+            windows
+        };
+        let grid = self.frequency_grid();
+        let max_k = (grid / 2).saturating_sub(1).max(1);
+        let fft_bins_per_k = self.fft.size() as f32 / grid.max(1) as f32;
+        let hz_per_k = self.sample_rate as f32 / grid.max(1) as f32;
+        let bounds = self.params.frequency_range.k_bounds(max_k, hz_per_k);
+
+        let mut magnitudes = vec![0.0f32; self.bin_count];
+        let mut energy = 0.0f32;
+        let mut peak = 0.0f32;
+        for samples in windows {
+            let window = prepare_window(samples, &self.window_coefficients);
+            energy += calculate_rms(&window).powi(2);
+            peak = peak.max(calculate_peak(&window));
+            // 关键行：整窗只做一次 FFT，各频段从幅度谱取值，代替逐频段 O(n) 的 DFT。
+            // 窗长不足 FFT 长度的部分由 FFT 补零，归一化仍按真实窗长，补零不改变幅值刻度。
+            self.fft
+                .magnitudes(&window, window.len() as f32, &mut self.spectrum);
+            for (index, sum) in magnitudes.iter_mut().enumerate() {
+                let mapped_k =
+                    self.params
+                        .frequency_scale
+                        .mapped_bin(index, self.bin_count, bounds, hz_per_k);
+                *sum += interpolate_spectrum(&self.spectrum, mapped_k * fft_bins_per_k);
+            }
+        }
+        let window_count = windows.len() as f32;
+        magnitudes.iter_mut().for_each(|sum| *sum /= window_count);
+        let rms = (energy / window_count).sqrt();
+
+        // 关键行：自相关用未加窗的原始样本，加窗会压低窗口两端、使长周期的相关值偏小。
+        let latest = windows[windows.len() - 1];
+        let pitch_hz = (self.params.pitch_detection && rms >= CENTROID_SILENCE_RMS)
+            .then(|| {
+                let start = latest
+                    .len()
+                    .saturating_sub(self.window_size.max(pitch_samples(self.sample_rate)));
+                estimate_pitch(&latest[start..], self.sample_rate)
+            })
+            .flatten();
+        let hz_per_fft_bin = hz_per_k / fft_bins_per_k.max(f32::EPSILON);
+        // 关键行：质心取自平均与平滑之前的本窗幅度谱；映射后的频段在低频更密，
+        // 直接加权会把质心拉向低频，因此在频率等间隔的 FFT 幅度谱上计算。
+        let centroid = if rms < CENTROID_SILENCE_RMS {
+            0.0
+        } else {
+            let frequencies = (0..self.spectrum.len())
+                .map(|index| index as f32 * hz_per_fft_bin)
+                .collect::<Vec<_>>();
+            spectral_centroid(&frequencies, &self.spectrum, self.sample_rate as f32 * 0.5)
+        };
+        let chroma = self.params.chroma.then(|| {
+            if rms < CENTROID_SILENCE_RMS {
+                [0.0; 12]
+            } else {
+                chromagram(&self.spectrum, hz_per_fft_bin)
+            }
+        });
+        let mut magnitudes = self.average_magnitudes(magnitudes);
+        // 关键行：先门限再白化，被门限的频段不会被基线均衡重新放大，其基线也随之回落。
+        let gate = self.params.bin_noise_gate;
+        if gate > 0.0 {
+            for magnitude in &mut magnitudes {
+                if *magnitude < gate {
+                    *magnitude = 0.0;
+                }
+            }
+        }
+        // 关键行：分贝刻度下计权与均衡作用于压缩前的幅度，即真实的 dB 偏移；自适应刻度的频段基线
+        // 会在几秒内追平压缩前的固定增益，因此改在白化之后施加（见 `adaptive_levels`）。
+        if matches!(self.params.scale_mode, ScaleMode::Decibel { .. }) {
+            for (index, magnitude) in magnitudes.iter_mut().enumerate() {
+                *magnitude *= self.band_gain(index);
+            }
+        }
+        let spread_bins = match self.params.scale_mode {
+            ScaleMode::Adaptive => self.adaptive_levels(magnitudes, rms, peak),
+            ScaleMode::Decibel { floor_db } => {
+                decibel_levels(&magnitudes, floor_db, self.params.window_function)
+            }
+        };
+        let mut levels = Vec::with_capacity(self.bin_count);
 
-        let max_k = (window.len() / 2).saturating_sub(1).max(1);
+        for (index, value) in spread_bins.into_iter().enumerate() {
+            let previous = self.previous_bins[index];
+            // 关键行：上升用 attack、回落用 release，峰值可以跟得快而衰减保持平滑。
+            let coefficient = if value > previous {
+                self.params.attack
+            } else {
+                self.params.release
+            };
+            let smoothed = previous * coefficient + value * (1.0 - coefficient);
+            self.previous_bins[index] = smoothed;
+            levels.push(smoothed);
+        }
+
+        let gate_gain = self.update_noise_gate(rms);
+        if gate_gain < 1.0 {
+            for level in &mut levels {
+                *level *= gate_gain;
+            }
+        }
+
+        SpectrumFrame {
+            levels,
+            rms,
+            peak,
+            centroid,
+            pitch_hz,
+            chroma,
+        }
+    }
+
+    /// 按本帧 RMS 推进噪声门并返回应施加的频段增益：低于阈值超过保持时长后按时长线性渐降，
+    /// 一旦回到阈值之上立即完全打开，不会吃掉曲目开头。平滑缓存不受门控影响，重新打开时柱子直接回到原高度。
+    fn update_noise_gate(&mut self, rms: f32) -> f32 {
+        if self.params.noise_gate_threshold <= 0.0 || rms >= self.params.noise_gate_threshold {
+            self.gate_below_ms = 0;
+            self.gate_gain = 1.0;
+            return 1.0;
+        }
+
+        self.gate_below_ms = self.gate_below_ms.saturating_add(self.frame_interval_ms);
+        if self.gate_below_ms > self.params.noise_gate_hold_ms {
+            let step = self.frame_interval_ms as f32 / NOISE_GATE_RAMP_MS;
+            self.gate_gain = (self.gate_gain - step).max(0.0);
+        }
+        self.gate_gain
+    }
+
+    /// 第 `index` 个频段的频率计权（含倾斜）与图形均衡合成倍数，均未启用时为 1。
+    fn band_gain(&self, index: usize) -> f32 {
+        self.weighting_gains.get(index).copied().unwrap_or(1.0)
+            * self.eq_gains.get(index).copied().unwrap_or(1.0)
+    }
+
+    /// 自适应刻度：log 压缩、频段基线均衡、计权与图形均衡、全局能量注入与邻域扩散。
+    fn adaptive_levels(&mut self, magnitudes: Vec<f32>, rms: f32, peak: f32) -> Vec<f32> {
         let mut raw_bins = Vec::with_capacity(self.bin_count);
 
-        for index in 0..self.bin_count {
-            let mapped_k = mixed_mapped_frequency_bin(index, self.bin_count, max_k);
-            let magnitude = calculate_dft_magnitude(&window, mapped_k);
+        for (index, magnitude) in magnitudes.into_iter().enumerate() {
             let energy = magnitude * self.params.gain * 180.0;
 
             // 关键行：先 log 压缩，再按频段历史基线做自适应均衡，避免只动某几个频段。
@@ -74,36 +761,275 @@ impl SpectrumAnalyzer {
             let baseline = self.band_baseline[index];
             self.band_baseline[index] = baseline * 0.992 + compressed * 0.008;
             let whitened = (compressed / (self.band_baseline[index] * 1.6 + 0.015)).clamp(0.0, 1.0);
-
-            raw_bins.push(whitened);
+            // 关键行：计权与图形均衡在白化之后按比例缩放，基线只跟随未加增益的电平，增益不会被抵消。
+            raw_bins.push((whitened * self.band_gain(index)).clamp(0.0, 1.0));
         }
 
         // 关键行：注入全局能量，让低活跃频段也保持可见动态，但不覆盖频率结构差异。
-        let global_motion = (rms * 0.8 + peak * 0.6).clamp(0.0, 1.0);
+        let global_motion = global_motion(rms, peak, &self.params);
         for value in &mut raw_bins {
             *value = (*value * 0.84 + global_motion * 0.16).clamp(0.0, 1.0);
         }
 
-        let spread_bins = diffuse_neighbors(&raw_bins);
-        let mut bins = Vec::with_capacity(self.bin_count);
+        diffuse_neighbors(&raw_bins, self.params.diffusion)
+    }
 
-        for (index, value) in spread_bins.into_iter().enumerate() {
-            let smoothed = self.previous_bins[index] * self.params.smoothing
-                + value * (1.0 - self.params.smoothing);
-            self.previous_bins[index] = smoothed;
-            bins.push((smoothed * 1023.0).round() as u16);
+    /// 把本窗口幅度并入历史，返回最近 `fft_averages` 个窗口的逐频段线性平均。
+    fn average_magnitudes(&mut self, magnitudes: Vec<f32>) -> Vec<f32> {
+        let averages = self.params.fft_averages.max(1);
+        if averages == 1 {
+            self.magnitude_history.clear();
+            return magnitudes;
+        }
+
+        if self.magnitude_history.len() >= averages {
+            self.magnitude_history.pop_front();
         }
+        self.magnitude_history.push_back(magnitudes);
 
-        SpectrumFrame { bins, rms, peak }
+        // 关键行：历史未填满时只对已有窗口求平均，避免启动阶段被空窗口拉低。
+        let count = self.magnitude_history.len() as f32;
+        let mut averaged = vec![0.0; self.bin_count];
+        for history in &self.magnitude_history {
+            for (sum, magnitude) in averaged.iter_mut().zip(history) {
+                *sum += magnitude;
+            }
+        }
+        averaged.iter_mut().for_each(|sum| *sum /= count);
+        averaged
     }
 }
 
-/// 对每个频段做邻域扩散，减少“只动局部几根柱子”的割裂感。
-fn diffuse_neighbors(values: &[f32]) -> Vec<f32> {
-    if values.is_empty() {
-        return Vec::new();
+/// 起音检测的滚动阈值窗口长度（帧），60 fps 下约 0.7 秒。
+const BEAT_FLUX_HISTORY: usize = 43;
+/// 阈值窗口至少积累这么多帧后才开始判定，避免启动阶段把首帧能量误判为起音。
+const BEAT_WARMUP_FRAMES: usize = 8;
+/// 灵敏度为 0 时阈值高出均值的标准差倍数，灵敏度越高倍数越小。
+const BEAT_MAX_SIGMA: f32 = 3.0;
+/// 阈值的绝对下限，防止静音底噪的微小波动触发起音。
+const BEAT_MIN_FLUX: f32 = 0.002;
+
+/// 基于谱通量（spectral flux）的起音检测：
+/// 对压缩后的幅度谱逐点取正向差分求均值，与滚动均值 + 若干倍标准差的自适应阈值比较。
+pub struct BeatDetector {
+    previous: Vec<f32>,
+    flux_history: VecDeque<f32>,
+    /// 灵敏度 0..1，越大阈值越低、越容易触发。
+    sensitivity: f32,
+    /// 两次起音之间的最小间隔（毫秒），用于去抖。
+    min_interval_ms: u64,
+    last_onset_ms: Option<u64>,
+}
+
+impl BeatDetector {
+    pub fn new(sensitivity: f32, min_interval_ms: u64) -> Self {
+        Self {
+            previous: Vec::new(),
+            flux_history: VecDeque::with_capacity(BEAT_FLUX_HISTORY),
+            sensitivity: sensitivity.clamp(0.0, 1.0),
+            min_interval_ms,
+            last_onset_ms: None,
+        }
+    }
+
+    /// 运行时调整灵敏度，不影响已积累的阈值窗口。
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity.clamp(0.0, 1.0);
+    }
+
+    /// 清空上一帧谱与阈值窗口，分析器重建或重置后调用，避免新旧频谱之间的差分被误判为起音。
+    pub fn reset(&mut self) {
+        self.previous.clear();
+        self.flux_history.clear();
+        self.last_onset_ms = None;
+    }
+
+    /// 送入一帧幅度谱，检测到起音时返回 0..1 的强度。
+    pub fn process(&mut self, spectrum: &[f32], timestamp_ms: u64) -> Option<f32> {
+        if spectrum.is_empty() {
+            return None;
+        }
+
+        // 关键行：先 log 压缩再差分，让弱音量下的鼓点也能产生可比的通量。
+        let compressed = spectrum
+            .iter()
+            .map(|magnitude| (1.0 + magnitude * 1000.0).ln())
+            .collect::<Vec<_>>();
+        if self.previous.len() != compressed.len() {
+            self.previous = compressed;
+            return None;
+        }
+
+        let flux = compressed
+            .iter()
+            .zip(&self.previous)
+            .map(|(current, previous)| (current - previous).max(0.0))
+            .sum::<f32>()
+            / compressed.len() as f32;
+        self.previous = compressed;
+
+        let threshold = self.threshold();
+        if self.flux_history.len() >= BEAT_FLUX_HISTORY {
+            self.flux_history.pop_front();
+        }
+        self.flux_history.push_back(flux);
+
+        let threshold = threshold?;
+        if flux <= threshold {
+            return None;
+        }
+        if self
+            .last_onset_ms
+            .is_some_and(|last| timestamp_ms.saturating_sub(last) < self.min_interval_ms)
+        {
+            return None;
+        }
+
+        self.last_onset_ms = Some(timestamp_ms);
+        Some(1.0 - threshold / flux)
+    }
+
+    /// 基于之前若干帧通量计算阈值；窗口未预热时返回 `None`。
+    fn threshold(&self) -> Option<f32> {
+        if self.flux_history.len() < BEAT_WARMUP_FRAMES {
+            return None;
+        }
+
+        let count = self.flux_history.len() as f32;
+        let mean = self.flux_history.iter().sum::<f32>() / count;
+        let variance = self
+            .flux_history
+            .iter()
+            .map(|flux| (flux - mean).powi(2))
+            .sum::<f32>()
+            / count;
+        let sigma = BEAT_MAX_SIGMA * (1.0 - self.sensitivity);
+        Some((mean + sigma * variance.sqrt()).max(BEAT_MIN_FLUX))
+    }
+}
+
+/// 直流阻断的截止频率（Hz），远低于最低频段，只去除直流偏置与极低频漂移。
+const DC_BLOCKER_CUTOFF_HZ: f32 = 10.0;
+
+/// 一阶直流阻断（高通）滤波器：`y[n] = x[n] - x[n-1] + R·y[n-1]`。
+///
+/// 只能作用于连续样本流：状态跨数据块保留，不能对分析窗口这类重叠片段重复调用。
+#[derive(Debug, Clone, Copy)]
+pub struct DcBlocker {
+    r: f32,
+    /// 上一个输入样本；首次处理前为 `None`，用首个样本初始化，避免启动时的阶跃。
+    x1: Option<f32>,
+    y1: f32,
+}
+
+impl DcBlocker {
+    /// 按采样率计算极点位置，使截止频率与采样率无关。
+    pub fn new(sample_rate: u32) -> Self {
+        let r = 1.0 - 2.0 * PI * DC_BLOCKER_CUTOFF_HZ / sample_rate.max(1) as f32;
+        Self {
+            r: r.clamp(0.9, 0.9999),
+            x1: None,
+            y1: 0.0,
+        }
+    }
+
+    /// 就地滤除一段连续样本中的直流分量。
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let Some(&first) = samples.first() else {
+            return;
+        };
+        let mut x1 = self.x1.unwrap_or(first);
+        for sample in samples.iter_mut() {
+            let x = *sample;
+            self.y1 = x - x1 + self.r * self.y1;
+            x1 = x;
+            *sample = self.y1;
+        }
+        self.x1 = Some(x1);
+    }
+
+    /// 清空滤波状态，停用后重新启用时调用，避免用过期状态处理新样本。
+    pub fn reset(&mut self) {
+        self.x1 = None;
+        self.y1 = 0.0;
+    }
+}
+
+/// 标准 A 计权（IEC 61672）在 `frequency_hz` 处的增益（dB），1 kHz 处为 0 dB。
+pub fn a_weighting_db(frequency_hz: f32) -> f32 {
+    let f2 = (frequency_hz as f64).powi(2);
+    if f2 <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    let response = 12194.0f64.powi(2) * f2 * f2
+        / ((f2 + 20.6f64.powi(2))
+            * ((f2 + 107.7f64.powi(2)) * (f2 + 737.9f64.powi(2))).sqrt()
+            * (f2 + 12194.0f64.powi(2)));
+    // 关键行：+2.00 dB 为归一化常数，使 1 kHz 处增益为 0 dB。
+    (20.0 * response.log10() + 2.0) as f32
+}
+
+/// 频谱倾斜在 `frequency_hz` 处的线性幅度倍数：`db_per_octave * log2(f / 1 kHz)` dB，1 kHz 处为 1。
+///
+/// 频率下限按 1 Hz 处理，避免 0 Hz 处的对数发散。
+pub fn tilt_gain(frequency_hz: f32, db_per_octave: f32) -> f32 {
+    if db_per_octave == 0.0 {
+        return 1.0;
+    }
+    let octaves = (frequency_hz.max(1.0) / TILT_REFERENCE_HZ).log2();
+    10.0f32.powf(db_per_octave * octaves / 20.0)
+}
+
+/// A 计权的线性幅度倍数；0 Hz 处为 0。
+pub fn a_weighting_gain(frequency_hz: f32) -> f32 {
+    10.0f32.powf(a_weighting_db(frequency_hz) / 20.0)
+}
+
+/// 标准 C 计权（IEC 61672）在 `frequency_hz` 处的增益（dB），1 kHz 处为 0 dB。
+pub fn c_weighting_db(frequency_hz: f32) -> f32 {
+    let f2 = (frequency_hz as f64).powi(2);
+    if f2 <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    let response = 12194.0f64.powi(2) * f2 / ((f2 + 20.6f64.powi(2)) * (f2 + 12194.0f64.powi(2)));
+    // 关键行：+0.06 dB 为归一化常数，使 1 kHz 处增益为 0 dB。
+    (20.0 * response.log10() + 0.06) as f32
+}
+
+/// C 计权的线性幅度倍数；0 Hz 处为 0。
+pub fn c_weighting_gain(frequency_hz: f32) -> f32 {
+    10.0f32.powf(c_weighting_db(frequency_hz) / 20.0)
+}
+
+/// 分贝刻度：幅度换算为 dBFS 后把 `floor_db`..0 dB 线性映射到 0..1，低于下限的归零、高于 0 dB 的截顶。
+fn decibel_levels(magnitudes: &[f32], floor_db: f32, window_function: WindowFunction) -> Vec<f32> {
+    // 关键行：幅度谱已除以窗长，满幅正弦的谱峰为“相干增益 / 2”，以此为 0 dBFS 参考。
+    let full_scale = window_function.coherent_gain() * 0.5;
+    let range = (-floor_db).max(f32::EPSILON);
+    magnitudes
+        .iter()
+        .map(|magnitude| {
+            let db = 20.0 * (magnitude / full_scale).max(1e-12).log10();
+            ((db - floor_db) / range).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// 按可配置权重与响应曲线计算全局能量，结果收敛到 0..1。
+fn global_motion(rms: f32, peak: f32, params: &DspParams) -> f32 {
+    let linear = (rms * params.global_motion_rms_weight + peak * params.global_motion_peak_weight)
+        .clamp(0.0, 1.0);
+    linear.powf(params.global_motion_gamma.max(f32::EPSILON))
+}
+
+/// 对每个频段做邻域扩散，减少“只动局部几根柱子”的割裂感；`center` 为本频段权重，剩余部分左右均分。
+fn diffuse_neighbors(values: &[f32], center: f32) -> Vec<f32> {
+    // 关键行：中心权重为 1 时原样返回，柱子较少的布局可以完全关闭空间模糊。
+    if values.is_empty() || center >= 1.0 {
+        return values.to_vec();
     }
 
+    let side = (1.0 - center) * 0.5;
     let mut output = vec![0.0; values.len()];
     for (index, value) in values.iter().copied().enumerate() {
         let left = if index > 0 { values[index - 1] } else { value };
@@ -113,13 +1039,14 @@ fn diffuse_neighbors(values: &[f32]) -> Vec<f32> {
             value
         };
 
-        output[index] = (value * 0.64 + left * 0.18 + right * 0.18).clamp(0.0, 1.0);
+        output[index] = (value * center + (left + right) * side).clamp(0.0, 1.0);
     }
     output
 }
 
-/// 生成固定窗口样本并应用 Hann 窗，降低频谱泄漏。
-fn prepare_window(samples: &[f32], window_size: usize) -> Vec<f32> {
+/// 取末尾固定长度的样本并乘以预计算的窗系数，降低频谱泄漏；窗长即系数长度。
+fn prepare_window(samples: &[f32], coefficients: &[f32]) -> Vec<f32> {
+    let window_size = coefficients.len();
     let mut output = Vec::with_capacity(window_size);
     if samples.is_empty() {
         output.resize(window_size, 0.0);
@@ -134,18 +1061,133 @@ fn prepare_window(samples: &[f32], window_size: usize) -> Vec<f32> {
     }
     output.extend_from_slice(slice);
 
-    let n = output.len().max(2) as f32;
-    for (i, value) in output.iter_mut().enumerate() {
-        let phase = i as f32 / (n - 1.0);
-        let hann = 0.5 - 0.5 * (2.0 * PI * phase).cos();
-        *value *= hann;
+    for (value, coefficient) in output.iter_mut().zip(coefficients) {
+        *value *= coefficient;
     }
 
     output
 }
 
+/// 频谱质心：以幅度为权重的平均频率，除以 `nyquist_hz` 归一化到 0..1；总幅度为 0 时返回 0。
+pub fn spectral_centroid(frequencies: &[f32], magnitudes: &[f32], nyquist_hz: f32) -> f32 {
+    let (weighted, total) = frequencies.iter().zip(magnitudes).fold(
+        (0.0f32, 0.0f32),
+        |(weighted, total), (&frequency, &magnitude)| {
+            (weighted + frequency * magnitude, total + magnitude)
+        },
+    );
+    if total <= f32::EPSILON || nyquist_hz <= 0.0 {
+        return 0.0;
+    }
+    (weighted / total / nyquist_hz).clamp(0.0, 1.0)
+}
+
+/// 色度图：把等间隔幅度谱（`hz_per_bin` 为相邻两点的频率间隔）中的谱峰按十二平均律折叠到 C..B 十二个音级，
+/// 以能量（幅度平方）累加后按最强音级归一化到 0..1；范围内没有能量时全为 0。
+///
+/// 频点间隔（48kHz/1024 点约 47Hz）在中低频宽于半音，直接按频点中心折叠会把单音分到相邻音级，
+/// 因此只取局部峰值，并用对数幅度的抛物线插值估计峰的真实频率。
+pub fn chromagram(spectrum: &[f32], hz_per_bin: f32) -> [f32; 12] {
+    let mut chroma = [0.0f32; 12];
+    if hz_per_bin <= 0.0 {
+        return chroma;
+    }
+    for index in 1..spectrum.len().saturating_sub(1) {
+        let (left, magnitude, right) = (spectrum[index - 1], spectrum[index], spectrum[index + 1]);
+        if magnitude <= f32::EPSILON || magnitude <= left || magnitude < right {
+            continue;
+        }
+        // 关键行：加窗后主瓣近似高斯，对数幅度上的抛物线顶点即峰的小数频点位置。
+        let (a, b, c) = (
+            left.max(f32::MIN_POSITIVE).ln(),
+            magnitude.ln(),
+            right.max(f32::MIN_POSITIVE).ln(),
+        );
+        let curvature = a - 2.0 * b + c;
+        let offset = if curvature < 0.0 {
+            (0.5 * (a - c) / curvature).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        let frequency = (index as f32 + offset) * hz_per_bin;
+        if !(CHROMA_MIN_HZ..=CHROMA_MAX_HZ).contains(&frequency) {
+            continue;
+        }
+        // 关键行：取最近的半音，相对 A4 的半音数加上 A 的下标后对 12 取模得到音级。
+        let semitone = (12.0 * (frequency / CHROMA_REFERENCE_HZ).log2()).round() as i32;
+        let class = (semitone + CHROMA_REFERENCE_CLASS).rem_euclid(12) as usize;
+        chroma[class] += magnitude * magnitude;
+    }
+
+    let strongest = chroma.iter().copied().fold(0.0f32, f32::max);
+    if strongest > f32::EPSILON {
+        for value in &mut chroma {
+            *value /= strongest;
+        }
+    }
+    chroma
+}
+
+/// 覆盖 `PITCH_MIN_HZ` 两个周期所需的样本数，自相关的最大延迟取到样本数的一半。
+pub fn pitch_samples(sample_rate: u32) -> usize {
+    2 * (sample_rate as f32 / PITCH_MIN_HZ).ceil() as usize
+}
+
+/// 基于归一化平方差函数（NSDF，McLeod 音高法）估计基频：
+/// 在 `PITCH_MIN_HZ..=PITCH_MAX_HZ` 对应的延迟范围内计算归一化自相关，取第一个足够高的峰并做抛物线插值。
+/// 峰值低于 `PITCH_MIN_CONFIDENCE` 时返回 `None`。
+///
+/// 最大延迟受样本数一半限制：样本不足 [`pitch_samples`] 时可检测的最低基频会高于 `PITCH_MIN_HZ`。
+pub fn estimate_pitch(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let sample_rate = sample_rate as f32;
+    let min_lag = (sample_rate / PITCH_MAX_HZ).floor().max(1.0) as usize;
+    let max_lag = ((sample_rate / PITCH_MIN_HZ).ceil() as usize).min(samples.len() / 2);
+    if max_lag <= min_lag + 2 {
+        return None;
+    }
+
+    let nsdf = (0..=max_lag + 1)
+        .map(|lag| {
+            let (correlation, energy) = samples
+                .iter()
+                .zip(&samples[lag..])
+                .fold((0.0f32, 0.0f32), |(correlation, energy), (a, b)| {
+                    (correlation + a * b, energy + a * a + b * b)
+                });
+            if energy > f32::EPSILON {
+                2.0 * correlation / energy
+            } else {
+                0.0
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // 关键行：跳过零延迟附近的主峰，从 NSDF 首次降到 0 以下之后再找峰。
+    let first_negative = nsdf.iter().position(|value| *value < 0.0)?;
+    let peaks = (first_negative.max(min_lag)..=max_lag)
+        .filter(|&lag| nsdf[lag] > nsdf[lag - 1] && nsdf[lag] >= nsdf[lag + 1] && nsdf[lag] > 0.0)
+        .collect::<Vec<_>>();
+    let highest = peaks.iter().map(|&lag| nsdf[lag]).fold(0.0f32, f32::max);
+    if highest < PITCH_MIN_CONFIDENCE {
+        return None;
+    }
+    let lag = peaks
+        .into_iter()
+        .find(|&lag| nsdf[lag] >= highest * PITCH_PEAK_RATIO)?;
+
+    // 抛物线插值得到小数延迟，提升低延迟（高音）处的频率分辨率。
+    let (left, center, right) = (nsdf[lag - 1], nsdf[lag], nsdf[lag + 1]);
+    let denominator = left - 2.0 * center + right;
+    let offset = if denominator.abs() > f32::EPSILON {
+        (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    Some(sample_rate / (lag as f32 + offset))
+}
+
 /// 计算短时均方根，用于前端展示整体能量。
-fn calculate_rms(samples: &[f32]) -> f32 {
+pub fn calculate_rms(samples: &[f32]) -> f32 {
     if samples.is_empty() {
         return 0.0;
     }
@@ -154,7 +1196,7 @@ fn calculate_rms(samples: &[f32]) -> f32 {
 }
 
 /// 计算峰值包络，帮助前端做冲击感响应。
-fn calculate_peak(samples: &[f32]) -> f32 {
+pub fn calculate_peak(samples: &[f32]) -> f32 {
     samples
         .iter()
         .copied()
@@ -163,33 +1205,463 @@ fn calculate_peak(samples: &[f32]) -> f32 {
         .clamp(0.0, 1.0)
 }
 
-/// 对目标频点计算 DFT 幅值，窗口较小时可接受且依赖更少。
-fn calculate_dft_magnitude(samples: &[f32], k: usize) -> f32 {
-    if samples.is_empty() {
+/// 在幅度谱中按小数频点位置线性插值；FFT 补零后窗口频点可能落在两个 FFT 频点之间。
+fn interpolate_spectrum(spectrum: &[f32], position: f32) -> f32 {
+    let Some(last) = spectrum.len().checked_sub(1) else {
         return 0.0;
+    };
+    let low = (position.floor().max(0.0) as usize).min(last);
+    let high = (low + 1).min(last);
+    let t = (position - low as f32).clamp(0.0, 1.0);
+    spectrum[low] + (spectrum[high] - spectrum[low]) * t
+}
+
+/// 计算各频段映射到的 DFT 频点对应的中心频率（Hz），与 `analyze` 的映射保持一致；
+/// `window_size` 为频点网格长度，补零时即窗长乘以补零倍数。
+pub fn bin_center_frequencies(
+    bin_count: usize,
+    window_size: usize,
+    sample_rate: u32,
+    scale: FrequencyScale,
+    range: FrequencyRange,
+) -> Vec<f32> {
+    if window_size == 0 {
+        return vec![0.0; bin_count];
     }
 
-    let n = samples.len() as f32;
-    let mut real = 0.0;
-    let mut imag = 0.0;
+    let max_k = (window_size / 2).saturating_sub(1).max(1);
+    let hz_per_k = sample_rate as f32 / window_size as f32;
+    let bounds = range.k_bounds(max_k, hz_per_k);
+    (0..bin_count)
+        .map(|index| scale.mapped_bin(index, bin_count, bounds, hz_per_k) * hz_per_k)
+        .collect()
+}
+
+/// 将 0..1 浮点频段量化为 0..=1023 的整数数组。
+pub fn quantize_levels(levels: &[f32]) -> Vec<u16> {
+    levels
+        .iter()
+        .map(|level| (level.clamp(0.0, 1.0) * BIN_FULL_SCALE).round() as u16)
+        .collect()
+}
+
+/// 将规范频段数组线性插值到目标数量，首尾频段保持对齐。
+///
+/// 用于按订阅方定制频段数，避免为每个客户端单独运行一个分析器。
+pub fn resample_bins(bins: &[u16], target: usize) -> Vec<u16> {
+    let levels = bins.iter().map(|bin| *bin as f32).collect::<Vec<_>>();
+    resample_levels(&levels, target)
+        .into_iter()
+        .map(|value| value.round() as u16)
+        .collect()
+}
 
-    for (index, sample) in samples.iter().copied().enumerate() {
-        let angle = 2.0 * PI * k as f32 * index as f32 / n;
-        real += sample * angle.cos();
-        imag -= sample * angle.sin();
+/// `resample_bins` 的浮点版本，供 `f32` 帧格式使用。
+pub fn resample_levels(levels: &[f32], target: usize) -> Vec<f32> {
+    if target == 0 {
+        return Vec::new();
+    }
+    if levels.is_empty() {
+        return vec![0.0; target];
+    }
+    if levels.len() == target {
+        return levels.to_vec();
+    }
+    if target == 1 {
+        return vec![levels.iter().sum::<f32>() / levels.len() as f32];
     }
 
-    (real * real + imag * imag).sqrt() / n
+    let last = (levels.len() - 1) as f32;
+    (0..target)
+        .map(|index| {
+            let position = index as f32 * last / (target - 1) as f32;
+            let low = position.floor() as usize;
+            let high = (low + 1).min(levels.len() - 1);
+            let t = position - low as f32;
+            levels[low] + (levels[high] - levels[low]) * t
+        })
+        .collect()
 }
 
-/// 混合“对数映射 + 线性映射”，兼顾低频细节和高频活跃度。
-fn mixed_mapped_frequency_bin(bin_index: usize, bin_count: usize, max_k: usize) -> usize {
+/// Hz 转 Mel（O'Shaughnessy 公式）。
+fn hz_to_mel(frequency_hz: f32) -> f32 {
+    2595.0 * (1.0 + frequency_hz / 700.0).log10()
+}
+
+/// Mel 转 Hz，`hz_to_mel` 的逆运算。
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10.0f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// 混合“对数映射 + 线性映射”，兼顾低频细节和高频活跃度；结果为 `low_k..=high_k` 内的小数频点位置。
+fn mixed_mapped_frequency_bin(bin_index: usize, bin_count: usize, low_k: f32, high_k: f32) -> f32 {
     if bin_count <= 1 {
-        return 1;
+        return low_k;
     }
 
     let ratio = bin_index as f32 / (bin_count - 1) as f32;
     let log_ratio = (1.0 + ratio * 9.0).ln() / 10.0f32.ln();
     let mixed_ratio = log_ratio * 0.7 + ratio * 0.3;
-    (1.0 + mixed_ratio * max_k as f32).round() as usize
+    low_k + mixed_ratio * (high_k - low_k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48_000;
+    const WINDOW: usize = 1024;
+
+    /// 窗中心的单位冲激：加窗后幅度谱在各频点上平坦。
+    fn impulse() -> Vec<f32> {
+        let mut samples = vec![0.0; WINDOW];
+        samples[WINDOW / 2] = 1.0;
+        samples
+    }
+
+    /// 同一输入连续分析足够多帧，让平滑与频段基线收敛后返回最后一帧电平。
+    fn settled_levels(params: DspParams, samples: &[f32]) -> Vec<f32> {
+        let mut analyzer = SpectrumAnalyzer::new(32, WINDOW, RATE, params);
+        let mut levels = Vec::new();
+        for _ in 0..1_500 {
+            levels = analyzer.analyze(samples).levels;
+        }
+        levels
+    }
+
+    fn assert_tilt_raises_highs(scale_mode: ScaleMode) {
+        let flat = DspParams {
+            scale_mode,
+            ..DspParams::default()
+        };
+        let tilted = DspParams {
+            tilt_db_per_octave: 3.0,
+            ..flat
+        };
+        let flat_levels = settled_levels(flat, &impulse());
+        let tilted_levels = settled_levels(tilted, &impulse());
+        let (low, high) = (0, 31);
+        let flat_slope = flat_levels[high] - flat_levels[low];
+        let tilted_slope = tilted_levels[high] - tilted_levels[low];
+        assert!(
+            tilted_slope > flat_slope + 0.2,
+            "{scale_mode:?}: flat {flat_slope}, tilted {tilted_slope}"
+        );
+    }
+
+    #[test]
+    fn positive_tilt_raises_high_bins_in_adaptive_scale() {
+        assert_tilt_raises_highs(ScaleMode::Adaptive);
+    }
+
+    #[test]
+    fn positive_tilt_raises_high_bins_in_decibel_scale() {
+        assert_tilt_raises_highs(ScaleMode::Decibel { floor_db: -90.0 });
+    }
+
+    #[test]
+    fn eq_boost_survives_adaptive_whitening() {
+        let flat = DspParams::default();
+        let mut gains = [1.0; 4];
+        gains[3] = 2.0;
+        let boosted = DspParams {
+            eq: EqCurve::from_slice(&gains),
+            ..flat
+        };
+        let flat_levels = settled_levels(flat, &impulse());
+        let boosted_levels = settled_levels(boosted, &impulse());
+        assert!(
+            boosted_levels[31] > flat_levels[31] + 0.1,
+            "flat {}, boosted {}",
+            flat_levels[31],
+            boosted_levels[31]
+        );
+        assert!((boosted_levels[0] - flat_levels[0]).abs() < 0.05);
+    }
+
+    #[test]
+    fn linear_scale_maps_bins_strictly_increasing() {
+        let frequencies = bin_center_frequencies(
+            256,
+            WINDOW,
+            RATE,
+            FrequencyScale::Linear,
+            FrequencyRange::default(),
+        );
+        assert!(frequencies.windows(2).all(|pair| pair[1] > pair[0]));
+    }
+
+    #[test]
+    fn low_end_bars_do_not_collapse_onto_one_fft_bin() {
+        for scale in [FrequencyScale::Logarithmic, FrequencyScale::Mel] {
+            let frequencies =
+                bin_center_frequencies(64, WINDOW, RATE, scale, FrequencyRange::default());
+            assert!(
+                frequencies[..8].windows(2).all(|pair| pair[1] > pair[0]),
+                "{scale:?}: {:?}",
+                &frequencies[..8]
+            );
+        }
+    }
+
+    #[test]
+    fn narrow_range_concentrates_distinct_bins_within_band() {
+        let range = FrequencyRange {
+            min_hz: 20.0,
+            max_hz: 250.0,
+        };
+        let scales = [
+            FrequencyScale::MixedLogLinear,
+            FrequencyScale::Mel,
+            FrequencyScale::Linear,
+            FrequencyScale::Logarithmic,
+        ];
+        for scale in scales {
+            let frequencies = bin_center_frequencies(64, WINDOW, RATE, scale, range);
+            // 关键行：下限不低于第 1 个频点（48 kHz / 1024 ≈ 46.9 Hz），上限不超过 250 Hz。
+            let first_bin_hz = RATE as f32 / WINDOW as f32;
+            assert!(
+                frequencies
+                    .iter()
+                    .all(|hz| (first_bin_hz - 1e-3..=250.0 + 1e-3).contains(hz)),
+                "{scale:?}: {frequencies:?}"
+            );
+            assert!(
+                frequencies.windows(2).all(|pair| pair[1] > pair[0]),
+                "{scale:?}: {frequencies:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn a_weighting_is_unity_at_1khz_and_attenuates_bass() {
+        assert!((a_weighting_gain(1_000.0) - 1.0).abs() < 0.01);
+        // A 计权 100 Hz 约 -19.1 dB。
+        let bass_db = 20.0 * a_weighting_gain(100.0).log10();
+        assert!((bass_db + 19.1).abs() < 0.2, "100 Hz: {bass_db} dB");
+    }
+
+    #[test]
+    fn c_weighting_is_unity_at_1khz_and_mild_at_extremes() {
+        assert!((c_weighting_gain(1_000.0) - 1.0).abs() < 0.01);
+        // C 计权 31.5 Hz 约 -3.0 dB，远轻于 A 计权的 -39.4 dB。
+        let bass_db = 20.0 * c_weighting_gain(31.5).log10();
+        assert!((bass_db + 3.0).abs() < 0.2, "31.5 Hz: {bass_db} dB");
+        assert_eq!(Weighting::C.gain(1_000.0), c_weighting_gain(1_000.0));
+    }
+
+    fn sine(frequency: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|n| 0.5 * (std::f32::consts::TAU * frequency * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn pitch_of_sine_matches_its_frequency_down_to_the_minimum() {
+        let len = pitch_samples(RATE);
+        for frequency in [PITCH_MIN_HZ + 5.0, 82.4, 220.0, 440.0, 900.0] {
+            let pitch = estimate_pitch(&sine(frequency, len), RATE).expect("pitched sine");
+            assert!(
+                (pitch - frequency).abs() < frequency * 0.01,
+                "{frequency} Hz estimated as {pitch} Hz"
+            );
+        }
+    }
+
+    #[test]
+    fn analyzer_detects_low_pitch_from_latest_samples() {
+        let params = DspParams {
+            pitch_detection: true,
+            ..DspParams::default()
+        };
+        let mut analyzer = SpectrumAnalyzer::new(32, WINDOW, RATE, params);
+        assert!(analyzer.latest_samples() >= pitch_samples(RATE));
+        let pitch = analyzer
+            .analyze(&sine(60.0, analyzer.latest_samples()))
+            .pitch_hz
+            .expect("pitched sine");
+        assert!((pitch - 60.0).abs() < 1.0, "60 Hz estimated as {pitch} Hz");
+    }
+
+    #[test]
+    fn sine_lights_up_its_pitch_class() {
+        let params = DspParams {
+            chroma: true,
+            ..DspParams::default()
+        };
+        // A4、C5、E4 分别对应音级 9、0、4；频点间隔约 47Hz，均宽于这些音附近的半音。
+        for (frequency, expected) in [(440.0, 9), (523.25, 0), (329.63, 4)] {
+            let mut analyzer = SpectrumAnalyzer::new(32, WINDOW, RATE, params);
+            let chroma = analyzer
+                .analyze(&sine(frequency, WINDOW))
+                .chroma
+                .expect("chroma enabled");
+            assert_eq!(chroma[expected], 1.0, "{frequency} Hz: {chroma:?}");
+            for (class, &value) in chroma.iter().enumerate() {
+                assert!(
+                    class == expected || value < 0.5,
+                    "{frequency} Hz: class {class} at {value}"
+                );
+            }
+        }
+    }
+
+    /// 确定性的白噪声（线性同余发生器），每次调用续接上一段。
+    fn white_noise(state: &mut u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|_| {
+                *state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (*state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+            })
+            .collect()
+    }
+
+    /// 连续分析白噪声，返回相邻两帧电平差的均方值（跨频段平均）。
+    fn frame_to_frame_variance(fft_averages: usize) -> f32 {
+        // 关键行：关闭平滑并使用分贝刻度，只留下频谱平均本身对帧间波动的影响。
+        let params = DspParams {
+            attack: 0.0,
+            release: 0.0,
+            fft_averages,
+            scale_mode: ScaleMode::Decibel { floor_db: -90.0 },
+            ..DspParams::default()
+        };
+        let mut analyzer = SpectrumAnalyzer::new(32, WINDOW, RATE, params);
+        let mut state = 1;
+        let mut previous = analyzer.analyze(&white_noise(&mut state, WINDOW)).levels;
+        let (mut sum, mut count) = (0.0, 0);
+        for frame in 0..200 {
+            let levels = analyzer.analyze(&white_noise(&mut state, WINDOW)).levels;
+            if frame >= fft_averages {
+                sum += levels
+                    .iter()
+                    .zip(&previous)
+                    .map(|(current, last)| (current - last).powi(2))
+                    .sum::<f32>();
+                count += levels.len();
+            }
+            previous = levels;
+        }
+        sum / count as f32
+    }
+
+    #[test]
+    fn fft_averages_reduce_frame_to_frame_variance_on_noise() {
+        let single = frame_to_frame_variance(1);
+        let averaged = frame_to_frame_variance(8);
+        assert!(single > 0.0);
+        assert!(
+            averaged < single * 0.5,
+            "single {single}, averaged {averaged}"
+        );
+    }
+
+    /// 落在两个频点正中的正弦（最坏情况泄漏）经指定窗函数后的幅度谱（峰值归一化为 1）。
+    fn off_bin_spectrum(window: WindowFunction) -> Vec<f32> {
+        let cycles = 100.5;
+        let windowed = window
+            .coefficients(WINDOW)
+            .iter()
+            .enumerate()
+            .map(|(n, coefficient)| {
+                coefficient * (std::f32::consts::TAU * cycles * n as f32 / WINDOW as f32).sin()
+            })
+            .collect::<Vec<_>>();
+        let mut spectrum = Vec::new();
+        Fft::new(WINDOW).magnitudes(&windowed, WINDOW as f32, &mut spectrum);
+        let max = spectrum.iter().copied().fold(0.0, f32::max);
+        spectrum.iter().map(|magnitude| magnitude / max).collect()
+    }
+
+    /// 距主峰（第 100、101 频点）超过 6 个频点处的最大泄漏（dB）。
+    fn far_leakage_db(spectrum: &[f32]) -> f32 {
+        let far = spectrum
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| !(94..=107).contains(k))
+            .map(|(_, magnitude)| *magnitude)
+            .fold(0.0, f32::max);
+        20.0 * far.log10()
+    }
+
+    #[test]
+    fn blackman_harris_trades_main_lobe_width_for_lower_leakage() {
+        let hann = off_bin_spectrum(WindowFunction::Hann);
+        let blackman_harris = off_bin_spectrum(WindowFunction::BlackmanHarris);
+
+        // 关键行：主瓣外 Blackman-Harris 的泄漏接近其 -92 dB 旁瓣，比 Hann 低 30 dB 以上。
+        let hann_leakage = far_leakage_db(&hann);
+        let blackman_harris_leakage = far_leakage_db(&blackman_harris);
+        assert!(
+            blackman_harris_leakage < -85.0,
+            "{blackman_harris_leakage} dB"
+        );
+        assert!(
+            blackman_harris_leakage < hann_leakage - 30.0,
+            "hann {hann_leakage} dB, blackman-harris {blackman_harris_leakage} dB"
+        );
+        // 代价是主瓣更宽：紧邻主峰的频点上 Blackman-Harris 保留的能量更多。
+        assert!(blackman_harris[99] > hann[99] * 1.5);
+    }
+
+    #[test]
+    fn reset_state_restores_the_baseline_but_keeps_params() {
+        let params = DspParams {
+            gain: 3.0,
+            window_function: WindowFunction::BlackmanHarris,
+            frequency_scale: FrequencyScale::Linear,
+            ..DspParams::default()
+        };
+        let signal = sine(440.0, WINDOW);
+        let first_frame = SpectrumAnalyzer::new(32, WINDOW, RATE, params)
+            .analyze(&signal)
+            .levels;
+
+        let mut analyzer = SpectrumAnalyzer::new(32, WINDOW, RATE, params);
+        let mut adapted = Vec::new();
+        for _ in 0..500 {
+            adapted = analyzer.analyze(&signal).levels;
+        }
+        assert_ne!(adapted, first_frame, "baseline should have adapted");
+
+        // 关键行：重置后首帧与同参数新建的分析器完全一致，说明基线与平滑已清空而参数未被改回默认。
+        analyzer.reset_state();
+        assert_eq!(analyzer.analyze(&signal).levels, first_frame);
+        let defaults = SpectrumAnalyzer::new(32, WINDOW, RATE, DspParams::default())
+            .analyze(&signal)
+            .levels;
+        assert_ne!(first_frame, defaults);
+    }
+
+    #[test]
+    fn global_motion_follows_weights_and_gamma() {
+        let defaults = DspParams::default();
+        let (rms, peak) = (0.2, 0.5);
+        let base = global_motion(rms, peak, &defaults);
+        assert!((base - (0.2 * 0.8 + 0.5 * 0.6)).abs() < 1e-6);
+
+        let rms_heavy = DspParams {
+            global_motion_rms_weight: 1.6,
+            ..defaults
+        };
+        let peak_free = DspParams {
+            global_motion_peak_weight: 0.0,
+            ..defaults
+        };
+        assert!(global_motion(rms, peak, &rms_heavy) > base);
+        assert!((global_motion(rms, peak, &peak_free) - rms * 0.8).abs() < 1e-6);
+
+        // 关键行：gamma 大于 1 压低中等能量，小于 1 抬升；满幅能量不受 gamma 影响。
+        let steep = DspParams {
+            global_motion_gamma: 2.0,
+            ..defaults
+        };
+        let gentle = DspParams {
+            global_motion_gamma: 0.5,
+            ..defaults
+        };
+        assert!((global_motion(rms, peak, &steep) - base * base).abs() < 1e-6);
+        assert!(global_motion(rms, peak, &gentle) > base);
+        assert_eq!(global_motion(1.0, 1.0, &steep), 1.0);
+    }
 }
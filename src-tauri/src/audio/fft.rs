@@ -0,0 +1,174 @@
+use std::f64::consts::PI;
+
+/// 基 2 迭代 FFT：预计算位反转表与旋转因子，同一长度重复变换时不再做三角函数计算。
+///
+/// 输入长度不是 2 的幂时补零到下一个 2 的幂，调用方需按 `size` 换算频点位置。
+pub struct Fft {
+    size: usize,
+    bit_reverse: Vec<usize>,
+    /// 第 k 项为 `e^{-2πik/size}`，只需前 `size / 2` 项。
+    twiddles: Vec<(f32, f32)>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl Fft {
+    /// 创建不短于 `min_size` 的变换计划。
+    pub fn new(min_size: usize) -> Self {
+        let size = min_size.max(2).next_power_of_two();
+        let bits = size.trailing_zeros();
+        let bit_reverse = (0..size)
+            .map(|index| index.reverse_bits() >> (usize::BITS - bits))
+            .collect();
+        // 关键行：旋转因子用 f64 计算后再转 f32，避免大尺寸下相位累积误差。
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * PI * k as f64 / size as f64;
+                (angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+
+        Self {
+            size,
+            bit_reverse,
+            twiddles,
+            re: vec![0.0; size],
+            im: vec![0.0; size],
+        }
+    }
+
+    /// 变换长度（2 的幂）。
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// 对实数样本做变换，把 `0..=size/2` 各频点的幅值除以 `norm` 后写入 `output`。
+    pub fn magnitudes(&mut self, samples: &[f32], norm: f32, output: &mut Vec<f32>) {
+        let size = self.size;
+        self.im.fill(0.0);
+        for (index, slot) in self.re.iter_mut().enumerate() {
+            *slot = samples.get(index).copied().unwrap_or(0.0);
+        }
+
+        for index in 0..size {
+            let reversed = self.bit_reverse[index];
+            if index < reversed {
+                self.re.swap(index, reversed);
+                self.im.swap(index, reversed);
+            }
+        }
+
+        let mut len = 2;
+        while len <= size {
+            let half = len / 2;
+            let stride = size / len;
+            for start in (0..size).step_by(len) {
+                for k in 0..half {
+                    let (wr, wi) = self.twiddles[k * stride];
+                    let even = start + k;
+                    let odd = even + half;
+                    let tr = self.re[odd] * wr - self.im[odd] * wi;
+                    let ti = self.re[odd] * wi + self.im[odd] * wr;
+                    self.re[odd] = self.re[even] - tr;
+                    self.im[odd] = self.im[even] - ti;
+                    self.re[even] += tr;
+                    self.im[even] += ti;
+                }
+            }
+            len <<= 1;
+        }
+
+        let norm = norm.max(f32::EPSILON);
+        output.clear();
+        output.extend(
+            self.re[..=size / 2]
+                .iter()
+                .zip(&self.im[..=size / 2])
+                .map(|(re, im)| (re * re + im * im).sqrt() / norm),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// 逐频点直接求和的 DFT，作为 FFT 的对照实现。
+    fn naive_dft_magnitude(samples: &[f32], bin: usize, norm: f32) -> f32 {
+        let size = samples.len() as f64;
+        let (re, im) =
+            samples
+                .iter()
+                .enumerate()
+                .fold((0.0f64, 0.0f64), |(re, im), (index, &sample)| {
+                    let angle = -2.0 * PI * bin as f64 * index as f64 / size;
+                    (
+                        re + sample as f64 * angle.cos(),
+                        im + sample as f64 * angle.sin(),
+                    )
+                });
+        (re * re + im * im).sqrt() as f32 / norm
+    }
+
+    fn test_signal(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|index| {
+                let t = index as f32 / len as f32;
+                (2.0 * std::f32::consts::PI * 37.0 * t).sin()
+                    + 0.5 * (2.0 * std::f32::consts::PI * 101.0 * t).cos()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn magnitudes_match_naive_dft() {
+        let samples = test_signal(1024);
+        let mut fft = Fft::new(samples.len());
+        let mut output = Vec::new();
+        fft.magnitudes(&samples, 512.0, &mut output);
+
+        assert_eq!(output.len(), 513);
+        for (bin, &magnitude) in output.iter().enumerate() {
+            let expected = naive_dft_magnitude(&samples, bin, 512.0);
+            assert!(
+                (magnitude - expected).abs() < 1e-3,
+                "bin {bin}: fft {magnitude} vs dft {expected}"
+            );
+        }
+    }
+
+    /// 对比旧实现（每帧 64 个频点各做一次逐样本 DFT）与单次 FFT 的每帧耗时。
+    ///
+    /// 计时受机器负载影响，默认不运行：`cargo test --release fft_frame_is_faster -- --ignored --nocapture`。
+    #[test]
+    #[ignore]
+    fn fft_frame_is_faster_than_per_bin_dft() {
+        const BINS: usize = 64;
+        const FRAMES: u32 = 50;
+        let samples = test_signal(1024);
+        let mut fft = Fft::new(samples.len());
+        let mut output = Vec::new();
+
+        let started = Instant::now();
+        for _ in 0..FRAMES {
+            let mut sum = 0.0;
+            for bin in 0..BINS {
+                sum += naive_dft_magnitude(&samples, bin * 8, 512.0);
+            }
+            std::hint::black_box(sum);
+        }
+        let dft_frame = started.elapsed() / FRAMES;
+
+        let started = Instant::now();
+        for _ in 0..FRAMES {
+            fft.magnitudes(&samples, 512.0, &mut output);
+            std::hint::black_box(&output);
+        }
+        let fft_frame = started.elapsed() / FRAMES;
+
+        println!("per-bin DFT: {dft_frame:?}/frame, FFT: {fft_frame:?}/frame");
+        // 关键行：release 下约快两个数量级，下限放宽到 4 倍以兼容未优化构建。
+        assert!(fft_frame.max(Duration::from_nanos(1)) * 4 < dft_frame);
+    }
+}
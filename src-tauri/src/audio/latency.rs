@@ -0,0 +1,338 @@
+use crate::audio::capture;
+use crate::error::AppError;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{SampleFormat, Stream, StreamConfig};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 探测脉冲长度（样本数）：48kHz 下约 10ms，足够让互相关峰值明显高于背景音乐。
+const CLICK_LEN: usize = 512;
+/// 探测脉冲幅度，避免在正在播放的音乐之上过于刺耳。
+const CLICK_AMPLITUDE: f32 = 0.5;
+/// 输出流启动后先播放的静音时长，等待音频引擎稳定再发出脉冲。
+const PRE_ROLL_MS: u64 = 200;
+/// 脉冲发出后等待回采的最长时长。
+const CAPTURE_WAIT_MS: u64 = 1000;
+/// 归一化互相关低于该值视为未检测到脉冲。
+const MIN_CLICK_CORRELATION: f32 = 0.3;
+
+/// 当前实时采集会话信息，供延迟探测判断是否为输出回环采集。
+#[derive(Debug, Clone)]
+pub struct ProbeCapture {
+    pub device_id: String,
+    pub sample_rate: u32,
+}
+
+/// 探测期间采集到的原始单声道样本，以及每个数据块写入后的样本数与到达时间。
+#[derive(Default)]
+struct ProbeRecording {
+    samples: Vec<f32>,
+    chunk_arrivals: Vec<(usize, Instant)>,
+}
+
+/// 延迟探测共享状态：分析线程登记采集会话并在探测期间转存原始样本，命令层发起测量。
+#[derive(Clone, Default)]
+pub struct LatencyProbeState {
+    capture: Arc<Mutex<Option<ProbeCapture>>>,
+    armed: Arc<AtomicBool>,
+    recording: Arc<Mutex<ProbeRecording>>,
+}
+
+impl LatencyProbeState {
+    /// 登记或清除（`None`）当前实时采集会话。
+    pub fn set_capture(&self, capture: Option<ProbeCapture>) {
+        if let Ok(mut guard) = self.capture.lock() {
+            *guard = capture;
+        }
+    }
+
+    /// 转存一个采集数据块；未处于探测状态时直接返回，不产生锁开销。
+    pub fn feed(&self, samples: &[f32]) {
+        if !self.armed.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Ok(mut guard) = self.recording.lock() {
+            guard.samples.extend_from_slice(samples);
+            let end = guard.samples.len();
+            guard.chunk_arrivals.push((end, Instant::now()));
+        }
+    }
+
+    /// 当前登记的实时采集会话，模拟数据阶段为 `None`。
+    pub fn capture(&self) -> Option<ProbeCapture> {
+        self.capture.lock().ok().and_then(|guard| guard.clone())
+    }
+
+    /// 开始转存；已有测量进行中时返回 `false`，且不触碰进行中的录制内容。
+    fn arm(&self) -> bool {
+        // 关键行：先抢占测量资格再清空录制，并发的第二次调用不会抹掉第一次测量的缓冲。
+        if self
+            .armed
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        if let Ok(mut guard) = self.recording.lock() {
+            *guard = ProbeRecording::default();
+        }
+        true
+    }
+
+    /// 停止转存并取出录制内容。
+    fn disarm(&self) -> ProbeRecording {
+        self.armed.store(false, Ordering::Release);
+        self.recording
+            .lock()
+            .map(|mut guard| std::mem::take(&mut *guard))
+            .unwrap_or_default()
+    }
+}
+
+/// 实测采集延迟：向输出设备（`output_device_id` 为空时用默认输出）播放一个探测脉冲，
+/// 在回环采集信号中定位它，返回从脉冲写入输出缓冲到其所在采集数据块到达分析线程的毫秒数。
+///
+/// 会阻塞约 1.2 秒，命令层需在后台线程调用。
+pub fn measure_latency(probe: &LatencyProbeState, output_device_id: &str) -> Result<f64, AppError> {
+    let capture = probe
+        .capture()
+        .ok_or_else(|| AppError::Capture("realtime capture is not active".to_string()))?;
+    let captured_output = capture::selectable_device_id(&capture.device_id);
+    if !captured_output.starts_with("output:") {
+        return Err(AppError::Unsupported(format!(
+            "latency probe requires output loopback capture, current device is {}",
+            capture.device_id
+        )));
+    }
+
+    let (device, resolved_id) = capture::resolve_output_device(output_device_id)?;
+    // 关键行：脉冲必须播放到正在被回环采集的设备上，否则永远检测不到。
+    if resolved_id != captured_output {
+        return Err(AppError::Unsupported(format!(
+            "probe output {resolved_id} is not the captured device {}",
+            capture.device_id
+        )));
+    }
+
+    let template = click_template(CLICK_LEN);
+    let click_written_at = Arc::new(Mutex::new(None::<Instant>));
+    let stream = build_click_stream(
+        &device,
+        capture.sample_rate,
+        template.clone(),
+        click_written_at.clone(),
+    )?;
+
+    if !probe.arm() {
+        return Err(AppError::InvalidArgument(
+            "latency measurement already in progress".to_string(),
+        ));
+    }
+    if let Err(err) = stream.play() {
+        probe.disarm();
+        return Err(AppError::Capture(format!(
+            "failed to play latency probe stream: {err}"
+        )));
+    }
+
+    thread::sleep(Duration::from_millis(PRE_ROLL_MS + CAPTURE_WAIT_MS));
+    drop(stream);
+    let recording = probe.disarm();
+
+    let click_at = click_written_at
+        .lock()
+        .ok()
+        .and_then(|guard| *guard)
+        .ok_or_else(|| {
+            AppError::Capture("output stream never played the probe click".to_string())
+        })?;
+    let click_index = detect_click(&recording.samples, &template).ok_or_else(|| {
+        AppError::Capture("probe click not detected in loopback signal (output muted?)".to_string())
+    })?;
+    let arrived_at = recording
+        .chunk_arrivals
+        .iter()
+        .find(|(end, _)| *end > click_index)
+        .map(|(_, arrived_at)| *arrived_at)
+        .ok_or_else(|| AppError::Internal("probe click arrival time missing".to_string()))?;
+
+    Ok(arrived_at.saturating_duration_since(click_at).as_secs_f64() * 1000.0)
+}
+
+/// 生成确定性的 ±1 伪随机探测脉冲，自相关尖锐，便于在背景音频中定位。
+pub fn click_template(len: usize) -> Vec<f32> {
+    let mut state = 0x1d87_2b41u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            if state & 1 == 0 {
+                CLICK_AMPLITUDE
+            } else {
+                -CLICK_AMPLITUDE
+            }
+        })
+        .collect()
+}
+
+/// 用归一化互相关在信号中定位探测脉冲起点；相关度不足时返回 `None`。
+pub fn detect_click(signal: &[f32], template: &[f32]) -> Option<usize> {
+    if template.is_empty() || signal.len() < template.len() {
+        return None;
+    }
+
+    let template_energy = template.iter().map(|value| value * value).sum::<f32>() as f64;
+    // 关键行：滑动窗口能量用 f64 累计，避免长信号上增量更新的舍入漂移。
+    let mut window_energy = signal[..template.len()]
+        .iter()
+        .map(|value| (*value as f64).powi(2))
+        .sum::<f64>();
+    let mut best: Option<(usize, f32)> = None;
+
+    for start in 0..=signal.len() - template.len() {
+        if start > 0 {
+            let leaving = signal[start - 1] as f64;
+            let entering = signal[start + template.len() - 1] as f64;
+            window_energy = (window_energy - leaving * leaving + entering * entering).max(0.0);
+        }
+        if window_energy <= f64::EPSILON {
+            continue;
+        }
+
+        let dot = signal[start..start + template.len()]
+            .iter()
+            .zip(template)
+            .map(|(sample, reference)| sample * reference)
+            .sum::<f32>();
+        let score = (dot as f64 / (template_energy * window_energy).sqrt()) as f32;
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((start, score));
+        }
+    }
+
+    best.filter(|(_, score)| *score >= MIN_CLICK_CORRELATION)
+        .map(|(start, _)| start)
+}
+
+/// 在指定输出设备上创建探测流：先播放静音，再写入一次脉冲，并记录脉冲写入时刻。
+fn build_click_stream(
+    device: &cpal::Device,
+    capture_rate: u32,
+    template: Vec<f32>,
+    click_written_at: Arc<Mutex<Option<Instant>>>,
+) -> Result<Stream, AppError> {
+    let supported = device
+        .default_output_config()
+        .map_err(|err| AppError::Capture(format!("failed to read output config: {err}")))?;
+    if supported.sample_rate().0 != capture_rate {
+        return Err(AppError::Unsupported(format!(
+            "output rate {} Hz differs from capture rate {capture_rate} Hz",
+            supported.sample_rate().0
+        )));
+    }
+
+    let sample_format = supported.sample_format();
+    let config: StreamConfig = supported.into();
+    let channels = config.channels as usize;
+    if channels == 0 {
+        return Err(AppError::Capture(
+            "output device reports zero channels".to_string(),
+        ));
+    }
+
+    let mut playback = ClickPlayback {
+        template,
+        click_start: (capture_rate as u64 * PRE_ROLL_MS / 1000) as usize,
+        sample_rate: capture_rate,
+        frames_written: 0,
+        click_written_at,
+    };
+    let error_callback = |error| eprintln!("latency probe stream error: {error}");
+
+    let stream = match sample_format {
+        SampleFormat::F32 => device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _| playback.fill(data, channels),
+            error_callback,
+            None,
+        ),
+        _ => {
+            return Err(AppError::Unsupported(format!(
+                "unsupported output sample format for latency probe: {sample_format:?}"
+            )))
+        }
+    };
+    stream.map_err(|err| AppError::Capture(format!("failed to build latency probe stream: {err}")))
+}
+
+/// 探测流回调状态：按已写入帧数决定输出静音还是脉冲样本。
+struct ClickPlayback {
+    template: Vec<f32>,
+    click_start: usize,
+    sample_rate: u32,
+    frames_written: usize,
+    click_written_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ClickPlayback {
+    /// 填充一次输出回调缓冲，所有声道写入相同样本。
+    fn fill(&mut self, data: &mut [f32], channels: usize) {
+        for (frame_offset, frame) in data.chunks_mut(channels).enumerate() {
+            let index = self.frames_written + frame_offset;
+            if index == self.click_start {
+                if let Ok(mut guard) = self.click_written_at.lock() {
+                    // 关键行：按帧偏移折算脉冲在本次回调中的写入时刻。
+                    let offset =
+                        Duration::from_secs_f64(frame_offset as f64 / self.sample_rate as f64);
+                    *guard = Some(Instant::now() + offset);
+                }
+            }
+            let sample = index
+                .checked_sub(self.click_start)
+                .and_then(|position| self.template.get(position).copied())
+                .unwrap_or(0.0);
+            frame.fill(sample);
+        }
+        self.frames_written += data.len() / channels;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 确定性的背景噪声，模拟探测时仍在播放的音频。
+    fn background_noise(len: usize, amplitude: f32) -> Vec<f32> {
+        let mut state = 7u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    #[test]
+    fn delayed_click_is_found_under_noise() {
+        let template = click_template(CLICK_LEN);
+        let delay = 1_234;
+        let mut signal = background_noise(4_800, 0.3);
+        // 关键行：回采的脉冲经过衰减并叠加在背景噪声上，仍应定位到准确的起点。
+        for (sample, click) in signal[delay..].iter_mut().zip(&template) {
+            *sample += click * 0.6;
+        }
+
+        assert_eq!(detect_click(&signal, &template), Some(delay));
+    }
+
+    #[test]
+    fn noise_without_click_is_rejected() {
+        let template = click_template(CLICK_LEN);
+        assert_eq!(detect_click(&background_noise(4_800, 0.3), &template), None);
+        assert_eq!(detect_click(&vec![0.0; 4_800], &template), None);
+        assert_eq!(detect_click(&template[..100], &template), None);
+    }
+}
@@ -0,0 +1,206 @@
+use std::f64::consts::PI;
+
+/// 门限块时长与步进（ITU-R BS.1770）：400ms 块、75% 重叠，即每 100ms 完成一个块。
+const BLOCK_MS: u32 = 400;
+const STEP_MS: u32 = 100;
+const STEPS_PER_BLOCK: usize = (BLOCK_MS / STEP_MS) as usize;
+/// 积分响度每 4 个块（约 400ms）重新计算一次。
+const BLOCKS_PER_UPDATE: u32 = 4;
+/// 绝对门限 -70 LUFS，相对门限为首轮门限后均值下方 10 LU。
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// 块响度直方图覆盖 -70..+5 LUFS，分辨率 0.1 LU；按直方图累计能量，长时间运行内存不增长。
+const HISTOGRAM_MIN_LUFS: f64 = ABSOLUTE_GATE_LUFS;
+const HISTOGRAM_MAX_LUFS: f64 = 5.0;
+const HISTOGRAM_STEP_LU: f64 = 0.1;
+const HISTOGRAM_BINS: usize =
+    ((HISTOGRAM_MAX_LUFS - HISTOGRAM_MIN_LUFS) / HISTOGRAM_STEP_LU) as usize + 1;
+
+/// 直接 II 型转置二阶节，K 计权两级滤波共用。
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z1;
+        self.z1 = self.b[1] * x - self.a[0] * y + self.z2;
+        self.z2 = self.b[2] * x - self.a[1] * y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// BS.1770 K 计权第一级：约 +4 dB 的高频搁架，模拟头部声学效应。
+fn k_shelf(sample_rate: f64) -> Biquad {
+    let f0 = 1_681.974_450_955_533;
+    let gain_db = 3.999_843_853_973_347;
+    let q = 0.707_175_236_955_419_6;
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    )
+}
+
+/// BS.1770 K 计权第二级：约 38 Hz 的二阶高通（RLB 曲线）。
+fn k_highpass(sample_rate: f64) -> Biquad {
+    let f0 = 38.135_470_876_024_44;
+    let q = 0.500_327_037_323_877_3;
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    )
+}
+
+/// 均方能量换算为响度（LUFS）。
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// K 计权积分响度（LUFS）表：按 BS.1770 的 400ms 门限块与两级门限累计整段节目的响度。
+///
+/// 只能作用于连续样本流，状态跨数据块保留；输入为折叠后的单声道分析流，
+/// 因此结果等同于对单声道缩混做测量，与多声道加权求和的标准读数会有少量差异。
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    shelf: Biquad,
+    highpass: Biquad,
+    step_samples: usize,
+    /// 当前 100ms 步进内已累计的平方和与样本数。
+    step_energy: f64,
+    step_filled: usize,
+    /// 最近 4 个步进的均方能量，组成一个 400ms 门限块。
+    steps: [f64; STEPS_PER_BLOCK],
+    steps_seen: usize,
+    /// 通过绝对门限的块：按响度分桶累计块数与能量和。
+    histogram_counts: Vec<u64>,
+    histogram_energy: Vec<f64>,
+    blocks_since_update: u32,
+    integrated: Option<f32>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let rate = f64::from(sample_rate.max(1));
+        Self {
+            shelf: k_shelf(rate),
+            highpass: k_highpass(rate),
+            step_samples: ((sample_rate as usize * STEP_MS as usize) / 1000).max(1),
+            step_energy: 0.0,
+            step_filled: 0,
+            steps: [0.0; STEPS_PER_BLOCK],
+            steps_seen: 0,
+            histogram_counts: vec![0; HISTOGRAM_BINS],
+            histogram_energy: vec![0.0; HISTOGRAM_BINS],
+            blocks_since_update: 0,
+            integrated: None,
+        }
+    }
+
+    /// 追加一段连续样本，每完成一个门限块累计一次，每约 400ms 刷新积分响度。
+    pub fn process(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let filtered = self.highpass.process(self.shelf.process(f64::from(sample)));
+            self.step_energy += filtered * filtered;
+            self.step_filled += 1;
+            if self.step_filled == self.step_samples {
+                self.finish_step();
+            }
+        }
+    }
+
+    /// 最近一次计算的积分响度；尚无块通过绝对门限（如一直静音）时为 `None`。
+    pub fn integrated(&self) -> Option<f32> {
+        self.integrated
+    }
+
+    /// 清空滤波状态与累计的门限块，重新开始测量。
+    pub fn reset(&mut self) {
+        self.shelf.reset();
+        self.highpass.reset();
+        self.step_energy = 0.0;
+        self.step_filled = 0;
+        self.steps = [0.0; STEPS_PER_BLOCK];
+        self.steps_seen = 0;
+        self.histogram_counts.fill(0);
+        self.histogram_energy.fill(0.0);
+        self.blocks_since_update = 0;
+        self.integrated = None;
+    }
+
+    fn finish_step(&mut self) {
+        self.steps[self.steps_seen % STEPS_PER_BLOCK] = self.step_energy / self.step_samples as f64;
+        self.steps_seen += 1;
+        self.step_energy = 0.0;
+        self.step_filled = 0;
+        if self.steps_seen < STEPS_PER_BLOCK {
+            return;
+        }
+
+        // 关键行：最近 4 个 100ms 步进的均值即 400ms 块的均方能量，相邻块重叠 75%。
+        let block_energy = self.steps.iter().sum::<f64>() / STEPS_PER_BLOCK as f64;
+        let block_lufs = energy_to_lufs(block_energy);
+        if block_lufs > ABSOLUTE_GATE_LUFS {
+            let index = (((block_lufs - HISTOGRAM_MIN_LUFS) / HISTOGRAM_STEP_LU) as usize)
+                .min(HISTOGRAM_BINS - 1);
+            self.histogram_counts[index] += 1;
+            self.histogram_energy[index] += block_energy;
+        }
+
+        self.blocks_since_update += 1;
+        if self.blocks_since_update >= BLOCKS_PER_UPDATE {
+            self.blocks_since_update = 0;
+            self.integrated = self.gated_loudness().map(|lufs| lufs as f32);
+        }
+    }
+
+    /// 两级门限：先求绝对门限以上块的平均能量，再只保留高于其 -10 LU 的块求积分响度。
+    fn gated_loudness(&self) -> Option<f64> {
+        let (count, energy) = self.sum_from(0);
+        if count == 0 {
+            return None;
+        }
+        let relative_gate = energy_to_lufs(energy / count as f64) + RELATIVE_GATE_LU;
+        // 关键行：相对门限落在某个分桶内部时无法逐块比较，按桶下沿取整（误差不超过 0.1 LU）。
+        let start = (((relative_gate - HISTOGRAM_MIN_LUFS) / HISTOGRAM_STEP_LU).max(0.0) as usize)
+            .min(HISTOGRAM_BINS - 1);
+        let (count, energy) = self.sum_from(start);
+        (count > 0).then(|| energy_to_lufs(energy / count as f64))
+    }
+
+    fn sum_from(&self, start: usize) -> (u64, f64) {
+        self.histogram_counts[start..]
+            .iter()
+            .zip(&self.histogram_energy[start..])
+            .fold((0, 0.0), |(count, energy), (&c, &e)| {
+                (count + c, energy + e)
+            })
+    }
+}
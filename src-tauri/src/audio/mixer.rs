@@ -0,0 +1,103 @@
+use super::capture::CaptureChunk;
+use super::resample::LinearResampler;
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+
+/// 附加来源缓存上限（秒）：两台设备时钟存在漂移，超出时丢弃最旧样本，避免混合延迟持续累积。
+const MAX_PENDING_SECONDS: f32 = 0.1;
+/// 主来源长时间无数据（如回环在系统静音时停止推送）时，附加来源积压超过该时长即单独输出。
+const ORPHAN_FLUSH_SECONDS: f32 = 0.04;
+
+/// 混合采集：把附加来源（默认输入）的单声道样本重采样到主来源采样率，按增益叠加到主来源数据块上。
+///
+/// 以主来源数据块为时钟；主来源暂停推送时由 [`SourceMixer::take_orphaned`] 单独输出附加来源，
+/// 保证只有麦克风有声音时画面仍然跟随。
+pub struct SourceMixer {
+    receiver: Receiver<CaptureChunk>,
+    resampler: Option<LinearResampler>,
+    pending: VecDeque<f32>,
+    /// 附加来源尚未并入输出的削波样本数。
+    pending_clipped: u32,
+    scratch: Vec<f32>,
+    max_pending: usize,
+    orphan_flush: usize,
+    /// 最近一个主来源数据块是否带左右声道，单独输出附加来源时保持相同形态。
+    stereo: bool,
+}
+
+impl SourceMixer {
+    pub fn new(receiver: Receiver<CaptureChunk>, input_rate: u32, output_rate: u32) -> Self {
+        let resampler = (input_rate != output_rate)
+            .then(|| LinearResampler::new(input_rate as f64, output_rate as f64));
+        Self {
+            receiver,
+            resampler,
+            pending: VecDeque::new(),
+            pending_clipped: 0,
+            scratch: Vec::new(),
+            max_pending: (output_rate as f32 * MAX_PENDING_SECONDS) as usize,
+            orphan_flush: (output_rate as f32 * ORPHAN_FLUSH_SECONDS) as usize,
+            stereo: false,
+        }
+    }
+
+    /// 取出附加来源已到达的样本，对齐到主来源采样率后追加到待混合缓存。
+    fn collect(&mut self) {
+        for chunk in self.receiver.try_iter() {
+            self.pending_clipped = self.pending_clipped.saturating_add(chunk.clipped);
+            self.scratch.clear();
+            match self.resampler.as_mut() {
+                Some(resampler) => resampler.process(&chunk.samples, &mut self.scratch),
+                None => self.scratch.extend_from_slice(&chunk.samples),
+            }
+            self.pending.extend(self.scratch.iter().copied());
+        }
+
+        let overflow = self.pending.len().saturating_sub(self.max_pending);
+        self.pending.drain(..overflow);
+    }
+
+    /// 把附加来源叠加到主来源数据块上；附加来源样本不足的部分按静音处理。
+    pub fn mix(&mut self, chunk: &mut CaptureChunk, primary_gain: f32, input_gain: f32) {
+        self.collect();
+        self.stereo = chunk.stereo.is_some();
+        chunk.clipped = chunk
+            .clipped
+            .saturating_add(std::mem::take(&mut self.pending_clipped));
+
+        let take = chunk.samples.len().min(self.pending.len());
+        let input = self.pending.drain(..take).collect::<Vec<_>>();
+        // 关键行：附加来源为单声道，立体声模式下等量叠加到左右声道，相当于居中声像。
+        let mut targets = vec![&mut chunk.samples];
+        if let Some((left, right)) = chunk.stereo.as_mut() {
+            targets.push(left);
+            targets.push(right);
+        }
+        for target in targets {
+            for (index, sample) in target.iter_mut().enumerate() {
+                let mixed = input.get(index).copied().unwrap_or(0.0);
+                *sample = *sample * primary_gain + mixed * input_gain;
+            }
+        }
+    }
+
+    /// 主来源未推送数据时调用：附加来源积压足够多时单独输出为一个数据块，否则返回 `None`。
+    pub fn take_orphaned(&mut self, input_gain: f32, timestamp_ms: u64) -> Option<CaptureChunk> {
+        self.collect();
+        if self.pending.len() < self.orphan_flush {
+            return None;
+        }
+
+        let samples = self
+            .pending
+            .drain(..)
+            .map(|sample| sample * input_gain)
+            .collect::<Vec<_>>();
+        Some(CaptureChunk {
+            timestamp_ms,
+            stereo: self.stereo.then(|| (samples.clone(), samples.clone())),
+            samples,
+            clipped: std::mem::take(&mut self.pending_clipped),
+        })
+    }
+}
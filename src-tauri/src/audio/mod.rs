@@ -1,4 +1,11 @@
-// 音频相关模块入口：采集、DSP、缓冲队列。
+// 音频相关模块入口：采集、混合、抽取降采样、重采样、DSP、FFT、延迟探测、响度、录制旁路、缓冲队列。
 pub mod capture;
+pub mod decimate;
 pub mod dsp;
+pub mod fft;
+pub mod latency;
+pub mod loudness;
+pub mod mixer;
+pub mod recorder;
+pub mod resample;
 pub mod ring_buffer;
@@ -0,0 +1,250 @@
+use crate::error::AppError;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 指定时长录制的时长上限（秒），排障只需几秒音频，避免误传参数写出巨大文件；
+/// 不指定时长的录制不受此限制，一直录到主动停止或采集中断。
+pub const MAX_RECORDING_SECONDS: u32 = 60;
+/// 录制截止时间在目标时长之外额外等待的时长（毫秒），容忍系统静音时回环采集暂停回调。
+const RECORDING_GRACE_MS: u64 = 2_000;
+
+/// 旁路转发的数据块：`(采样率, 单声道样本)`。
+type TeeChunk = (u32, Vec<f32>);
+
+struct ActiveRecording {
+    /// 录制序号，写盘任务结束时只清理自己开启的录制，不会误清提前停止后新开启的录制。
+    id: u64,
+    /// 首个数据块到达时记下的采样率，之后采样率变化（采集已重建）即结束录制；尚未收到数据时为 `None`。
+    sample_rate: Option<u32>,
+    sender: Sender<TeeChunk>,
+    /// 由 [`RecordingState::stop`] 主动停止时置位，写盘任务据此区分主动停止与采集中断。
+    stopped: Arc<AtomicBool>,
+}
+
+/// 一次录制的结果：写入路径、采样率与实际写入的样本数和时长。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSummary {
+    pub path: String,
+    pub sample_rate: u32,
+    pub samples: u64,
+    pub duration_seconds: f64,
+}
+
+/// 录制旁路：分析线程把每个采集数据块复制一份发给写盘任务，写 WAV 不占用分析线程。
+#[derive(Clone, Default)]
+pub struct RecordingState {
+    active: Arc<Mutex<Option<ActiveRecording>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RecordingState {
+    /// 分析线程每收到一个数据块调用一次；采样率与首个数据块不一致（采集已重建）时结束录制。
+    pub fn tee(&self, samples: &[f32], sample_rate: u32) {
+        // 关键行：只尝试加锁，命令线程正在启停录制时宁可漏掉一个数据块，也不阻塞实时链路。
+        let Ok(mut guard) = self.active.try_lock() else {
+            return;
+        };
+        let Some(active) = guard.as_mut() else {
+            return;
+        };
+        // 关键行：采样率取自旁路数据本身，不依赖可能已过期的采集状态。
+        let recorded_rate = *active.sample_rate.get_or_insert(sample_rate);
+        if recorded_rate != sample_rate
+            || active.sender.send((sample_rate, samples.to_vec())).is_err()
+        {
+            *guard = None;
+        }
+    }
+
+    /// 提前停止当前录制：丢弃发送端后写盘任务收完已转发的样本即写好 WAV 头并返回。
+    pub fn stop(&self) -> Result<(), AppError> {
+        let mut guard = self
+            .active
+            .lock()
+            .map_err(|_| AppError::Internal("recording state poisoned".to_string()))?;
+        let Some(active) = guard.take() else {
+            return Err(AppError::InvalidArgument(
+                "no recording in progress".to_string(),
+            ));
+        };
+        active.stopped.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// 采集停止时中断当前录制：丢弃发送端但不标记主动停止，写盘任务按采集中断返回错误。
+    pub fn interrupt(&self) {
+        if let Ok(mut guard) = self.active.lock() {
+            *guard = None;
+        }
+    }
+
+    fn begin(&self) -> Result<(u64, Receiver<TeeChunk>, Arc<AtomicBool>), AppError> {
+        let mut guard = self
+            .active
+            .lock()
+            .map_err(|_| AppError::Internal("recording state poisoned".to_string()))?;
+        if guard.is_some() {
+            return Err(AppError::InvalidArgument(
+                "a recording is already in progress".to_string(),
+            ));
+        }
+        let (sender, receiver) = mpsc::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let stopped = Arc::new(AtomicBool::new(false));
+        *guard = Some(ActiveRecording {
+            id,
+            sample_rate: None,
+            sender,
+            stopped: stopped.clone(),
+        });
+        Ok((id, receiver, stopped))
+    }
+
+    fn end(&self, id: u64) {
+        if let Ok(mut guard) = self.active.lock() {
+            if guard.as_ref().is_some_and(|active| active.id == id) {
+                *guard = None;
+            }
+        }
+    }
+}
+
+/// 录制采集到的原始单声道样本并写入 32 位浮点 WAV，录满 `seconds` 秒或被 [`RecordingState::stop`]
+/// 提前停止后结束，返回实际写入的样本数与时长；未指定时长时不设上限，直到被停止。
+///
+/// 采样率取自首个旁路数据块。录制途中采集被停止或重建（采样率变化、旁路被替换）时，已写入的部分
+/// 仍会写好 WAV 头保留在磁盘上，但返回错误，调用方不会把截断的文件当作完整录制。
+/// 指定时长时，系统静音导致回环采集暂停的录制在截止时间结束，返回的时长可能短于请求值。
+///
+/// 阻塞到录制结束，调用方应放到阻塞线程池执行。
+pub fn record_to_wav(
+    state: &RecordingState,
+    path: &str,
+    seconds: Option<u32>,
+) -> Result<RecordingSummary, AppError> {
+    if let Some(seconds) = seconds.filter(|seconds| !(1..=MAX_RECORDING_SECONDS).contains(seconds))
+    {
+        return Err(AppError::InvalidArgument(format!(
+            "recording length must be within 1..={MAX_RECORDING_SECONDS} s, got {seconds}"
+        )));
+    }
+    let trimmed_path = path.trim();
+    if trimmed_path.is_empty() {
+        return Err(AppError::InvalidArgument(
+            "recording path must not be empty".to_string(),
+        ));
+    }
+
+    // 关键行：先占用录制槽位再创建文件，已有录制进行时不会截断目标文件。
+    let (id, receiver, stopped) = state.begin()?;
+    let result = write_wav(Path::new(trimmed_path), &receiver, seconds);
+    state.end(id);
+    let (sample_rate, samples, ended) = result?;
+    let duration_seconds = samples as f64 / sample_rate.max(1) as f64;
+    if ended == RecordingEnd::Disconnected && !stopped.load(Ordering::Acquire) {
+        return Err(AppError::Capture(format!(
+            "recording interrupted after {duration_seconds:.2} s because capture stopped or \
+             restarted; partial file kept at {trimmed_path}"
+        )));
+    }
+    Ok(RecordingSummary {
+        path: trimmed_path.to_string(),
+        sample_rate,
+        samples,
+        duration_seconds,
+    })
+}
+
+/// 写盘循环结束的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordingEnd {
+    /// 写满目标样本数。
+    Complete,
+    /// 超过截止时间仍未写满。
+    TimedOut,
+    /// 旁路发送端被丢弃：主动停止、采集停止或重建。
+    Disconnected,
+}
+
+/// 等到首个旁路数据块确定采样率后创建 WAV 文件，再持续写盘，直到写满目标时长、录制被停止、
+/// 采集重建或超过截止时间；返回 `(采样率, 写入样本数, 结束原因)`。未指定时长时没有截止时间。
+fn write_wav(
+    output_path: &Path,
+    receiver: &Receiver<TeeChunk>,
+    seconds: Option<u32>,
+) -> Result<(u32, u64, RecordingEnd), AppError> {
+    let deadline = seconds.map(|seconds| {
+        Instant::now() + Duration::from_millis(seconds as u64 * 1_000 + RECORDING_GRACE_MS)
+    });
+    let next_chunk = || match deadline {
+        Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => receiver
+            .recv()
+            .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+    };
+    let (sample_rate, first) = match next_chunk() {
+        Ok(chunk) => chunk,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            return Err(AppError::Capture(
+                "no audio arrived before the recording deadline".to_string(),
+            ))
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            return Err(AppError::Capture(
+                "recording ended before any audio arrived".to_string(),
+            ))
+        }
+    };
+
+    if let Some(parent) = output_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(|err| {
+            AppError::Export(format!("failed to create recording directory: {err}"))
+        })?;
+    }
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(output_path, spec)
+        .map_err(|err| AppError::Export(format!("failed to create wav file: {err}")))?;
+
+    let target = seconds.map(|seconds| sample_rate as u64 * seconds as u64);
+    let mut written = 0u64;
+    let mut chunk = first;
+    let ended = loop {
+        let take = match target {
+            Some(target) => chunk.len().min((target - written) as usize),
+            None => chunk.len(),
+        };
+        for &sample in &chunk[..take] {
+            writer
+                .write_sample(sample)
+                .map_err(|err| AppError::Export(format!("failed to write wav sample: {err}")))?;
+        }
+        written += take as u64;
+        if target.is_some_and(|target| written >= target) {
+            break RecordingEnd::Complete;
+        }
+        match next_chunk() {
+            Ok((_, samples)) => chunk = samples,
+            Err(mpsc::RecvTimeoutError::Timeout) => break RecordingEnd::TimedOut,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break RecordingEnd::Disconnected,
+        }
+    };
+
+    writer
+        .finalize()
+        .map_err(|err| AppError::Export(format!("failed to finalize wav file: {err}")))?;
+    Ok((sample_rate, written, ended))
+}
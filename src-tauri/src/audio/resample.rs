@@ -0,0 +1,149 @@
+use super::decimate::{self, Decimator};
+
+/// 线性插值重采样器：把输入序列从 `from_rate` 转换到 `to_rate`。
+///
+/// 上一块末尾样本与插值位置跨数据块保留，保证块边界处连续。
+pub struct LinearResampler {
+    /// 每个输出样本在输入序列上前进的步长（`from_rate / to_rate`）。
+    step: f64,
+    /// 下一个输出样本在“上一块末尾样本 + 本块样本”序列中的位置，0 对应上一块末尾样本。
+    position: f64,
+    last: f32,
+}
+
+impl LinearResampler {
+    pub fn new(from_rate: f64, to_rate: f64) -> Self {
+        Self {
+            step: from_rate / to_rate,
+            // 关键行：从 1 开始即对齐本块首个样本，避免首个输出被初始的 0 拉低。
+            position: 1.0,
+            last: 0.0,
+        }
+    }
+
+    /// 处理一个数据块并把重采样后的样本追加到 `output`。
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let Some(&tail) = input.last() else {
+            return;
+        };
+
+        let len = input.len() as f64;
+        output.reserve((len / self.step) as usize + 1);
+        while self.position < len {
+            let base = self.position as usize;
+            let frac = (self.position - base as f64) as f32;
+            let left = if base == 0 {
+                self.last
+            } else {
+                input[base - 1]
+            };
+            let right = input[base];
+            output.push(left + (right - left) * frac);
+            self.position += self.step;
+        }
+
+        self.position -= len;
+        self.last = tail;
+    }
+}
+
+/// 分析前的采样率转换：先按整数倍抽取（带抗混叠），再线性插值到固定分析采样率。
+pub struct AnalysisRateConverter {
+    decimator: Decimator,
+    resampler: Option<LinearResampler>,
+    scratch: Vec<f32>,
+    output_rate: u32,
+}
+
+impl AnalysisRateConverter {
+    /// `target_rate` 为 0 时不做固定采样率转换，仅按 `max_rate` 抽取。
+    pub fn new(device_rate: u32, max_rate: u32, target_rate: u32) -> Self {
+        // 关键行：固定分析采样率时以目标采样率作为抽取上限，抽取后不高于目标，
+        // 线性插值只需升采样，不会引入混叠。
+        let decimator = Decimator::new(device_rate, decimation_limit(max_rate, target_rate));
+        let decimated_rate = device_rate as f64 / decimator.factor() as f64;
+        let resampler = (target_rate > 0 && decimated_rate != target_rate as f64)
+            .then(|| LinearResampler::new(decimated_rate, target_rate as f64));
+
+        Self {
+            decimator,
+            resampler,
+            scratch: Vec::new(),
+            output_rate: analysis_rate(device_rate, max_rate, target_rate),
+        }
+    }
+
+    /// 送入分析器的有效采样率。
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// 处理一个数据块并把转换后的样本追加到 `output`。
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let Some(resampler) = self.resampler.as_mut() else {
+            self.decimator.process(input, output);
+            return;
+        };
+
+        self.scratch.clear();
+        self.decimator.process(input, &mut self.scratch);
+        resampler.process(&self.scratch, output);
+    }
+}
+
+/// 计算送入分析器的有效采样率：固定分析采样率优先，否则为抽取后的设备采样率。
+pub fn analysis_rate(device_rate: u32, max_rate: u32, target_rate: u32) -> u32 {
+    if target_rate > 0 {
+        return target_rate;
+    }
+    device_rate / decimate::decimation_factor(device_rate, max_rate) as u32
+}
+
+fn decimation_limit(max_rate: u32, target_rate: u32) -> u32 {
+    if target_rate > 0 {
+        target_rate
+    } else {
+        max_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::dsp::{self, FrequencyRange, FrequencyScale};
+
+    #[test]
+    fn converter_decimates_192k_to_the_cap() {
+        let mut converter = AnalysisRateConverter::new(192_000, 48_000, 0);
+        assert_eq!(converter.output_rate(), 48_000);
+
+        let mut output = Vec::new();
+        converter.process(&[0.0; 1920], &mut output);
+        assert_eq!(output.len(), 480);
+    }
+
+    #[test]
+    fn bin_frequencies_use_the_decimated_rate() {
+        for (device_rate, max_rate, target_rate) in [
+            (192_000, 48_000, 0),
+            (192_000, 44_100, 0),
+            (96_000, 96_000, 0),
+            (192_000, 48_000, 44_100),
+        ] {
+            let converter = AnalysisRateConverter::new(device_rate, max_rate, target_rate);
+            // 关键行：频率轴按 `analysis_rate` 计算，必须与分析线程实际送入分析器的采样率一致。
+            let rate = analysis_rate(device_rate, max_rate, target_rate);
+            assert_eq!(rate, converter.output_rate());
+            assert!(rate <= max_rate);
+
+            let range = FrequencyRange {
+                min_hz: 20.0,
+                max_hz: device_rate as f32,
+            };
+            let frequencies =
+                dsp::bin_center_frequencies(64, 1024, rate, FrequencyScale::Linear, range);
+            let top = frequencies.iter().copied().fold(0.0, f32::max);
+            assert!(top <= rate as f32 * 0.5, "{device_rate} -> {rate}: {top}");
+        }
+    }
+}
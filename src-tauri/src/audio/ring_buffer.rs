@@ -16,7 +16,10 @@ impl<T> RingBuffer<T> {
     }
 
     pub fn push(&mut self, item: T) {
-        if self.data.len() == self.max_len {
+        if self.max_len == 0 {
+            return;
+        }
+        if self.is_full() {
             let _ = self.data.pop_front();
         }
         self.data.push_back(item);
@@ -25,4 +28,123 @@ impl<T> RingBuffer<T> {
     pub fn pop(&mut self) -> Option<T> {
         self.data.pop_front()
     }
+
+    /// 按写入顺序（旧 -> 新）遍历当前缓存内容，不消费数据。
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// 是否已写满容量，之后每次写入都会覆盖最老数据。
+    pub fn is_full(&self) -> bool {
+        self.data.len() >= self.max_len
+    }
+}
+
+impl<T: Copy> RingBuffer<T> {
+    /// 批量追加，超出容量时丢弃最老数据；只移动队首位置，不搬移剩余数据。
+    pub fn extend_from_slice(&mut self, items: &[T]) {
+        if self.max_len == 0 {
+            return;
+        }
+        // 关键行：单次写入超过容量时只保留其末尾，前面的部分写入后也会立刻被覆盖。
+        let items = &items[items.len().saturating_sub(self.max_len)..];
+        let overflow = (self.data.len() + items.len()).saturating_sub(self.max_len);
+        self.data.drain(..overflow);
+        self.data.extend(items.iter().copied());
+    }
+
+    /// 把最新的 `n` 个元素按时间顺序连续复制到 `out` 开头，返回实际复制的个数
+    /// （不超过当前长度与 `out` 长度）。环绕处分两段整块复制。
+    pub fn copy_latest(&self, n: usize, out: &mut [T]) -> usize {
+        let count = n.min(self.data.len()).min(out.len());
+        let skip = self.data.len() - count;
+        let (front, back) = self.data.as_slices();
+        if skip >= front.len() {
+            let start = skip - front.len();
+            out[..count].copy_from_slice(&back[start..start + count]);
+        } else {
+            let head = &front[skip..];
+            out[..head.len()].copy_from_slice(head);
+            out[head.len()..count].copy_from_slice(&back[..count - head.len()]);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 容量 4 的缓存依次写入 0..6：内容为 [2, 3, 4, 5]，且队首已绕回，内部分成两段。
+    fn wrapped() -> RingBuffer<i32> {
+        let mut buffer = RingBuffer::new(4);
+        for value in 0..6 {
+            buffer.push(value);
+        }
+        buffer
+    }
+
+    #[test]
+    fn copy_latest_spans_both_halves_in_order() {
+        let buffer = wrapped();
+        let (front, back) = buffer.data.as_slices();
+        assert!(!front.is_empty() && !back.is_empty(), "buffer did not wrap");
+
+        let mut out = [0; 4];
+        assert_eq!(buffer.copy_latest(4, &mut out), 4);
+        assert_eq!(out, [2, 3, 4, 5]);
+
+        let mut out = [0; 3];
+        assert_eq!(buffer.copy_latest(3, &mut out), 3);
+        assert_eq!(out, [3, 4, 5]);
+    }
+
+    #[test]
+    fn copy_latest_clamps_to_buffer_length() {
+        let buffer = wrapped();
+        let mut out = [-1; 6];
+        assert_eq!(buffer.copy_latest(10, &mut out), 4);
+        assert_eq!(out, [2, 3, 4, 5, -1, -1]);
+    }
+
+    #[test]
+    fn copy_latest_clamps_to_output_length() {
+        let buffer = wrapped();
+        let mut out = [0; 2];
+        assert_eq!(buffer.copy_latest(4, &mut out), 2);
+        // 输出不足时仍取最新的元素。
+        assert_eq!(out, [4, 5]);
+    }
+
+    #[test]
+    fn extend_longer_than_capacity_keeps_the_tail() {
+        let mut buffer = RingBuffer::new(4);
+        buffer.extend_from_slice(&[1, 2]);
+        buffer.extend_from_slice(&[10, 11, 12, 13, 14, 15]);
+        assert!(buffer.is_full());
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [12, 13, 14, 15]);
+
+        buffer.extend_from_slice(&[16]);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [13, 14, 15, 16]);
+    }
+
+    #[test]
+    fn zero_capacity_ignores_writes() {
+        let mut buffer = RingBuffer::<i32>::new(0);
+        buffer.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn zero_capacity_ignores_pushes() {
+        let mut buffer = RingBuffer::<i32>::new(0);
+        buffer.push(1);
+        buffer.push(2);
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.pop(), None);
+    }
 }
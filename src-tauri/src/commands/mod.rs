@@ -1,12 +1,38 @@
 ﻿use crate::audio::capture::{self, AudioDeviceInfo};
+use crate::audio::dsp::{WindowFunction, MAX_EQ_BANDS};
+use crate::audio::latency::{self, LatencyProbeState};
+use crate::audio::recorder::{self, RecordingState, RecordingSummary};
 use crate::desktop::{
-    click_through,
-    window_mode::{self, MonitorInfo, WindowBehaviorState, WindowMode},
+    autostart::{self, AutostartStatus},
+    click_through::{self, Rect},
+    opacity,
+    window_geometry::{self, WindowGeometry, WindowGeometryState},
+    window_mode::{self, MonitorInfo, SnapAnchor, WindowBehaviorState, WindowMode},
 };
-use crate::settings::{self, AppSettings};
-use crate::telemetry::{runtime_config_from_settings, RuntimeDspState, RuntimeVisualState};
+use crate::error::AppError;
+use crate::params::{
+    self, ParamDescriptor, BIN_COUNT_RANGE, EQ_GAIN_RANGE, OVERLAY_OPACITY_RANGE,
+    WINDOW_FADE_MS_RANGE,
+};
+use crate::settings::{self, AppSettings, DspPreset, RecentDevice, SettingsWarningState};
+use crate::telemetry::delivery::FrameDeliveryState;
+use crate::telemetry::level_history::{LevelEntry, LevelHistoryState};
+use crate::telemetry::spectrogram::{self, SpectrogramFormat, SpectrogramHistory};
+use crate::telemetry::{
+    self, runtime_config_from_settings, CaptureControlState, CaptureStatus, CaptureStatusState,
+    DspBenchmarkReport, LevelMeterState, RuntimeDspState, RuntimeVisualState,
+};
+use crate::theme::{Theme, ThemeState};
+use std::collections::BTreeMap;
 use tauri::{Emitter, State};
 
+#[cfg(desktop)]
+use crate::desktop::hotkey;
+#[cfg(desktop)]
+use crate::desktop::tray_menu;
+#[cfg(desktop)]
+use tauri::Manager;
+
 /// 基础健康检查命令，用于验证前后端命令桥接是否可用。
 #[tauri::command]
 pub fn health_check() -> &'static str {
@@ -15,42 +41,199 @@ pub fn health_check() -> &'static str {
 
 /// 读取可用音频设备列表，供前端设备选择器使用。
 #[tauri::command]
-pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, AppError> {
     capture::list_audio_devices()
 }
 
+/// 读取当前实际生效的采集设备与格式；分析线程尚未完成首次启动或采集已停止时返回 `None`。
+#[tauri::command]
+pub fn get_capture_status(capture_status: State<'_, CaptureStatusState>) -> Option<CaptureStatus> {
+    capture_status.get()
+}
+
+/// 读取采集格式与缓冲延迟详情，供排查延迟问题；与 `get_capture_status` 数据相同，但采集未运行时返回错误。
+#[tauri::command]
+pub fn get_capture_info(
+    capture_status: State<'_, CaptureStatusState>,
+) -> Result<CaptureStatus, AppError> {
+    capture_status
+        .get()
+        .ok_or_else(|| AppError::Capture("audio capture is not running".to_string()))
+}
+
+/// 读取最新数据块的 `(rms, peak)`，供电平表等轻量组件高频轮询，不依赖分析帧推送。
+#[tauri::command]
+pub fn get_current_levels(level_meter: State<'_, LevelMeterState>) -> (f32, f32) {
+    level_meter.current()
+}
+
+/// 读取最近 `count` 条数据块电平（按时间先后），供 VU 表绘制滚动响度曲线；不足时返回现有全部记录。
+#[tauri::command]
+pub fn get_level_history(
+    count: usize,
+    level_history: State<'_, LevelHistoryState>,
+) -> Vec<LevelEntry> {
+    level_history.latest(count)
+}
+
+/// 返回最近成功采集过的设备（最近使用在前），并剔除当前已不存在的设备。
+#[tauri::command]
+pub fn get_recent_devices(app: tauri::AppHandle) -> Result<Vec<RecentDevice>, AppError> {
+    let current = settings::load_settings_from_disk()?;
+    let available = capture::list_audio_devices().unwrap_or_default();
+    let is_available =
+        |recent: &RecentDevice| available.iter().any(|device| device.id == recent.id);
+    if current.recent_devices.iter().all(is_available) {
+        return Ok(current.recent_devices);
+    }
+
+    let updated = settings::update_and_emit(&app, |settings| {
+        settings.recent_devices.retain(is_available);
+        Ok(())
+    })?;
+    Ok(updated.recent_devices)
+}
+
 /// 枚举系统显示器信息，供前端设置目标显示器。
 #[tauri::command]
-pub fn list_monitors(app: tauri::AppHandle) -> Result<Vec<MonitorInfo>, String> {
+pub fn list_monitors(app: tauri::AppHandle) -> Result<Vec<MonitorInfo>, AppError> {
     let window = window_mode::main_window(&app)?;
     window_mode::list_monitors(&window)
 }
 
 /// 加载持久化设置，如果不存在则返回默认值。
 #[tauri::command]
-pub fn load_settings() -> Result<AppSettings, String> {
+pub fn load_settings() -> Result<AppSettings, AppError> {
     settings::load_settings_from_disk()
 }
 
+/// 取出启动阶段产生的设置告警（例如降级后备份了新版本配置），每条只返回一次。
+#[tauri::command]
+pub fn take_settings_warnings(warnings: State<'_, SettingsWarningState>) -> Vec<String> {
+    warnings.take_all()
+}
+
 /// 保存完整设置对象，并同步运行时 DSP 与窗口行为。
 #[tauri::command]
 pub fn save_settings(
     mut settings: AppSettings,
     app: tauri::AppHandle,
     runtime_dsp: State<'_, RuntimeDspState>,
+    runtime_visual: State<'_, RuntimeVisualState>,
     window_state: State<'_, WindowBehaviorState>,
-) -> Result<(), String> {
+    geometry_state: State<'_, WindowGeometryState>,
+    theme_state: State<'_, ThemeState>,
+) -> Result<(), AppError> {
+    // 关键行：先校验再同步运行时，内存中的设置与写盘结果保持一致。
+    settings = settings.validated();
+    settings.theme.validate()?;
+    #[cfg(desktop)]
+    hotkey::parse_hotkey(&settings.pause_hotkey)?;
     runtime_dsp.set(runtime_config_from_settings(&settings));
+    runtime_dsp.set_capture_device_id(&telemetry::capture_target_device_id(&settings));
+    runtime_visual.set_animate_tray(settings.animate_tray);
+
+    let theme_changed = theme_state.get().theme != settings.theme;
+    theme_state.set_theme(settings.theme.clone());
+    theme_state.set_emit_bin_colors(settings.emit_bin_colors);
+    if theme_changed {
+        let _ = app.emit("app:theme_changed", &settings.theme);
+    }
+
+    if let Err(error) = autostart::reconcile_autostart(settings.launch_at_startup) {
+        eprintln!("failed to apply launch at startup: {error}");
+    }
+
+    #[cfg(desktop)]
+    if let Err(error) = hotkey::apply_pause_hotkey(
+        &app,
+        &app.state::<hotkey::PauseHotkeyState>(),
+        &settings.pause_hotkey,
+    ) {
+        eprintln!("failed to apply pause hotkey: {error}");
+    }
 
     let requested_click = settings.click_through;
-    let effective_click = apply_runtime_window_behavior(&app, &settings, &window_state)?;
+    let effective_click =
+        apply_runtime_window_behavior(&app, &settings, &window_state, &geometry_state)?;
     settings.click_through = effective_click;
+    #[cfg(desktop)]
+    tray_menu::sync_tray_checks(&app);
 
     if requested_click != effective_click {
         let _ = app.emit("app:click_through_changed", effective_click);
     }
 
-    settings::save_settings_to_disk(&settings)
+    settings::update_and_emit(&app, |current| {
+        // 关键行：最近设备列表由采集线程维护、窗口几何由窗口事件维护、预设由预设命令维护，
+        // 在写锁内保留磁盘上的版本，避免前端旧快照覆盖。
+        settings.recent_devices = std::mem::take(&mut current.recent_devices);
+        settings.window_geometry = std::mem::take(&mut current.window_geometry);
+        settings.presets = std::mem::take(&mut current.presets);
+        settings.active_preset = std::mem::take(&mut current.active_preset);
+        *current = settings;
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+/// 列出全部命名 DSP 预设（按名称排序）。
+#[tauri::command]
+pub fn list_presets() -> Result<BTreeMap<String, DspPreset>, AppError> {
+    Ok(settings::load_settings_from_disk()?.presets)
+}
+
+/// 以当前已保存的设置创建或覆盖同名预设，并记为当前预设。
+#[tauri::command]
+pub fn save_preset(app: tauri::AppHandle, name: String) -> Result<(), AppError> {
+    let name = settings::normalize_preset_name(&name)?;
+    settings::update_and_emit(&app, |current| {
+        let preset = current.capture_preset();
+        current.presets.insert(name.clone(), preset);
+        current.active_preset = name;
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+/// 加载预设：立即应用到运行时 DSP 并落盘，返回更新后的完整设置供前端同步表单。
+#[tauri::command]
+pub fn load_preset(
+    app: tauri::AppHandle,
+    name: String,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<AppSettings, AppError> {
+    let name = settings::normalize_preset_name(&name)?;
+    let current = settings::update_and_emit(&app, |current| {
+        let preset = current
+            .presets
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| AppError::PresetNotFound(format!("preset not found: {name}")))?;
+        current.apply_preset(&preset);
+        current.active_preset = name;
+        Ok(())
+    })?;
+    runtime_dsp.set(runtime_config_from_settings(&current));
+    Ok(current)
+}
+
+/// 删除预设；删除的是当前预设时一并清空当前预设名。
+#[tauri::command]
+pub fn delete_preset(app: tauri::AppHandle, name: String) -> Result<(), AppError> {
+    let name = settings::normalize_preset_name(&name)?;
+    settings::update_and_emit(&app, |current| {
+        if current.presets.remove(&name).is_none() {
+            return Err(AppError::PresetNotFound(format!(
+                "preset not found: {name}"
+            )));
+        }
+        if current.active_preset == name {
+            current.active_preset.clear();
+        }
+        Ok(())
+    })
+    .map(|_| ())
 }
 
 /// 切换窗口模式：普通窗口 / 桌面组件 / 悬浮覆盖层。
@@ -59,34 +242,52 @@ pub fn set_window_mode(
     app: tauri::AppHandle,
     mode: String,
     window_state: State<'_, WindowBehaviorState>,
-) -> Result<(), String> {
+    geometry_state: State<'_, WindowGeometryState>,
+) -> Result<(), AppError> {
     let window = window_mode::main_window(&app)?;
     let parsed_mode = WindowMode::from_raw(&mode);
+    let mode_changed = window_state.get().mode != parsed_mode;
 
-    window_mode::apply_window_mode(&window, parsed_mode)?;
+    if mode_changed {
+        geometry_state.suppress();
+    }
+    apply_mode_transition(&window, parsed_mode, &window_state)?;
     window_state.set_mode(parsed_mode);
+    if mode_changed {
+        let saved = settings::load_settings_from_disk()?.window_geometry;
+        restore_mode_geometry(&window, &saved, parsed_mode);
+    }
 
     let click_requested = window_state.get().click_through;
     let effective = click_through::apply_click_through(&window, parsed_mode, click_requested)?;
 
+    // 关键行：穿透刚被重新应用，写回状态让热区跟踪线程按新状态重新设置。
+    window_state.set_click_through(effective);
     // 当普通模式强制关闭穿透时，通知前端同步状态，避免 UI 与实际行为不一致。
     if click_requested && !effective {
-        window_state.set_click_through(false);
         let _ = app.emit("app:click_through_changed", false);
     }
+    #[cfg(desktop)]
+    tray_menu::sync_tray_checks(&app);
 
     Ok(())
 }
 
-/// 将窗口移动到指定显示器。
+/// 将窗口移动到指定显示器；`rescale` 为真时按两屏缩放比换算窗口尺寸，保持逻辑尺寸不变（默认不换算）。
+///
+/// 返回目标显示器当前的规范标识，传入旧格式标识时前端可据此更新保存的 `targetMonitorId`；空标识原样返回。
 #[tauri::command]
-pub fn set_target_monitor(app: tauri::AppHandle, monitor_id: String) -> Result<(), String> {
+pub fn set_target_monitor(
+    app: tauri::AppHandle,
+    monitor_id: String,
+    rescale: Option<bool>,
+) -> Result<String, AppError> {
     if monitor_id.trim().is_empty() {
-        return Ok(());
+        return Ok(monitor_id);
     }
 
     let window = window_mode::main_window(&app)?;
-    window_mode::move_window_to_monitor(&window, &monitor_id)
+    window_mode::move_window_to_monitor(&window, &monitor_id, rescale.unwrap_or(false))
 }
 
 /// 切换点击穿透：仅在桌面组件/悬浮模式生效，普通模式会自动禁用。
@@ -95,7 +296,7 @@ pub fn set_click_through(
     app: tauri::AppHandle,
     enabled: bool,
     window_state: State<'_, WindowBehaviorState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let window = window_mode::main_window(&app)?;
     let snapshot = window_state.get();
     let effective = click_through::apply_click_through(&window, snapshot.mode, enabled)?;
@@ -104,34 +305,396 @@ pub fn set_click_through(
     if effective != enabled {
         let _ = app.emit("app:click_through_changed", effective);
     }
+    #[cfg(desktop)]
+    tray_menu::sync_tray_checks(&app);
+
+    Ok(())
+}
+
+/// 把主窗口吸附到当前所在显示器工作区的指定位置（`top-left`、`top`、`top-right`、`left`、`center`、
+/// `right`、`bottom-left`、`bottom`、`bottom-right`），`margin` 为与边缘的逻辑像素间距，默认 0。
+#[tauri::command]
+pub fn snap_window(
+    app: tauri::AppHandle,
+    anchor: String,
+    margin: Option<u32>,
+) -> Result<(), AppError> {
+    let parsed = SnapAnchor::from_raw(&anchor)
+        .ok_or_else(|| AppError::InvalidArgument(format!("unknown snap anchor: {anchor}")))?;
+    let window = window_mode::main_window(&app)?;
+    window_mode::snap_window(&window, parsed, margin.unwrap_or(0))
+}
+
+/// 设置悬浮覆盖层模式的窗口不透明度（钳制到 0.1..=1.0），不写入持久化设置。
+///
+/// 当前处于悬浮覆盖层模式时立即生效；其它模式只记录该值，切换到覆盖层时再应用，
+/// 普通模式与桌面组件模式始终保持完全不透明。仅 Windows 支持，其它平台返回 `Unsupported`。
+#[tauri::command]
+pub fn set_window_opacity(
+    app: tauri::AppHandle,
+    opacity: f32,
+    window_state: State<'_, WindowBehaviorState>,
+) -> Result<(), AppError> {
+    if !opacity.is_finite() {
+        return Err(AppError::InvalidArgument(
+            "opacity must be a finite number".to_string(),
+        ));
+    }
+    let window = window_mode::main_window(&app)?;
+    window_state.set_overlay_opacity(OVERLAY_OPACITY_RANGE.clamp(opacity));
+    let snapshot = window_state.get();
+    opacity::set_window_opacity(&window, snapshot.target_opacity(snapshot.mode))
+}
+
+/// 设置点击穿透时仍可交互的窗口区域（逻辑像素，相对窗口客户区左上角），传空列表恢复整窗穿透。
+///
+/// 穿透生效时由热区跟踪线程按光标位置动态切换穿透；区域不写入持久化设置，由前端按布局上报。
+#[tauri::command]
+pub fn set_interactive_regions(
+    rects: Vec<Rect>,
+    window_state: State<'_, WindowBehaviorState>,
+) -> Result<(), AppError> {
+    if let Some(invalid) = rects.iter().find(|rect| !rect.is_valid()) {
+        return Err(AppError::InvalidArgument(format!(
+            "invalid interactive region: {invalid:?}"
+        )));
+    }
+    window_state.set_interactive_regions(rects);
+    Ok(())
+}
 
+/// 查询开机自启的实际状态与设置值，供前端检测用户手动删除自启项等漂移。
+#[tauri::command]
+pub fn get_autostart_status() -> Result<AutostartStatus, AppError> {
+    Ok(AutostartStatus {
+        enabled: autostart::is_autostart_enabled()?,
+        configured: settings::load_settings_from_disk()?.launch_at_startup,
+    })
+}
+
+/// 切换采集设备：持久化选择并通知分析线程重建采集流。
+///
+/// 设备无法解析时分析线程回退默认设备，实际生效的设备通过 `app:capture_device_changed` 事件告知前端。
+#[tauri::command]
+pub fn set_capture_device(
+    app: tauri::AppHandle,
+    device_id: String,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<(), AppError> {
+    let current = settings::update_and_emit(&app, |current| {
+        current.capture_device_id = device_id.trim().to_string();
+        Ok(())
+    })?;
+    runtime_dsp.set_capture_device_id(&telemetry::capture_target_device_id(&current));
     Ok(())
 }
 
+/// 停止采集并释放音频设备，适合笔记本省电；分析线程释放后推送 `audio:capture_running` = `false`。
+#[tauri::command]
+pub fn stop_capture(capture_control: State<'_, CaptureControlState>) {
+    capture_control.stop();
+}
+
+/// 按当前设置重新获取音频设备并恢复采集；未停止时不做任何事。
+#[tauri::command]
+pub fn start_capture(capture_control: State<'_, CaptureControlState>) {
+    capture_control.start();
+}
+
 /// 切换可视化暂停状态，用于托盘菜单的暂停/恢复。
 #[tauri::command]
 pub fn set_visual_paused(
+    app: tauri::AppHandle,
     paused: bool,
     runtime_visual: State<'_, RuntimeVisualState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     runtime_visual.set_paused(paused);
+    #[cfg(desktop)]
+    tray_menu::sync_tray_checks(&app);
+    #[cfg(not(desktop))]
+    let _ = app;
     Ok(())
 }
 
+/// 读取当前频段配色主题。
+#[tauri::command]
+pub fn get_theme(theme_state: State<'_, ThemeState>) -> Theme {
+    theme_state.get().theme
+}
+
+/// 校验并应用频段配色主题：立即影响分析帧颜色提示，同时落盘并通知所有前端。
+#[tauri::command]
+pub fn set_theme(
+    app: tauri::AppHandle,
+    theme: Theme,
+    theme_state: State<'_, ThemeState>,
+) -> Result<(), AppError> {
+    theme.validate()?;
+    theme_state.set_theme(theme.clone());
+
+    settings::update_and_emit(&app, |current| {
+        current.theme = theme.clone();
+        Ok(())
+    })?;
+
+    app.emit("app:theme_changed", &theme)
+        .map_err(|err| AppError::Internal(format!("failed to emit theme event: {err}")))
+}
+
+/// 录制指定时长的频谱并导出为 CSV（时间 × 频段）或 PNG 热力图，返回写入路径。
+#[tauri::command]
+pub async fn export_spectrogram(
+    duration_ms: u64,
+    path: String,
+    format: String,
+    history: State<'_, SpectrogramHistory>,
+) -> Result<String, AppError> {
+    let parsed_format = SpectrogramFormat::parse(&format)?;
+    let history = history.inner().clone();
+
+    // 关键行：录制窗口需要阻塞等待，放到阻塞线程池执行，避免占用 IPC 异步运行时。
+    tauri::async_runtime::spawn_blocking(move || {
+        spectrogram::record_and_export(&history, duration_ms, &path, parsed_format)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("spectrogram export task failed: {err}")))?
+}
+
+/// 把实际采集到的单声道样本录制为 WAV（按采集采样率、32 位浮点），录满 `seconds` 秒
+/// 或调用 `stop_recording` 后停止，返回写入路径与实际录制时长；不传 `seconds` 时不设时长上限，
+/// 一直录到 `stop_recording`。录制途中采集被停止或重建时返回错误（已写入的部分保留在磁盘上）。
+#[tauri::command]
+pub async fn start_recording(
+    path: String,
+    seconds: Option<u32>,
+    capture_status: State<'_, CaptureStatusState>,
+    recording: State<'_, RecordingState>,
+) -> Result<RecordingSummary, AppError> {
+    let status = capture_status
+        .get()
+        .ok_or_else(|| AppError::Capture("audio capture is not running".to_string()))?;
+    if status.is_mock {
+        return Err(AppError::Unsupported(
+            "recording requires real audio capture, currently using simulated audio".to_string(),
+        ));
+    }
+    let recording = recording.inner().clone();
+
+    // 关键行：录制需要阻塞等待，放到阻塞线程池执行；分析线程只做一次非阻塞的样本转发。
+    tauri::async_runtime::spawn_blocking(move || {
+        recorder::record_to_wav(&recording, &path, seconds)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("recording task failed: {err}")))?
+}
+
+/// 提前停止进行中的录制；`start_recording` 随即写好 WAV 头并返回。没有进行中的录制时返回错误。
+#[tauri::command]
+pub fn stop_recording(recording: State<'_, RecordingState>) -> Result<(), AppError> {
+    recording.stop()
+}
+
+/// 返回每个频段的中心频率（Hz），供前端绘制频率轴与低/中/高频标记；采集尚未启动时返回错误。
+///
+/// 频率轴变化时分析线程另行推送 `audio:bin_frequencies` 事件，前端无需轮询本命令。
+#[tauri::command]
+pub fn get_bin_frequencies(
+    capture_status: State<'_, CaptureStatusState>,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<Vec<f32>, AppError> {
+    telemetry::bin_frequencies(&capture_status, &runtime_dsp.get())
+}
+
+/// 用当前 DSP 参数对合成信号做分析基准测试，返回单帧耗时统计，不影响实时链路。
+#[tauri::command]
+pub async fn benchmark_dsp(
+    iterations: usize,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<DspBenchmarkReport, AppError> {
+    let config = runtime_dsp.get();
+    tauri::async_runtime::spawn_blocking(move || telemetry::benchmark_dsp(config, iterations))
+        .await
+        .map_err(|err| AppError::Internal(format!("dsp benchmark task failed: {err}")))?
+}
+
+/// 实测采集链路延迟：向设置中的输出设备播放探测脉冲并在回环信号中定位，返回毫秒数；
+/// 需要输出回环采集处于活动状态。
+#[tauri::command]
+pub async fn measure_latency(probe: State<'_, LatencyProbeState>) -> Result<f64, AppError> {
+    let probe = probe.inner().clone();
+    let output_device_id = settings::load_settings_from_disk()?.output_device_id;
+    tauri::async_runtime::spawn_blocking(move || {
+        latency::measure_latency(&probe, &output_device_id)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("latency probe task failed: {err}")))?
+}
+
+/// 重新校准：清空分析器的频段基线与平滑缓存，保留全部已调参数与设置。
+#[tauri::command]
+pub fn reset_dsp_adaptation(runtime_dsp: State<'_, RuntimeDspState>) {
+    runtime_dsp.request_adaptation_reset();
+}
+
+/// 列出所有运行时可调参数的自描述信息（类型、当前值、范围、默认值、所属子系统）。
+#[tauri::command]
+pub fn introspect_params() -> Result<Vec<ParamDescriptor>, AppError> {
+    let current = settings::load_settings_from_disk()?;
+    Ok(params::describe_params(&current))
+}
+
+/// 调整规范频段数（柱子数量）并落盘，分析线程在下一帧即按新频段数输出，无需重启采集。
+#[tauri::command]
+pub fn set_bin_count(
+    app: tauri::AppHandle,
+    bin_count: usize,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<(), AppError> {
+    let (min, max) = (BIN_COUNT_RANGE.min as usize, BIN_COUNT_RANGE.max as usize);
+    if !(min..=max).contains(&bin_count) {
+        return Err(AppError::InvalidArgument(format!(
+            "bin count must be within {min}..={max}, got {bin_count}"
+        )));
+    }
+
+    settings::update_and_emit(&app, |current| {
+        current.bin_count = bin_count;
+        Ok(())
+    })?;
+
+    let mut config = runtime_dsp.get();
+    config.bin_count = bin_count;
+    runtime_dsp.set(config);
+    Ok(())
+}
+
+/// 切换分析窗函数并落盘，分析器在下一帧按新窗重算系数，无需重启采集。
+#[tauri::command]
+pub fn set_window_function(
+    app: tauri::AppHandle,
+    window_function: String,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<(), AppError> {
+    let parsed = WindowFunction::parse(&window_function).ok_or_else(|| {
+        AppError::InvalidArgument(format!("unknown window function: {window_function}"))
+    })?;
+
+    settings::update_and_emit(&app, |current| {
+        current.window_function = parsed.name().to_string();
+        Ok(())
+    })?;
+
+    let mut config = runtime_dsp.get();
+    config.window_function = parsed;
+    runtime_dsp.set(config);
+    Ok(())
+}
+
+/// 设置可视化图形均衡曲线（等距控制点的幅度倍数，低频在前）并落盘，传空数组恢复平直；下一帧即生效。
+#[tauri::command]
+pub fn set_eq(
+    app: tauri::AppHandle,
+    gains: Vec<f32>,
+    runtime_dsp: State<'_, RuntimeDspState>,
+) -> Result<(), AppError> {
+    if gains.len() > MAX_EQ_BANDS {
+        return Err(AppError::InvalidArgument(format!(
+            "eq supports at most {MAX_EQ_BANDS} bands, got {}",
+            gains.len()
+        )));
+    }
+    if let Some(gain) = gains
+        .iter()
+        .find(|gain| !gain.is_finite() || **gain < EQ_GAIN_RANGE.min || **gain > EQ_GAIN_RANGE.max)
+    {
+        return Err(AppError::InvalidArgument(format!(
+            "eq gain must be within {}..={}, got {gain}",
+            EQ_GAIN_RANGE.min, EQ_GAIN_RANGE.max
+        )));
+    }
+
+    let persisted_settings = settings::update_and_emit(&app, |current| {
+        current.eq_gains = gains;
+        Ok(())
+    })?;
+
+    let mut config = runtime_dsp.get();
+    config.eq = telemetry::eq_curve(&persisted_settings.eq_gains);
+    runtime_dsp.set(config);
+    Ok(())
+}
+
+/// 开始把推送的分析帧逐帧写入 JSONL 文件（一行一个帧对象），实时与模拟数据均可录制；返回写入路径。
+#[tauri::command]
+pub fn start_frame_recording(
+    path: String,
+    frame_delivery: State<'_, FrameDeliveryState>,
+) -> Result<String, AppError> {
+    frame_delivery.start_recording(&path)
+}
+
+/// 停止帧录制并返回写入的帧数。
+#[tauri::command]
+pub fn stop_frame_recording(
+    frame_delivery: State<'_, FrameDeliveryState>,
+) -> Result<u64, AppError> {
+    frame_delivery.stop_recording()
+}
+
+/// 按原始节奏回放 JSONL 帧录制到 `audio:analysis_frame`，回放期间暂停实时帧推送；返回回放的帧数。
+#[tauri::command]
+pub async fn replay_frames(
+    app: tauri::AppHandle,
+    path: String,
+    frame_delivery: State<'_, FrameDeliveryState>,
+) -> Result<u64, AppError> {
+    let frame_delivery = frame_delivery.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || frame_delivery.replay(&app, &path))
+        .await
+        .map_err(|err| AppError::Internal(format!("frame replay task failed: {err}")))?
+}
+
+/// 为调用方窗口临时覆盖分析帧频段数，传 `None` 恢复规范频段数；不写入持久化设置。
+#[tauri::command]
+pub fn set_frame_bin_count(
+    window: tauri::WebviewWindow,
+    bin_count: Option<usize>,
+    frame_delivery: State<'_, FrameDeliveryState>,
+) -> Result<(), AppError> {
+    frame_delivery.set_bin_count(window.label(), bin_count)
+}
+
 /// 统一应用窗口相关设置，避免不同命令分叉出不一致行为。
 pub fn apply_runtime_window_behavior(
     app: &tauri::AppHandle,
     settings: &AppSettings,
     window_state: &WindowBehaviorState,
-) -> Result<bool, String> {
+    geometry_state: &WindowGeometryState,
+) -> Result<bool, AppError> {
     let window = window_mode::main_window(app)?;
     let mode = WindowMode::from_raw(&settings.window_mode);
+    // 关键行：几何只在启动与模式切换时恢复，普通的设置保存不能把用户刚拖动的窗口拉回旧位置。
+    let restore_geometry = geometry_state.take_initial_restore() || window_state.get().mode != mode;
+    if restore_geometry {
+        geometry_state.suppress();
+    }
 
-    window_mode::apply_window_mode(&window, mode)?;
+    window_state.set_overlay_opacity(OVERLAY_OPACITY_RANGE.clamp(settings.overlay_opacity));
+    window_state.set_transition_fade(settings.mode_transition_fade);
+    window_state.set_window_fade(
+        settings.window_fade,
+        WINDOW_FADE_MS_RANGE.clamp_u64(settings.window_fade_ms),
+    );
+    apply_mode_transition(&window, mode, window_state)?;
     window_state.set_mode(mode);
+    if restore_geometry {
+        restore_mode_geometry(&window, &settings.window_geometry, mode);
+    }
 
     if !settings.target_monitor_id.trim().is_empty() {
-        if let Err(error) = window_mode::move_window_to_monitor(&window, &settings.target_monitor_id)
+        // 关键行：应用设置时窗口尺寸来自已保存的几何或当前窗口，不做缩放换算。
+        if let Err(error) =
+            window_mode::move_window_to_monitor(&window, &settings.target_monitor_id, false)
         {
             eprintln!(
                 "failed to move window to monitor {}: {error}",
@@ -144,3 +707,47 @@ pub fn apply_runtime_window_behavior(
     window_state.set_click_through(effective_click);
     Ok(effective_click)
 }
+
+/// 恢复指定模式保存的窗口几何；该模式尚未保存过几何时保持当前位置与尺寸。
+fn restore_mode_geometry(
+    window: &tauri::WebviewWindow,
+    saved: &BTreeMap<WindowMode, WindowGeometry>,
+    mode: WindowMode,
+) {
+    let Some(geometry) = saved.get(&mode) else {
+        return;
+    };
+    if let Err(error) = window_geometry::restore_window_geometry(window, *geometry) {
+        eprintln!("failed to restore window geometry for {mode:?}: {error}");
+    }
+}
+
+/// 应用窗口模式；模式确有变化且开启淡入淡出时，用淡出/淡入包裹属性变更。
+///
+/// 变更完成后统一把不透明度设为该模式的目标值：切到悬浮覆盖层时应用 `overlay_opacity`，
+/// 切回其它模式时恢复完全不透明。开启淡入时由后台淡入线程到达目标值，命令不等待动画。
+fn apply_mode_transition(
+    window: &tauri::WebviewWindow,
+    mode: WindowMode,
+    window_state: &WindowBehaviorState,
+) -> Result<(), AppError> {
+    let snapshot = window_state.get();
+    let fade = snapshot.transition_fade && snapshot.mode != mode;
+    let target = snapshot.target_opacity(mode);
+    let canceller = window_state.fade_canceller();
+    let (result, fading) = opacity::with_fade(window, canceller, fade, target, || {
+        window_mode::apply_window_mode(window, mode)
+    });
+    result?;
+    if fading {
+        return Ok(());
+    }
+    // 关键行：不支持整体透明度的平台静默跳过，窗口本就保持完全不透明。
+    match opacity::set_window_opacity(window, target) {
+        Ok(()) | Err(AppError::Unsupported(_)) => Ok(()),
+        Err(error) => {
+            eprintln!("failed to apply window opacity {target}: {error}");
+            Ok(())
+        }
+    }
+}
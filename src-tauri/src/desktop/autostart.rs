@@ -0,0 +1,156 @@
+use crate::error::AppError;
+use serde::Serialize;
+
+/// 开机自启状态：`enabled` 为系统中的实际状态，`configured` 为设置中的期望值，两者不同说明被外部修改。
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutostartStatus {
+    pub enabled: bool,
+    pub configured: bool,
+}
+
+/// 开机自启注册表项中的值名称。
+#[cfg(windows)]
+const RUN_VALUE_NAME: &str = "tt-audio-lab";
+/// 当前用户开机自启注册表键（位于 `HKEY_CURRENT_USER` 下）。
+#[cfg(windows)]
+const RUN_KEY_PATH: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+/// 转为以 0 结尾的 UTF-16 字符串，供 Win32 宽字符 API 使用。
+#[cfg(windows)]
+fn wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// 自启命令：带引号的当前可执行文件路径，兼容路径中的空格。
+#[cfg(windows)]
+fn autostart_command() -> Result<String, AppError> {
+    let exe = std::env::current_exe().map_err(|err| {
+        AppError::Internal(format!("failed to resolve current executable: {err}"))
+    })?;
+    Ok(format!("\"{}\"", exe.display()))
+}
+
+/// 打开 Run 键，调用方负责关闭句柄。
+#[cfg(windows)]
+fn open_run_key(
+    access: windows_sys::Win32::System::Registry::REG_SAM_FLAGS,
+) -> Result<windows_sys::Win32::System::Registry::HKEY, AppError> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{RegOpenKeyExW, HKEY_CURRENT_USER};
+
+    let path = wide(RUN_KEY_PATH);
+    let mut key = std::ptr::null_mut();
+    let status = unsafe { RegOpenKeyExW(HKEY_CURRENT_USER, path.as_ptr(), 0, access, &mut key) };
+    if status != ERROR_SUCCESS {
+        return Err(AppError::Internal(format!(
+            "failed to open autostart registry key (error {status})"
+        )));
+    }
+    Ok(key)
+}
+
+/// 读取 Run 键中本应用的自启命令，值不存在时返回 `None`。
+#[cfg(windows)]
+fn registered_command() -> Result<Option<String>, AppError> {
+    use windows_sys::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+    use windows_sys::Win32::System::Registry::{RegCloseKey, RegQueryValueExW, KEY_READ};
+
+    let key = open_run_key(KEY_READ)?;
+    let name = wide(RUN_VALUE_NAME);
+    let mut buffer = vec![0u16; 1024];
+    let mut byte_len = (buffer.len() * 2) as u32;
+    let status = unsafe {
+        RegQueryValueExW(
+            key,
+            name.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            buffer.as_mut_ptr().cast(),
+            &mut byte_len,
+        )
+    };
+    unsafe {
+        RegCloseKey(key);
+    }
+
+    match status {
+        ERROR_SUCCESS => {
+            let len = (byte_len as usize / 2).min(buffer.len());
+            let value = String::from_utf16_lossy(&buffer[..len]);
+            Ok(Some(value.trim_end_matches('\0').to_string()))
+        }
+        ERROR_FILE_NOT_FOUND => Ok(None),
+        _ => Err(AppError::Internal(format!(
+            "failed to read autostart registry value (error {status})"
+        ))),
+    }
+}
+
+/// 查询开机自启是否已启用：注册表项存在且指向当前可执行文件才算启用。
+#[cfg(windows)]
+pub fn is_autostart_enabled() -> Result<bool, AppError> {
+    let expected = autostart_command()?;
+    Ok(registered_command()?.is_some_and(|command| command.eq_ignore_ascii_case(&expected)))
+}
+
+/// 写入或删除 Run 键中的自启项。
+#[cfg(windows)]
+pub fn set_autostart(enabled: bool) -> Result<(), AppError> {
+    use windows_sys::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+    use windows_sys::Win32::System::Registry::{
+        RegCloseKey, RegDeleteValueW, RegSetValueExW, KEY_SET_VALUE, REG_SZ,
+    };
+
+    let key = open_run_key(KEY_SET_VALUE)?;
+    let name = wide(RUN_VALUE_NAME);
+    let status = if enabled {
+        let command = wide(&autostart_command()?);
+        unsafe {
+            RegSetValueExW(
+                key,
+                name.as_ptr(),
+                0,
+                REG_SZ,
+                command.as_ptr().cast(),
+                (command.len() * 2) as u32,
+            )
+        }
+    } else {
+        unsafe { RegDeleteValueW(key, name.as_ptr()) }
+    };
+    unsafe {
+        RegCloseKey(key);
+    }
+
+    // 关键行：关闭时值本就不存在视为成功，保证重复关闭幂等。
+    if status == ERROR_SUCCESS || (!enabled && status == ERROR_FILE_NOT_FOUND) {
+        Ok(())
+    } else {
+        Err(AppError::Internal(format!(
+            "failed to update autostart registry value (error {status})"
+        )))
+    }
+}
+
+/// 非 Windows 平台不支持开机自启，始终视为未启用。
+#[cfg(not(windows))]
+pub fn is_autostart_enabled() -> Result<bool, AppError> {
+    Ok(false)
+}
+
+/// 非 Windows 平台暂不支持开机自启。
+#[cfg(not(windows))]
+pub fn set_autostart(_enabled: bool) -> Result<(), AppError> {
+    Err(AppError::Unsupported(
+        "launch at startup is only supported on Windows".to_string(),
+    ))
+}
+
+/// 让注册表与设置保持一致：仅在实际状态不同时写入，重复保存相同设置不会触碰注册表。
+pub fn reconcile_autostart(enabled: bool) -> Result<(), AppError> {
+    if is_autostart_enabled()? != enabled {
+        set_autostart(enabled)?;
+    }
+    Ok(())
+}
@@ -1,16 +1,102 @@
-﻿use crate::desktop::window_mode::WindowMode;
-use tauri::WebviewWindow;
+﻿use crate::desktop::window_mode::{self, WindowBehaviorState, WindowMode};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, WebviewWindow};
+
+/// 热区命中检测的轮询间隔：约 30Hz，足以跟上鼠标移向按钮的速度。
+///
+/// 穿透开启时窗口收不到鼠标事件，只能轮询全局光标位置做命中检测。
+const HOT_ZONE_POLL_MS: u64 = 33;
+
+/// 窗口内矩形区域，坐标为逻辑像素（与前端 CSS 像素一致），原点为窗口客户区左上角。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    /// 坐标有限且宽高为正的矩形才参与命中检测。
+    pub fn is_valid(&self) -> bool {
+        [self.x, self.y, self.width, self.height]
+            .iter()
+            .all(|value| value.is_finite())
+            && self.width > 0.0
+            && self.height > 0.0
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
 
 /// 应用点击穿透策略：仅在桌面组件/覆盖层模式允许真正穿透，避免普通模式锁死交互。
 pub fn apply_click_through(
     window: &WebviewWindow,
     mode: WindowMode,
     requested_enabled: bool,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     // 关键行：普通窗口强制禁用系统级穿透，确保设置窗口始终可恢复操作。
     let effective_enabled = requested_enabled && !matches!(mode, WindowMode::Normal);
     window
         .set_ignore_cursor_events(effective_enabled)
-        .map_err(|err| format!("failed to set click-through: {err}"))?;
+        .map_err(|err| AppError::Window(format!("failed to set click-through: {err}")))?;
     Ok(effective_enabled)
 }
+
+/// 启动交互热区跟踪线程：穿透生效时按光标是否位于可交互区域动态切换 `set_ignore_cursor_events`，
+/// 光标进入热区时窗口恢复接收鼠标，离开后重新穿透；未配置热区时保持整窗穿透。
+///
+/// 穿透关闭（或处于普通窗口模式）时同样按版本号把窗口恢复为可交互：本线程的设置可能与命令线程的
+/// 关闭操作交错，晚到的一次“穿透”会在下一轮轮询被纠正，不会留下无法点击的窗口。
+pub fn start_hot_zone_tracker(app: AppHandle, window_state: WindowBehaviorState) {
+    thread::spawn(move || {
+        // 上次由本线程应用的 (行为版本号, 是否穿透)；版本号变化说明穿透刚被重新应用，需要重新设置。
+        let mut applied: Option<(u64, bool)> = None;
+
+        loop {
+            thread::sleep(Duration::from_millis(HOT_ZONE_POLL_MS));
+
+            // 关键行：先读版本号再读状态，期间若有变化，下一轮会看到新版本号并重新应用。
+            let revision = window_state.revision();
+            let snapshot = window_state.get();
+            let Ok(window) = window_mode::main_window(&app) else {
+                continue;
+            };
+
+            let ignore = if !snapshot.click_through || snapshot.mode == WindowMode::Normal {
+                false
+            } else {
+                let regions = window_state.interactive_regions();
+                regions.is_empty() || !cursor_in_regions(&window, &regions)
+            };
+            if applied == Some((revision, ignore)) {
+                continue;
+            }
+            match window.set_ignore_cursor_events(ignore) {
+                Ok(()) => applied = Some((revision, ignore)),
+                Err(error) => eprintln!("failed to update hot-zone click-through: {error}"),
+            }
+        }
+    });
+}
+
+/// 把全局光标位置换算到窗口客户区逻辑坐标后做命中检测；无法取得位置时视为未命中。
+fn cursor_in_regions(window: &WebviewWindow, regions: &[Rect]) -> bool {
+    let (Ok(cursor), Ok(origin), Ok(scale)) = (
+        window.cursor_position(),
+        window.inner_position(),
+        window.scale_factor(),
+    ) else {
+        return false;
+    };
+    // 关键行：光标与客户区原点均为物理像素，先求差再按缩放比换算为逻辑像素。
+    let x = (cursor.x - origin.x as f64) / scale;
+    let y = (cursor.y - origin.y as f64) / scale;
+    regions.iter().any(|region| region.contains(x, y))
+}
@@ -0,0 +1,81 @@
+use crate::desktop::tray_menu;
+use crate::error::AppError;
+use crate::telemetry::RuntimeVisualState;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// 当前已注册的暂停/恢复快捷键，保存设置时据此判断是否需要重新注册。
+#[derive(Clone, Default)]
+pub struct PauseHotkeyState {
+    current: Arc<Mutex<Option<Shortcut>>>,
+}
+
+impl PauseHotkeyState {
+    fn get(&self) -> Option<Shortcut> {
+        self.current.lock().ok().and_then(|guard| *guard)
+    }
+
+    fn set(&self, shortcut: Option<Shortcut>) {
+        if let Ok(mut guard) = self.current.lock() {
+            *guard = shortcut;
+        }
+    }
+}
+
+/// 全局快捷键插件：系统级监听，窗口未聚焦或处于点击穿透时同样生效。
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            if app.state::<PauseHotkeyState>().get() == Some(*shortcut) {
+                let paused = app.state::<RuntimeVisualState>().is_paused();
+                tray_menu::set_visual_paused(app, !paused);
+            }
+        })
+        .build()
+}
+
+/// 解析快捷键字符串（如 `CommandOrControl+Shift+P`），空字符串表示不启用。
+pub fn parse_hotkey(raw: &str) -> Result<Option<Shortcut>, AppError> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    Shortcut::from_str(raw)
+        .map(Some)
+        .map_err(|err| AppError::InvalidArgument(format!("invalid pause hotkey {raw}: {err}")))
+}
+
+/// 按设置注册暂停快捷键；与已注册的相同则不做任何事，变化时先注销旧快捷键再注册新快捷键。
+pub fn apply_pause_hotkey(
+    app: &AppHandle,
+    state: &PauseHotkeyState,
+    raw: &str,
+) -> Result<(), AppError> {
+    let wanted = parse_hotkey(raw)?;
+    let current = state.get();
+    if wanted == current {
+        return Ok(());
+    }
+
+    let shortcuts = app.global_shortcut();
+    if let Some(old) = current {
+        if let Err(err) = shortcuts.unregister(old) {
+            eprintln!("failed to unregister pause hotkey: {err}");
+        }
+        state.set(None);
+    }
+    if let Some(new) = wanted {
+        // 关键行：快捷键可能已被其他程序占用，注册失败时保持未注册状态，下次保存设置会重试。
+        shortcuts.register(new).map_err(|err| {
+            AppError::Internal(format!("failed to register pause hotkey {raw}: {err}"))
+        })?;
+        state.set(Some(new));
+    }
+    Ok(())
+}
@@ -1,3 +1,12 @@
-// 桌面窗口行为模块入口：点击穿透与窗口模式控制。
+// 桌面窗口行为模块入口：开机自启、点击穿透、全局快捷键、窗口透明度、窗口模式、窗口几何、托盘活动指示与托盘勾选项。
+pub mod autostart;
 pub mod click_through;
+#[cfg(desktop)]
+pub mod hotkey;
+pub mod opacity;
+#[cfg(desktop)]
+pub mod tray_activity;
+#[cfg(desktop)]
+pub mod tray_menu;
+pub mod window_geometry;
 pub mod window_mode;
@@ -0,0 +1,266 @@
+use crate::error::AppError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::WebviewWindow;
+
+/// 模式切换淡入淡出的单程时长与步数，总时长控制在人眼可感知但不拖沓的范围。
+const FADE_DURATION_MS: u64 = 120;
+const FADE_STEPS: u32 = 8;
+
+/// 设置窗口整体不透明度（0.0..=1.0）。
+///
+/// Windows 下通过 `WS_EX_LAYERED` + `SetLayeredWindowAttributes(LWA_ALPHA)` 实现，
+/// 可在任意线程调用，不依赖 Tauri 事件循环派发。
+#[cfg(windows)]
+pub fn set_window_opacity(window: &WebviewWindow, opacity: f32) -> Result<(), AppError> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE, LWA_ALPHA,
+        WS_EX_LAYERED,
+    };
+
+    let hwnd = window
+        .hwnd()
+        .map_err(|err| AppError::Window(format!("failed to get window handle: {err}")))?
+        .0;
+    let alpha = (opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    // 关键行：分层样式可能已由点击穿透设置，这里只追加不移除，避免破坏穿透行为。
+    unsafe {
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        if ex_style & WS_EX_LAYERED as isize == 0 {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_LAYERED as isize);
+        }
+        if SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA) == 0 {
+            return Err(AppError::Window(
+                "SetLayeredWindowAttributes failed".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 非 Windows 平台暂不支持窗口整体透明度。
+#[cfg(not(windows))]
+pub fn set_window_opacity(_window: &WebviewWindow, _opacity: f32) -> Result<(), AppError> {
+    Err(AppError::Unsupported(
+        "window opacity is only supported on Windows".to_string(),
+    ))
+}
+
+/// 显示/隐藏动画的取消令牌：每次开始新动画递增代数，旧动画在下一步检测到代数变化后放弃。
+#[derive(Clone, Default)]
+pub struct FadeCanceller {
+    generation: Arc<AtomicU64>,
+}
+
+impl FadeCanceller {
+    /// 取消进行中的动画并返回新动画的代数。
+    pub fn begin(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::Acquire) == generation
+    }
+}
+
+/// 在 `duration_ms` 内把不透明度从 `from` 线性过渡到 `to`，每步通过 `apply` 写入；
+/// `keep_going` 返回 `false` 时中止并返回 `false`。过渡曲线与窗口解耦，不依赖真实窗口即可验证。
+fn fade_opacity(
+    from: f32,
+    to: f32,
+    duration_ms: u64,
+    keep_going: impl Fn() -> bool,
+    mut apply: impl FnMut(f32) -> Result<(), AppError>,
+) -> Result<bool, AppError> {
+    let step_delay = Duration::from_millis(duration_ms / FADE_STEPS as u64);
+    for step in 1..=FADE_STEPS {
+        if !keep_going() {
+            return Ok(false);
+        }
+        let t = step as f32 / FADE_STEPS as f32;
+        apply(from + (to - from) * t)?;
+        thread::sleep(step_delay);
+    }
+    Ok(true)
+}
+
+/// 从全透明淡入到 `target_opacity`；中途写入失败时直接设为目标值，保证窗口不会停留在不可见或半透明状态。
+fn fade_in(
+    target_opacity: f32,
+    duration_ms: u64,
+    keep_going: impl Fn() -> bool,
+    mut apply: impl FnMut(f32) -> Result<(), AppError>,
+) {
+    if let Err(error) = fade_opacity(0.0, target_opacity, duration_ms, keep_going, &mut apply) {
+        eprintln!("window fade-in failed, forcing target opacity: {error}");
+        let _ = apply(target_opacity);
+    }
+}
+
+/// 以 0 不透明度显示窗口，再在后台线程淡入到 `target_opacity`；不支持透明度的平台直接显示。
+///
+/// 聚焦由调用方在本函数返回后处理，窗口此时已可见。
+pub fn show_with_fade(
+    window: &WebviewWindow,
+    canceller: &FadeCanceller,
+    target_opacity: f32,
+    duration_ms: u64,
+) -> Result<(), AppError> {
+    let generation = canceller.begin();
+    let fade_supported = set_window_opacity(window, 0.0).is_ok();
+    window
+        .show()
+        .map_err(|err| AppError::Window(format!("failed to show main window: {err}")))?;
+    if !fade_supported {
+        return Ok(());
+    }
+
+    let window = window.clone();
+    let canceller = canceller.clone();
+    thread::spawn(move || {
+        fade_in(
+            target_opacity,
+            duration_ms,
+            || canceller.is_current(generation),
+            |opacity| set_window_opacity(&window, opacity),
+        );
+    });
+    Ok(())
+}
+
+/// 在后台线程淡出后隐藏窗口，并把不透明度恢复到 `target_opacity` 供下次显示；
+/// 淡出途中被新的显示请求取消时不隐藏。不支持透明度的平台直接隐藏。
+pub fn hide_with_fade(
+    window: &WebviewWindow,
+    canceller: &FadeCanceller,
+    target_opacity: f32,
+    duration_ms: u64,
+) {
+    let generation = canceller.begin();
+    let window = window.clone();
+    let canceller = canceller.clone();
+    thread::spawn(move || {
+        let faded = fade_opacity(
+            target_opacity,
+            0.0,
+            duration_ms,
+            || canceller.is_current(generation),
+            |opacity| set_window_opacity(&window, opacity),
+        );
+        // 关键行：被取消说明已有新的显示请求接管窗口，此时不能再隐藏。
+        if matches!(faded, Ok(false)) {
+            return;
+        }
+        if let Err(error) = window.hide() {
+            eprintln!("failed to hide main window after fade-out: {error}");
+        }
+        let _ = set_window_opacity(&window, target_opacity);
+    });
+}
+
+/// 先把窗口置为全透明再应用属性变更，之后在后台线程淡入到 `target_opacity`，避免透明窗口切换装饰时闪烁。
+///
+/// 调用线程不等待动画，命令可立即返回；淡入可被同一 `canceller` 上的新动画取消。
+/// 无论变更是否成功都会淡入，保证窗口不会停留在不可见状态。返回值表示是否已接管不透明度：
+/// 为 `false`（未开启或平台不支持透明度）时由调用方自行设置最终不透明度。
+pub fn with_fade<T>(
+    window: &WebviewWindow,
+    canceller: &FadeCanceller,
+    enabled: bool,
+    target_opacity: f32,
+    change: impl FnOnce() -> Result<T, AppError>,
+) -> (Result<T, AppError>, bool) {
+    if !enabled {
+        return (change(), false);
+    }
+
+    let generation = canceller.begin();
+    if let Err(error) = set_window_opacity(window, 0.0) {
+        eprintln!("window fade skipped: {error}");
+        return (change(), false);
+    }
+
+    let result = change();
+    let window = window.clone();
+    let canceller = canceller.clone();
+    thread::spawn(move || {
+        fade_in(
+            target_opacity,
+            FADE_DURATION_MS,
+            || canceller.is_current(generation),
+            |opacity| set_window_opacity(&window, opacity),
+        );
+    });
+    (result, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_switch_fade_restores_target_opacity_after_a_failed_step() {
+        let mut applied = Vec::new();
+        let mut failures = 0;
+        fade_in(
+            0.8,
+            0,
+            || true,
+            |opacity| {
+                // 关键行：第三步写入失败时中断过渡，兜底仍把窗口恢复到目标不透明度。
+                if applied.len() == 2 && failures == 0 {
+                    failures += 1;
+                    return Err(AppError::Window("transient failure".to_string()));
+                }
+                applied.push(opacity);
+                Ok(())
+            },
+        );
+        assert_eq!(applied.len(), 3);
+        assert_eq!(applied.last(), Some(&0.8));
+    }
+
+    #[test]
+    fn fade_in_reaches_the_target_opacity() {
+        let mut applied = Vec::new();
+        fade_in(
+            0.9,
+            0,
+            || true,
+            |opacity| {
+                applied.push(opacity);
+                Ok(())
+            },
+        );
+        assert_eq!(applied.len(), FADE_STEPS as usize);
+        assert!(applied.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(applied.last(), Some(&0.9));
+    }
+
+    #[test]
+    fn cancelled_fade_stops_without_further_writes() {
+        let canceller = FadeCanceller::default();
+        let generation = canceller.begin();
+        let mut applied = 0;
+        let finished = fade_opacity(
+            0.0,
+            1.0,
+            0,
+            || canceller.is_current(generation),
+            |_| {
+                applied += 1;
+                // 关键行：模拟过渡途中到来的新显示/隐藏请求，旧动画应在下一步放弃。
+                if applied == 2 {
+                    canceller.begin();
+                }
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert!(!finished);
+        assert_eq!(applied, 2);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::telemetry::{LatestLevelState, LevelSnapshot, RuntimeVisualState};
+use std::thread;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::AppHandle;
+
+/// 主托盘图标 ID，创建托盘与动态更新图标共用。
+pub const MAIN_TRAY_ID: &str = "main-tray";
+/// 托盘图标刷新间隔：约 4Hz，部分平台重建托盘图标开销较大，不宜更快。
+const TRAY_UPDATE_INTERVAL_MS: u64 = 250;
+/// 电平超过该时长未更新即视为空闲（暂停或无音频），恢复静态图标。
+const LEVEL_MAX_AGE_MS: u64 = 1000;
+/// RMS 到亮度的放大倍数，常见音乐 RMS 多在 0.1~0.3 之间。
+const LEVEL_GAIN: f32 = 3.0;
+/// 亮度量化档位数，档位不变时跳过图标更新。
+const BRIGHTNESS_STEPS: usize = 8;
+/// 最低亮度，保证安静时图标仍然可辨认。
+const MIN_BRIGHTNESS: f32 = 0.35;
+
+/// 启动托盘电平动画线程：开启时按最新电平调整图标亮度，关闭、暂停或空闲时恢复静态图标。
+pub fn start_tray_activity(
+    app: AppHandle,
+    runtime_visual: RuntimeVisualState,
+    latest_level: LatestLevelState,
+) {
+    let Some(base_icon) = app
+        .default_window_icon()
+        .map(|icon| icon.clone().to_owned())
+    else {
+        eprintln!("tray activity disabled: no default window icon");
+        return;
+    };
+
+    thread::spawn(move || {
+        // 关键行：预先生成全部亮度档位的图标，运行期只做档位切换。
+        let frames = (0..BRIGHTNESS_STEPS)
+            .map(|step| tinted_icon(&base_icon, step))
+            .collect::<Vec<_>>();
+        let mut shown_step: Option<usize> = None;
+
+        loop {
+            thread::sleep(Duration::from_millis(TRAY_UPDATE_INTERVAL_MS));
+
+            let step = if runtime_visual.animate_tray() && !runtime_visual.is_paused() {
+                latest_level.fresh(LEVEL_MAX_AGE_MS).map(brightness_step)
+            } else {
+                None
+            };
+            if step == shown_step {
+                continue;
+            }
+
+            let Some(tray) = app.tray_by_id(MAIN_TRAY_ID) else {
+                continue;
+            };
+            let icon = step.map_or_else(|| base_icon.clone(), |index| frames[index].clone());
+            match tray.set_icon(Some(icon)) {
+                Ok(()) => shown_step = step,
+                Err(error) => eprintln!("failed to update tray icon: {error}"),
+            }
+        }
+    });
+}
+
+/// 将电平映射到亮度档位。
+fn brightness_step(level: LevelSnapshot) -> usize {
+    let normalized = (level.rms * LEVEL_GAIN).clamp(0.0, 1.0);
+    (normalized * (BRIGHTNESS_STEPS - 1) as f32).round() as usize
+}
+
+/// 按档位缩放图标 RGB 通道，保留透明度。
+fn tinted_icon(base: &Image<'static>, step: usize) -> Image<'static> {
+    let brightness =
+        MIN_BRIGHTNESS + (1.0 - MIN_BRIGHTNESS) * step as f32 / (BRIGHTNESS_STEPS - 1) as f32;
+    let rgba = base
+        .rgba()
+        .chunks_exact(4)
+        .flat_map(|pixel| {
+            let scale = |channel: u8| (channel as f32 * brightness).round() as u8;
+            [scale(pixel[0]), scale(pixel[1]), scale(pixel[2]), pixel[3]]
+        })
+        .collect::<Vec<_>>();
+    Image::new_owned(rgba, base.width(), base.height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(rms: f32) -> LevelSnapshot {
+        LevelSnapshot {
+            timestamp_ms: 0,
+            rms,
+        }
+    }
+
+    #[test]
+    fn brightness_step_spans_all_steps_and_saturates() {
+        assert_eq!(brightness_step(level(0.0)), 0);
+        assert_eq!(
+            brightness_step(level(1.0 / LEVEL_GAIN)),
+            BRIGHTNESS_STEPS - 1
+        );
+        // 关键行：超出满量程的电平夹到最高档，不会越界访问预生成的图标。
+        assert_eq!(brightness_step(level(2.0)), BRIGHTNESS_STEPS - 1);
+        assert_eq!(brightness_step(level(-0.5)), 0);
+        assert!(brightness_step(level(0.1)) < brightness_step(level(0.2)));
+    }
+}
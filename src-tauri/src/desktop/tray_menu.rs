@@ -0,0 +1,33 @@
+use crate::desktop::window_mode::WindowBehaviorState;
+use crate::telemetry::RuntimeVisualState;
+use tauri::menu::CheckMenuItem;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+/// 托盘菜单中的勾选项句柄：创建托盘后注册为托管状态，暂停或点击穿透变化时据此刷新勾选标记。
+pub struct TrayCheckItems {
+    pub paused: CheckMenuItem<Wry>,
+    pub click_through: CheckMenuItem<Wry>,
+}
+
+/// 按当前运行时状态刷新托盘勾选项；托盘尚未创建时跳过。
+pub fn sync_tray_checks(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayCheckItems>() else {
+        return;
+    };
+    let paused = app.state::<RuntimeVisualState>().is_paused();
+    let click_through = app.state::<WindowBehaviorState>().get().click_through;
+
+    if let Err(err) = items.paused.set_checked(paused) {
+        eprintln!("failed to update tray pause check: {err}");
+    }
+    if let Err(err) = items.click_through.set_checked(click_through) {
+        eprintln!("failed to update tray click-through check: {err}");
+    }
+}
+
+/// 设置可视化暂停状态并通知前端、刷新托盘勾选，托盘菜单与全局快捷键共用。
+pub fn set_visual_paused(app: &AppHandle, paused: bool) {
+    app.state::<RuntimeVisualState>().set_paused(paused);
+    let _ = app.emit("app:visual_paused", paused);
+    sync_tray_checks(app);
+}
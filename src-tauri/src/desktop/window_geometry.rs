@@ -0,0 +1,165 @@
+use crate::desktop::window_mode::WindowMode;
+use crate::error::AppError;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, PhysicalPosition, PhysicalSize, WebviewWindow, Window};
+
+/// 窗口停止移动/缩放多久后才写盘（毫秒），拖动过程中不反复写设置文件。
+const PERSIST_DEBOUNCE_MS: u64 = 500;
+/// 程序主动切换模式或恢复几何后忽略窗口事件的时长（毫秒），避免这些变化被记到错误的模式下。
+const SUPPRESS_AFTER_APPLY_MS: u64 = 400;
+/// 判定位置是否可见时，锚点相对窗口顶边下移的像素，容忍外框略超出屏幕上沿。
+const ANCHOR_OFFSET_Y: i32 = 8;
+
+/// 窗口几何：外框左上角坐标与外框尺寸，均为物理像素。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+struct PendingGeometry {
+    mode: WindowMode,
+    geometry: WindowGeometry,
+    changed_at: Instant,
+}
+
+#[derive(Default)]
+struct GeometryTracker {
+    pending: Option<PendingGeometry>,
+    suppressed_until: Option<Instant>,
+    initial_restore_done: bool,
+}
+
+/// 窗口几何持久化状态：窗口事件只更新内存中的待写入值，由后台线程防抖后写盘。
+#[derive(Clone, Default)]
+pub struct WindowGeometryState {
+    inner: Arc<Mutex<GeometryTracker>>,
+}
+
+impl WindowGeometryState {
+    /// 记录窗口当前几何；最小化/最大化时的坐标不代表用户摆放的位置，直接忽略。
+    pub fn record(&self, window: &Window, mode: WindowMode) {
+        if window.is_minimized().unwrap_or(true) || window.is_maximized().unwrap_or(true) {
+            return;
+        }
+        let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+            return;
+        };
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
+        };
+        if guard
+            .suppressed_until
+            .is_some_and(|until| Instant::now() < until)
+        {
+            return;
+        }
+
+        guard.pending = Some(PendingGeometry {
+            mode,
+            geometry: WindowGeometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+            },
+            changed_at: Instant::now(),
+        });
+    }
+
+    /// 程序主动调整窗口前调用：短时间内忽略随之而来的窗口事件；已记录的待写入几何仍按原模式写盘。
+    pub fn suppress(&self) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.suppressed_until =
+                Some(Instant::now() + Duration::from_millis(SUPPRESS_AFTER_APPLY_MS));
+        }
+    }
+
+    /// 启动后首次调用返回 `true`，用于在启动时恢复一次几何，之后只在模式切换时恢复。
+    pub fn take_initial_restore(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|mut guard| !std::mem::replace(&mut guard.initial_restore_done, true))
+            .unwrap_or(false)
+    }
+
+    /// 取出已停止变化超过防抖时长的几何。
+    fn take_settled(&self) -> Option<(WindowMode, WindowGeometry)> {
+        let mut guard = self.inner.lock().ok()?;
+        let settled = guard.pending.as_ref().is_some_and(|pending| {
+            pending.changed_at.elapsed() >= Duration::from_millis(PERSIST_DEBOUNCE_MS)
+        });
+        if !settled {
+            return None;
+        }
+        guard
+            .pending
+            .take()
+            .map(|pending| (pending.mode, pending.geometry))
+    }
+}
+
+/// 启动后台写盘线程：几何停止变化超过防抖时长后写入对应模式的设置项。
+pub fn start_geometry_writer(app: AppHandle, state: WindowGeometryState) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(PERSIST_DEBOUNCE_MS / 2));
+        let Some((mode, geometry)) = state.take_settled() else {
+            continue;
+        };
+        if let Err(err) = persist_geometry(&app, mode, geometry) {
+            eprintln!("failed to save window geometry for {mode:?}: {err}");
+        }
+    });
+}
+
+/// 在设置写锁内更新指定模式的几何并写回，同时通知前端。
+fn persist_geometry(
+    app: &AppHandle,
+    mode: WindowMode,
+    geometry: WindowGeometry,
+) -> Result<(), AppError> {
+    settings::update_and_emit(app, |settings| {
+        settings.window_geometry.insert(mode, geometry);
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+/// 恢复保存的几何：位置仍落在某个可用显示器内时原样恢复；显示器已拔出时只恢复尺寸并居中。
+pub fn restore_window_geometry(
+    window: &WebviewWindow,
+    geometry: WindowGeometry,
+) -> Result<(), AppError> {
+    let monitors = window
+        .available_monitors()
+        .map_err(|err| AppError::Window(format!("failed to get monitors: {err}")))?;
+    // 关键行：以顶边中点判定可见性，保证标题栏/拖动区域落在屏幕内，用户总能把窗口拖回来。
+    let anchor_x = geometry.x.saturating_add((geometry.width / 2) as i32);
+    let anchor_y = geometry.y.saturating_add(ANCHOR_OFFSET_Y);
+    let visible = monitors.iter().any(|monitor| {
+        let position = monitor.position();
+        let size = monitor.size();
+        (position.x..position.x.saturating_add(size.width as i32)).contains(&anchor_x)
+            && (position.y..position.y.saturating_add(size.height as i32)).contains(&anchor_y)
+    });
+
+    window
+        .set_size(PhysicalSize::new(geometry.width, geometry.height))
+        .map_err(|err| AppError::Window(format!("failed to resize window: {err}")))?;
+    if visible {
+        window
+            .set_position(PhysicalPosition::new(geometry.x, geometry.y))
+            .map_err(|err| AppError::Window(format!("failed to move window: {err}")))
+    } else {
+        window
+            .center()
+            .map_err(|err| AppError::Window(format!("failed to center window: {err}")))
+    }
+}
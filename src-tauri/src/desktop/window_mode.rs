@@ -1,9 +1,13 @@
-﻿use serde::{Deserialize, Serialize};
+﻿use crate::desktop::click_through::Rect;
+use crate::desktop::opacity::FadeCanceller;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
 
 /// 窗口模式：普通窗口 / 桌面组件 / 悬浮覆盖层。
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum WindowMode {
     #[default]
@@ -22,19 +26,49 @@ impl WindowMode {
         }
     }
 
+    /// 持久化与前端使用的模式字符串，与 `from_raw` 互逆。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::DesktopWidget => "desktopWidget",
+            Self::Overlay => "overlay",
+        }
+    }
 }
 
+/// 显示/隐藏淡入淡出的默认单程时长（毫秒）。
+pub const DEFAULT_WINDOW_FADE_MS: u64 = 150;
+
 /// 窗口行为快照：用于命令层在多状态间保持一致行为。
 #[derive(Debug, Clone, Copy)]
 pub struct WindowBehaviorSnapshot {
     pub mode: WindowMode,
     pub click_through: bool,
+    /// 模式切换时是否用淡入淡出包裹属性变更。
+    pub transition_fade: bool,
+    /// 托盘显示/隐藏窗口时是否淡入淡出，以及单程时长。
+    pub window_fade: bool,
+    pub window_fade_ms: u64,
+    /// 悬浮覆盖层模式下的窗口不透明度。
+    pub overlay_opacity: f32,
+}
+
+impl WindowBehaviorSnapshot {
+    /// 指定模式下窗口应保持的不透明度。
+    pub fn target_opacity(&self, mode: WindowMode) -> f32 {
+        target_opacity(mode, self.overlay_opacity)
+    }
 }
 
 /// 窗口行为运行时状态：共享当前模式和点击穿透配置。
 #[derive(Clone)]
 pub struct WindowBehaviorState {
     inner: Arc<Mutex<WindowBehaviorSnapshot>>,
+    fade_canceller: FadeCanceller,
+    /// 点击穿透开启时仍可交互的窗口区域（逻辑像素，相对窗口客户区左上角）。
+    interactive_regions: Arc<Mutex<Vec<Rect>>>,
+    /// 模式、穿透或交互区域变化时递增，热区跟踪线程据此重新应用穿透状态。
+    revision: Arc<AtomicU64>,
 }
 
 impl WindowBehaviorState {
@@ -44,7 +78,14 @@ impl WindowBehaviorState {
             inner: Arc::new(Mutex::new(WindowBehaviorSnapshot {
                 mode,
                 click_through,
+                transition_fade: false,
+                window_fade: false,
+                window_fade_ms: DEFAULT_WINDOW_FADE_MS,
+                overlay_opacity: 1.0,
             })),
+            fade_canceller: FadeCanceller::default(),
+            interactive_regions: Arc::new(Mutex::new(Vec::new())),
+            revision: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -56,6 +97,10 @@ impl WindowBehaviorState {
             .unwrap_or(WindowBehaviorSnapshot {
                 mode: WindowMode::Normal,
                 click_through: false,
+                transition_fade: false,
+                window_fade: false,
+                window_fade_ms: DEFAULT_WINDOW_FADE_MS,
+                overlay_opacity: 1.0,
             })
     }
 
@@ -64,6 +109,7 @@ impl WindowBehaviorState {
         if let Ok(mut guard) = self.inner.lock() {
             guard.mode = mode;
         }
+        self.bump_revision();
     }
 
     /// 更新点击穿透配置（是否请求穿透，而非是否最终生效）。
@@ -71,6 +117,59 @@ impl WindowBehaviorState {
         if let Ok(mut guard) = self.inner.lock() {
             guard.click_through = enabled;
         }
+        self.bump_revision();
+    }
+
+    /// 替换点击穿透时仍可交互的区域列表，空列表表示整窗穿透。
+    pub fn set_interactive_regions(&self, regions: Vec<Rect>) {
+        if let Ok(mut guard) = self.interactive_regions.lock() {
+            *guard = regions;
+        }
+        self.bump_revision();
+    }
+
+    /// 读取当前的可交互区域列表。
+    pub fn interactive_regions(&self) -> Vec<Rect> {
+        self.interactive_regions
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    /// 当前行为版本号：穿透相关配置任一变化都会使其递增。
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Acquire)
+    }
+
+    fn bump_revision(&self) {
+        self.revision.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// 更新悬浮覆盖层模式的不透明度，调用方负责钳制到合法范围。
+    pub fn set_overlay_opacity(&self, opacity: f32) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.overlay_opacity = opacity;
+        }
+    }
+
+    /// 更新模式切换淡入淡出开关。
+    pub fn set_transition_fade(&self, enabled: bool) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.transition_fade = enabled;
+        }
+    }
+
+    /// 更新显示/隐藏淡入淡出开关与时长。
+    pub fn set_window_fade(&self, enabled: bool, duration_ms: u64) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.window_fade = enabled;
+            guard.window_fade_ms = duration_ms;
+        }
+    }
+
+    /// 显示/隐藏动画共用的取消令牌。
+    pub fn fade_canceller(&self) -> &FadeCanceller {
+        &self.fade_canceller
     }
 }
 
@@ -87,105 +186,120 @@ pub struct MonitorInfo {
     pub is_current: bool,
 }
 
+/// 模式切换完成后窗口应恢复到的不透明度：仅悬浮覆盖层使用 `overlay_opacity`，
+/// 其它模式始终完全不透明，保证切回普通模式后设置界面清晰可读。
+pub fn target_opacity(mode: WindowMode, overlay_opacity: f32) -> f32 {
+    match mode {
+        WindowMode::Overlay => overlay_opacity,
+        WindowMode::Normal | WindowMode::DesktopWidget => 1.0,
+    }
+}
+
 /// 获取主窗口句柄，统一错误文案。
-pub fn main_window(app: &tauri::AppHandle) -> Result<WebviewWindow, String> {
+pub fn main_window(app: &tauri::AppHandle) -> Result<WebviewWindow, AppError> {
     app.get_webview_window("main")
-        .ok_or_else(|| "main window not found".to_string())
+        .ok_or_else(|| AppError::WindowNotFound("main window not found".to_string()))
 }
 
 /// 应用窗口模式策略：不同模式切换窗口层级、装饰和任务栏行为。
-pub fn apply_window_mode(window: &WebviewWindow, mode: WindowMode) -> Result<(), String> {
+pub fn apply_window_mode(window: &WebviewWindow, mode: WindowMode) -> Result<(), AppError> {
     match mode {
         WindowMode::Normal => {
             window
                 .set_decorations(true)
-                .map_err(|err| format!("failed to enable decorations: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to enable decorations: {err}")))?;
             window
                 .set_resizable(true)
-                .map_err(|err| format!("failed to set resizable: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to set resizable: {err}")))?;
             window
                 .set_skip_taskbar(false)
-                .map_err(|err| format!("failed to show taskbar item: {err}"))?;
-            window
-                .set_always_on_bottom(false)
-                .map_err(|err| format!("failed to disable always-on-bottom: {err}"))?;
-            window
-                .set_always_on_top(false)
-                .map_err(|err| format!("failed to disable always-on-top: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to show taskbar item: {err}")))?;
+            window.set_always_on_bottom(false).map_err(|err| {
+                AppError::Window(format!("failed to disable always-on-bottom: {err}"))
+            })?;
+            window.set_always_on_top(false).map_err(|err| {
+                AppError::Window(format!("failed to disable always-on-top: {err}"))
+            })?;
             window
                 .set_focusable(true)
-                .map_err(|err| format!("failed to set focusable: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to set focusable: {err}")))?;
         }
         WindowMode::DesktopWidget => {
             window
                 .set_decorations(false)
-                .map_err(|err| format!("failed to disable decorations: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to disable decorations: {err}")))?;
             window
                 .set_resizable(false)
-                .map_err(|err| format!("failed to set resizable: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to set resizable: {err}")))?;
             window
                 .set_skip_taskbar(true)
-                .map_err(|err| format!("failed to hide taskbar item: {err}"))?;
-            window
-                .set_always_on_top(false)
-                .map_err(|err| format!("failed to disable always-on-top: {err}"))?;
-            window
-                .set_always_on_bottom(true)
-                .map_err(|err| format!("failed to enable always-on-bottom: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to hide taskbar item: {err}")))?;
+            window.set_always_on_top(false).map_err(|err| {
+                AppError::Window(format!("failed to disable always-on-top: {err}"))
+            })?;
+            window.set_always_on_bottom(true).map_err(|err| {
+                AppError::Window(format!("failed to enable always-on-bottom: {err}"))
+            })?;
             window
                 .set_focusable(true)
-                .map_err(|err| format!("failed to set focusable: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to set focusable: {err}")))?;
         }
         WindowMode::Overlay => {
             window
                 .set_decorations(false)
-                .map_err(|err| format!("failed to disable decorations: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to disable decorations: {err}")))?;
             window
                 .set_resizable(false)
-                .map_err(|err| format!("failed to set resizable: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to set resizable: {err}")))?;
             window
                 .set_skip_taskbar(true)
-                .map_err(|err| format!("failed to hide taskbar item: {err}"))?;
-            window
-                .set_always_on_bottom(false)
-                .map_err(|err| format!("failed to disable always-on-bottom: {err}"))?;
-            window
-                .set_always_on_top(true)
-                .map_err(|err| format!("failed to enable always-on-top: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to hide taskbar item: {err}")))?;
+            window.set_always_on_bottom(false).map_err(|err| {
+                AppError::Window(format!("failed to disable always-on-bottom: {err}"))
+            })?;
+            window.set_always_on_top(true).map_err(|err| {
+                AppError::Window(format!("failed to enable always-on-top: {err}"))
+            })?;
             window
                 .set_focusable(true)
-                .map_err(|err| format!("failed to set focusable: {err}"))?;
+                .map_err(|err| AppError::Window(format!("failed to set focusable: {err}")))?;
         }
     }
 
     Ok(())
 }
 
-/// 枚举可用显示器并标记主屏/当前屏，供前端选择目标显示器。
-pub fn list_monitors(window: &WebviewWindow) -> Result<Vec<MonitorInfo>, String> {
-    let monitors = window
+/// 枚举系统当前可用的显示器，统一错误文案。
+fn available_monitors(window: &WebviewWindow) -> Result<Vec<tauri::Monitor>, AppError> {
+    window
         .available_monitors()
-        .map_err(|err| format!("failed to get monitors: {err}"))?;
+        .map_err(|err| AppError::Window(format!("failed to get monitors: {err}")))
+}
+
+/// 枚举可用显示器并标记主屏/当前屏，供前端选择目标显示器。
+pub fn list_monitors(window: &WebviewWindow) -> Result<Vec<MonitorInfo>, AppError> {
+    let monitors = available_monitors(window)?;
     let primary_name = window
         .primary_monitor()
-        .map_err(|err| format!("failed to get primary monitor: {err}"))?
+        .map_err(|err| AppError::Window(format!("failed to get primary monitor: {err}")))?
         .and_then(|monitor| monitor.name().cloned());
     let current_name = window
         .current_monitor()
-        .map_err(|err| format!("failed to get current monitor: {err}"))?
+        .map_err(|err| AppError::Window(format!("failed to get current monitor: {err}")))?
         .and_then(|monitor| monitor.name().cloned());
 
+    let ids = monitor_ids(&monitor_keys(&monitors));
     let items = monitors
         .iter()
+        .zip(ids)
         .enumerate()
-        .map(|(index, monitor)| {
+        .map(|(index, (monitor, id))| {
             let name = monitor
                 .name()
                 .cloned()
                 .unwrap_or_else(|| format!("显示器 {}", index + 1));
             let size = monitor.size();
             let label = format!("{name} ({}x{})", size.width, size.height);
-            let id = monitor_identity(index, monitor);
 
             MonitorInfo {
                 id,
@@ -207,42 +321,271 @@ pub fn list_monitors(window: &WebviewWindow) -> Result<Vec<MonitorInfo>, String>
 }
 
 /// 将窗口移动到目标显示器工作区，尺寸自动裁剪到工作区内。
-pub fn move_window_to_monitor(window: &WebviewWindow, monitor_id: &str) -> Result<(), String> {
-    let monitors = window
-        .available_monitors()
-        .map_err(|err| format!("failed to get monitors: {err}"))?;
-    let maybe_target = monitors
-        .iter()
-        .enumerate()
-        .find(|(index, monitor)| monitor_identity(*index, monitor) == monitor_id);
-
-    let Some((_, target_monitor)) = maybe_target else {
-        return Err(format!("monitor not found: {monitor_id}"));
+///
+/// `rescale` 为真时按源、目标显示器缩放比换算尺寸，保持逻辑尺寸不变（1x 与 2x 屏之间往返对称）；
+/// 否则沿用当前物理像素尺寸。
+///
+/// 旧格式或显示器重排后已不精确匹配的标识按名称/几何尽力匹配，返回目标显示器当前的规范标识。
+pub fn move_window_to_monitor(
+    window: &WebviewWindow,
+    monitor_id: &str,
+    rescale: bool,
+) -> Result<String, AppError> {
+    let monitors = available_monitors(window)?;
+    let keys = monitor_keys(&monitors);
+    let Some(target_index) = resolve_monitor(&keys, monitor_id) else {
+        return Err(AppError::MonitorNotFound(format!(
+            "monitor not found: {monitor_id}"
+        )));
     };
+    let target_monitor = &monitors[target_index];
 
     let work_area = target_monitor.work_area();
     let current_size = window
         .outer_size()
-        .map_err(|err| format!("failed to read window size: {err}"))?;
+        .map_err(|err| AppError::Window(format!("failed to read window size: {err}")))?;
+    let current_size = if rescale {
+        let current_scale = window
+            .scale_factor()
+            .map_err(|err| AppError::Window(format!("failed to read scale factor: {err}")))?;
+        // 关键行：物理尺寸先换算为当前屏的逻辑尺寸，再乘目标屏缩放比，跨屏后视觉大小不变。
+        current_size
+            .to_logical::<f64>(current_scale)
+            .to_physical::<u32>(target_monitor.scale_factor())
+    } else {
+        current_size
+    };
     let width = current_size.width.min(work_area.size.width);
     let height = current_size.height.min(work_area.size.height);
 
     // 关键行：先移动到目标屏工作区左上角，再按工作区限制调整窗口尺寸。
     window
         .set_position(PhysicalPosition::new(work_area.position.x, work_area.position.y))
-        .map_err(|err| format!("failed to move window: {err}"))?;
+        .map_err(|err| AppError::Window(format!("failed to move window: {err}")))?;
     window
         .set_size(PhysicalSize::new(width, height))
-        .map_err(|err| format!("failed to resize window: {err}"))?;
-    Ok(())
+        .map_err(|err| AppError::Window(format!("failed to resize window: {err}")))?;
+    Ok(monitor_ids(&keys).swap_remove(target_index))
+}
+
+/// 窗口吸附位置：四角、四边中点与屏幕中央。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl SnapAnchor {
+    /// 解析 `top-left`、`bottom`、`center` 等吸附位置，忽略大小写与首尾空白；无法识别时返回 `None`。
+    pub fn from_raw(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "top-left" => Some(Self::TopLeft),
+            "top" => Some(Self::Top),
+            "top-right" => Some(Self::TopRight),
+            "left" => Some(Self::Left),
+            "center" => Some(Self::Center),
+            "right" => Some(Self::Right),
+            "bottom-left" => Some(Self::BottomLeft),
+            "bottom" => Some(Self::Bottom),
+            "bottom-right" => Some(Self::BottomRight),
+            _ => None,
+        }
+    }
+
+    /// 水平、垂直方向的对齐方式：-1 贴近起始边，0 居中，1 贴近末尾边。
+    fn alignment(self) -> (i8, i8) {
+        match self {
+            Self::TopLeft => (-1, -1),
+            Self::Top => (0, -1),
+            Self::TopRight => (1, -1),
+            Self::Left => (-1, 0),
+            Self::Center => (0, 0),
+            Self::Right => (1, 0),
+            Self::BottomLeft => (-1, 1),
+            Self::Bottom => (0, 1),
+            Self::BottomRight => (1, 1),
+        }
+    }
+}
+
+/// 把窗口吸附到其当前所在显示器工作区的指定位置，保持窗口尺寸不变，与窗口模式无关。
+///
+/// `margin` 为与工作区边缘的间距（逻辑像素，按该显示器缩放比换算），居中方向不受影响；
+/// 窗口大于工作区或间距过大时贴齐工作区起始边，不会移出工作区。
+pub fn snap_window(
+    window: &WebviewWindow,
+    anchor: SnapAnchor,
+    margin: u32,
+) -> Result<(), AppError> {
+    let monitors = available_monitors(window)?;
+    let position = window
+        .outer_position()
+        .map_err(|err| AppError::Window(format!("failed to read window position: {err}")))?;
+    let size = window
+        .outer_size()
+        .map_err(|err| AppError::Window(format!("failed to read window size: {err}")))?;
+    let monitor = occupied_monitor(&monitors, position, size)
+        .cloned()
+        .or_else(|| window.current_monitor().ok().flatten())
+        .ok_or_else(|| AppError::MonitorNotFound("window is not on any monitor".to_string()))?;
+
+    let work_area = monitor.work_area();
+    let margin = (f64::from(margin) * monitor.scale_factor()).round() as i64;
+    let (horizontal, vertical) = anchor.alignment();
+    let x = snap_axis(
+        work_area.position.x,
+        work_area.size.width,
+        size.width,
+        margin,
+        horizontal,
+    );
+    let y = snap_axis(
+        work_area.position.y,
+        work_area.size.height,
+        size.height,
+        margin,
+        vertical,
+    );
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|err| AppError::Window(format!("failed to snap window: {err}")))
+}
+
+/// 计算单个方向上的吸附坐标，并限制在工作区范围内。
+fn snap_axis(start: i32, extent: u32, window_extent: u32, margin: i64, alignment: i8) -> i32 {
+    let start = i64::from(start);
+    let free = i64::from(extent) - i64::from(window_extent);
+    let offset = match alignment {
+        -1 => margin,
+        0 => free / 2,
+        _ => free - margin,
+    };
+    (start + offset.clamp(0, free.max(0))) as i32
+}
+
+/// 窗口当前所在的显示器：取与窗口矩形重叠面积最大的一块，跨屏时以占比更大的为准；完全不重叠时返回 `None`。
+fn occupied_monitor(
+    monitors: &[tauri::Monitor],
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+) -> Option<&tauri::Monitor> {
+    let overlap = |start: i32, extent: u32, other_start: i32, other_extent: u32| {
+        let low = i64::from(start.max(other_start));
+        let high = (i64::from(start) + i64::from(extent))
+            .min(i64::from(other_start) + i64::from(other_extent));
+        (high - low).max(0)
+    };
+    monitors
+        .iter()
+        .map(|monitor| {
+            let area = overlap(
+                position.x,
+                size.width,
+                monitor.position().x,
+                monitor.size().width,
+            ) * overlap(
+                position.y,
+                size.height,
+                monitor.position().y,
+                monitor.size().height,
+            );
+            (monitor, area)
+        })
+        .filter(|(_, area)| *area > 0)
+        .max_by_key(|(_, area)| *area)
+        .map(|(monitor, _)| monitor)
+}
+
+/// 生成显示器标识所需的名称与几何信息。
+struct MonitorKey {
+    name: Option<String>,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+fn monitor_keys(monitors: &[tauri::Monitor]) -> Vec<MonitorKey> {
+    monitors
+        .iter()
+        .map(|monitor| MonitorKey {
+            name: monitor.name().cloned().filter(|name| !name.is_empty()),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        })
+        .collect()
+}
+
+/// 生成稳定显示器标识：名称唯一时为 `name:{名称}`，不随排列、分辨率或枚举顺序变化；
+/// 名称缺失或重名时退回 `geo:{名称}:{x}:{y}:{宽}:{高}`。
+fn monitor_ids(keys: &[MonitorKey]) -> Vec<String> {
+    keys.iter()
+        .map(|key| {
+            let unique_name = key.name.as_ref().filter(|name| {
+                keys.iter()
+                    .filter(|other| other.name.as_ref() == Some(*name))
+                    .count()
+                    == 1
+            });
+            match unique_name {
+                Some(name) => format!("name:{name}"),
+                None => format!(
+                    "geo:{}:{}:{}:{}:{}",
+                    key.name.as_deref().unwrap_or_default(),
+                    key.x,
+                    key.y,
+                    key.width,
+                    key.height
+                ),
+            }
+        })
+        .collect()
 }
 
-/// 生成稳定显示器标识，避免只依赖名称导致重名冲突。
-fn monitor_identity(index: usize, monitor: &tauri::Monitor) -> String {
-    let position = monitor.position();
-    let size = monitor.size();
-    format!(
-        "{}:{}:{}:{}:{}",
-        index, position.x, position.y, size.width, size.height
-    )
+/// 按标识查找显示器下标：先精确匹配当前标识，再按名称与几何尽力匹配，
+/// 兼容重名状态变化后的新格式标识与旧版 `{序号}:{x}:{y}:{宽}:{高}` 标识。
+fn resolve_monitor(keys: &[MonitorKey], monitor_id: &str) -> Option<usize> {
+    if let Some(index) = monitor_ids(keys).iter().position(|id| id == monitor_id) {
+        return Some(index);
+    }
+    let find = |predicate: &dyn Fn(&MonitorKey) -> bool| keys.iter().position(predicate);
+
+    if let Some(name) = monitor_id.strip_prefix("name:") {
+        // 关键行：原先唯一的名称现在重名（如接入同型号显示器）时取第一块同名屏。
+        return find(&|key| key.name.as_deref() == Some(name));
+    }
+    if let Some(rest) = monitor_id.strip_prefix("geo:") {
+        // 名称可能含冒号，几何字段从右侧取。
+        let mut parts = rest.rsplitn(5, ':');
+        let (height, width, y, x) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let name = parts.next().filter(|name| !name.is_empty());
+        let (x, y) = (x.parse::<i32>().ok()?, y.parse::<i32>().ok()?);
+        let (width, height) = (width.parse::<u32>().ok()?, height.parse::<u32>().ok()?);
+        return find(&|key| key.name.as_deref() == name && key.x == x && key.y == y)
+            .or_else(|| name.and_then(|name| find(&|key| key.name.as_deref() == Some(name))))
+            .or_else(|| {
+                find(&|key| key.width == width && key.height == height && key.x == x && key.y == y)
+            });
+    }
+
+    // 旧版标识：按几何完全匹配，其次按位置匹配，最后按枚举序号。
+    let parts = monitor_id.split(':').collect::<Vec<_>>();
+    let [index, x, y, width, height] = parts.as_slice() else {
+        return None;
+    };
+    let index = index.parse::<usize>().ok()?;
+    let (x, y) = (x.parse::<i32>().ok()?, y.parse::<i32>().ok()?);
+    let (width, height) = (width.parse::<u32>().ok()?, height.parse::<u32>().ok()?);
+    find(&|key| key.x == x && key.y == y && key.width == width && key.height == height)
+        .or_else(|| find(&|key| key.x == x && key.y == y))
+        .or_else(|| (index < keys.len()).then_some(index))
 }
@@ -0,0 +1,98 @@
+use serde::{Serialize, Serializer};
+
+/// 应用统一错误类型：命令层序列化为 `{ code, message }`，前端按 `code` 分支处理与本地化，
+/// `message` 仅用于日志与兜底展示。
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// 找不到可用音频设备。
+    #[error("{0}")]
+    DeviceNotFound(String),
+    /// 目标显示器不存在（可能已断开）。
+    #[error("{0}")]
+    MonitorNotFound(String),
+    /// 指定名称的 DSP 预设不存在。
+    #[error("{0}")]
+    PresetNotFound(String),
+    /// 目标窗口不存在。
+    #[error("{0}")]
+    WindowNotFound(String),
+    /// 设置文件读写失败。
+    #[error("{0}")]
+    SettingsIo(String),
+    /// 设置文件解析或序列化失败。
+    #[error("{0}")]
+    SettingsParse(String),
+    /// 音频采集流创建或启动失败。
+    #[error("{0}")]
+    Capture(String),
+    /// 窗口属性设置失败。
+    #[error("{0}")]
+    Window(String),
+    /// 前端传入的参数非法。
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// 当前平台或设备不支持该操作。
+    #[error("{0}")]
+    Unsupported(String),
+    /// 导出文件失败。
+    #[error("{0}")]
+    Export(String),
+    /// 其他内部错误（事件发送、后台任务异常等）。
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// 稳定的错误码，前端据此分支，不随提示文案变化。
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DeviceNotFound(_) => "DeviceNotFound",
+            Self::MonitorNotFound(_) => "MonitorNotFound",
+            Self::PresetNotFound(_) => "PresetNotFound",
+            Self::WindowNotFound(_) => "WindowNotFound",
+            Self::SettingsIo(_) => "SettingsIo",
+            Self::SettingsParse(_) => "SettingsParse",
+            Self::Capture(_) => "Capture",
+            Self::Window(_) => "Window",
+            Self::InvalidArgument(_) => "InvalidArgument",
+            Self::Unsupported(_) => "Unsupported",
+            Self::Export(_) => "Export",
+            Self::Internal(_) => "Internal",
+        }
+    }
+}
+
+/// 序列化给前端的错误结构。
+#[derive(Serialize)]
+struct AppErrorPayload {
+    code: &'static str,
+    message: String,
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        AppErrorPayload {
+            code: self.code(),
+            message: self.to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_monitor_serializes_stable_code_and_message() {
+        let error = AppError::MonitorNotFound("monitor 2 is not connected".to_string());
+        assert_eq!(error.code(), "MonitorNotFound");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "MonitorNotFound",
+                "message": "monitor 2 is not connected",
+            })
+        );
+    }
+}
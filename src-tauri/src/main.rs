@@ -3,10 +3,15 @@
 mod audio;
 mod commands;
 mod desktop;
+mod error;
+mod params;
 mod settings;
 mod telemetry;
+mod theme;
 
+use desktop::window_geometry::WindowGeometryState;
 use desktop::window_mode::{WindowBehaviorState, WindowMode};
+use error::AppError;
 use tauri::{Emitter, Manager};
 
 #[cfg(desktop)]
@@ -16,66 +21,100 @@ const TRAY_HIDE_ID: &str = "tray_hide";
 #[cfg(desktop)]
 const TRAY_PAUSE_ID: &str = "tray_pause";
 #[cfg(desktop)]
-const TRAY_RESUME_ID: &str = "tray_resume";
-#[cfg(desktop)]
 const TRAY_SETTINGS_ID: &str = "tray_settings";
 #[cfg(desktop)]
-const TRAY_DISABLE_CLICK_THROUGH_ID: &str = "tray_disable_click_through";
+const TRAY_CLICK_THROUGH_ID: &str = "tray_click_through";
 #[cfg(desktop)]
 const TRAY_EXIT_ID: &str = "tray_exit";
 
 #[cfg(desktop)]
-fn show_main_window(app: &tauri::AppHandle) -> Result<(), String> {
+fn show_main_window(app: &tauri::AppHandle) -> Result<(), AppError> {
     let window = desktop::window_mode::main_window(app)?;
-    window
-        .show()
-        .map_err(|err| format!("failed to show main window: {err}"))?;
+    let behavior_state = app.state::<WindowBehaviorState>();
+    let behavior = behavior_state.get();
+    if behavior.window_fade {
+        desktop::opacity::show_with_fade(
+            &window,
+            behavior_state.fade_canceller(),
+            behavior.target_opacity(behavior.mode),
+            behavior.window_fade_ms,
+        )?;
+    } else {
+        // 关键行：未开启淡入时也要取消进行中的淡出，避免窗口显示后又被隐藏。
+        behavior_state.fade_canceller().begin();
+        window
+            .show()
+            .map_err(|err| AppError::Window(format!("failed to show main window: {err}")))?;
+    }
     window
         .set_focus()
-        .map_err(|err| format!("failed to focus main window: {err}"))?;
+        .map_err(|err| AppError::Window(format!("failed to focus main window: {err}")))?;
     Ok(())
 }
 
 #[cfg(desktop)]
-fn hide_main_window(app: &tauri::AppHandle) -> Result<(), String> {
+fn hide_main_window(app: &tauri::AppHandle) -> Result<(), AppError> {
     let window = desktop::window_mode::main_window(app)?;
+    let behavior_state = app.state::<WindowBehaviorState>();
+    let behavior = behavior_state.get();
+    if behavior.window_fade {
+        desktop::opacity::hide_with_fade(
+            &window,
+            behavior_state.fade_canceller(),
+            behavior.target_opacity(behavior.mode),
+            behavior.window_fade_ms,
+        );
+        return Ok(());
+    }
+
+    behavior_state.fade_canceller().begin();
     window
         .hide()
-        .map_err(|err| format!("failed to hide main window: {err}"))
+        .map_err(|err| AppError::Window(format!("failed to hide main window: {err}")))
 }
 
 #[cfg(desktop)]
-fn open_settings_from_tray(app: &tauri::AppHandle) -> Result<(), String> {
+fn open_settings_from_tray(app: &tauri::AppHandle) -> Result<(), AppError> {
     show_main_window(app)?;
     app.emit("app:open_settings", ())
-        .map_err(|err| format!("failed to emit open settings event: {err}"))
+        .map_err(|err| AppError::Internal(format!("failed to emit open settings event: {err}")))
 }
 
 #[cfg(desktop)]
-fn set_visual_paused_from_tray(app: &tauri::AppHandle, paused: bool) -> Result<(), String> {
-    let visual_state = app.state::<telemetry::RuntimeVisualState>();
-    visual_state.set_paused(paused);
-    app.emit("app:visual_paused", paused)
-        .map_err(|err| format!("failed to emit pause event: {err}"))
+fn toggle_visual_paused_from_tray(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let paused = app.state::<telemetry::RuntimeVisualState>().is_paused();
+    desktop::tray_menu::set_visual_paused(app, !paused);
+    Ok(())
 }
 
 #[cfg(desktop)]
-fn disable_click_through_from_tray(app: &tauri::AppHandle) -> Result<(), String> {
+fn toggle_click_through_from_tray(app: &tauri::AppHandle) -> Result<(), AppError> {
     let window = desktop::window_mode::main_window(app)?;
     let behavior_state = app.state::<WindowBehaviorState>();
-    let mode = behavior_state.get().mode;
+    let behavior = behavior_state.get();
+    let enabled = !behavior.click_through;
 
-    desktop::click_through::apply_click_through(&window, mode, false)?;
-    behavior_state.set_click_through(false);
+    // 关键行：失败时也要刷新勾选，撤销菜单点击时系统自动切换的勾选标记。
+    let effective =
+        match desktop::click_through::apply_click_through(&window, behavior.mode, enabled) {
+            Ok(effective) => effective,
+            Err(err) => {
+                desktop::tray_menu::sync_tray_checks(app);
+                return Err(err);
+            }
+        };
+    // 关键行：普通窗口模式会强制关闭穿透，状态、勾选、落盘与事件都以实际生效值为准。
+    behavior_state.set_click_through(effective);
+    desktop::tray_menu::sync_tray_checks(app);
 
-    // 关键行：托盘关闭点击穿透后同步落盘，避免重启后又恢复到穿透状态。
-    if let Ok(mut persisted_settings) = settings::load_settings_from_disk() {
-        persisted_settings.click_through = false;
-        let _ = settings::save_settings_to_disk(&persisted_settings);
-    }
+    // 关键行：托盘切换点击穿透后同步落盘，避免重启后又恢复到之前的状态。
+    let _ = settings::update_and_emit(app, |current| {
+        current.click_through = effective;
+        Ok(())
+    });
 
-    app.emit("app:click_through_changed", false)
-        .map_err(|err| format!("failed to emit click-through event: {err}"))
+    app.emit("app:click_through_changed", effective)
+        .map_err(|err| AppError::Internal(format!("failed to emit click-through event: {err}")))
 }
 
 #[cfg(desktop)]
@@ -83,10 +122,9 @@ fn handle_tray_menu_event(app: &tauri::AppHandle, menu_id: &str) {
     let result = match menu_id {
         TRAY_SHOW_ID => show_main_window(app),
         TRAY_HIDE_ID => hide_main_window(app),
-        TRAY_PAUSE_ID => set_visual_paused_from_tray(app, true),
-        TRAY_RESUME_ID => set_visual_paused_from_tray(app, false),
+        TRAY_PAUSE_ID => toggle_visual_paused_from_tray(app),
         TRAY_SETTINGS_ID => open_settings_from_tray(app),
-        TRAY_DISABLE_CLICK_THROUGH_ID => disable_click_through_from_tray(app),
+        TRAY_CLICK_THROUGH_ID => toggle_click_through_from_tray(app),
         TRAY_EXIT_ID => {
             app.exit(0);
             Ok(())
@@ -100,38 +138,45 @@ fn handle_tray_menu_event(app: &tauri::AppHandle, menu_id: &str) {
 }
 
 #[cfg(desktop)]
-fn setup_tray(app: &tauri::AppHandle) -> Result<(), String> {
-    use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+fn setup_tray(app: &tauri::AppHandle) -> Result<(), AppError> {
+    use desktop::tray_menu::TrayCheckItems;
+    use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
     use tauri::tray::TrayIconBuilder;
 
     let item_show = MenuItem::with_id(app, TRAY_SHOW_ID, "显示主窗口", true, None::<&str>)
-        .map_err(|err| format!("failed to create tray item: {err}"))?;
+        .map_err(|err| AppError::Internal(format!("failed to create tray item: {err}")))?;
     let item_hide = MenuItem::with_id(app, TRAY_HIDE_ID, "隐藏主窗口", true, None::<&str>)
-        .map_err(|err| format!("failed to create tray item: {err}"))?;
-    let item_pause = MenuItem::with_id(app, TRAY_PAUSE_ID, "暂停可视化", true, None::<&str>)
-        .map_err(|err| format!("failed to create tray item: {err}"))?;
-    let item_resume = MenuItem::with_id(app, TRAY_RESUME_ID, "恢复可视化", true, None::<&str>)
-        .map_err(|err| format!("failed to create tray item: {err}"))?;
-    let item_settings =
-        MenuItem::with_id(app, TRAY_SETTINGS_ID, "打开设置", true, None::<&str>)
-            .map_err(|err| format!("failed to create tray item: {err}"))?;
-    let item_disable_click_through = MenuItem::with_id(
+        .map_err(|err| AppError::Internal(format!("failed to create tray item: {err}")))?;
+    // 关键行：暂停与点击穿透用勾选项表示当前状态，点击即切换，初始勾选取自运行时状态。
+    let item_pause = CheckMenuItem::with_id(
+        app,
+        TRAY_PAUSE_ID,
+        "暂停可视化",
+        true,
+        app.state::<telemetry::RuntimeVisualState>().is_paused(),
+        None::<&str>,
+    )
+    .map_err(|err| AppError::Internal(format!("failed to create tray item: {err}")))?;
+    let item_settings = MenuItem::with_id(app, TRAY_SETTINGS_ID, "打开设置", true, None::<&str>)
+        .map_err(|err| AppError::Internal(format!("failed to create tray item: {err}")))?;
+    let item_click_through = CheckMenuItem::with_id(
         app,
-        TRAY_DISABLE_CLICK_THROUGH_ID,
-        "关闭点击穿透",
+        TRAY_CLICK_THROUGH_ID,
+        "点击穿透",
         true,
+        app.state::<WindowBehaviorState>().get().click_through,
         None::<&str>,
     )
-    .map_err(|err| format!("failed to create tray item: {err}"))?;
+    .map_err(|err| AppError::Internal(format!("failed to create tray item: {err}")))?;
     let item_exit = MenuItem::with_id(app, TRAY_EXIT_ID, "退出", true, None::<&str>)
-        .map_err(|err| format!("failed to create tray item: {err}"))?;
+        .map_err(|err| AppError::Internal(format!("failed to create tray item: {err}")))?;
 
-    let separator_1 =
-        PredefinedMenuItem::separator(app).map_err(|err| format!("failed to create separator: {err}"))?;
-    let separator_2 =
-        PredefinedMenuItem::separator(app).map_err(|err| format!("failed to create separator: {err}"))?;
-    let separator_3 =
-        PredefinedMenuItem::separator(app).map_err(|err| format!("failed to create separator: {err}"))?;
+    let separator_1 = PredefinedMenuItem::separator(app)
+        .map_err(|err| AppError::Internal(format!("failed to create separator: {err}")))?;
+    let separator_2 = PredefinedMenuItem::separator(app)
+        .map_err(|err| AppError::Internal(format!("failed to create separator: {err}")))?;
+    let separator_3 = PredefinedMenuItem::separator(app)
+        .map_err(|err| AppError::Internal(format!("failed to create separator: {err}")))?;
 
     let menu = Menu::with_items(
         app,
@@ -140,17 +185,16 @@ fn setup_tray(app: &tauri::AppHandle) -> Result<(), String> {
             &item_hide,
             &separator_1,
             &item_pause,
-            &item_resume,
             &separator_2,
             &item_settings,
-            &item_disable_click_through,
+            &item_click_through,
             &separator_3,
             &item_exit,
         ],
     )
-    .map_err(|err| format!("failed to build tray menu: {err}"))?;
+    .map_err(|err| AppError::Internal(format!("failed to build tray menu: {err}")))?;
 
-    let mut tray_builder = TrayIconBuilder::with_id("main-tray")
+    let mut tray_builder = TrayIconBuilder::with_id(desktop::tray_activity::MAIN_TRAY_ID)
         .tooltip("tt-audio-lab")
         .menu(&menu)
         .show_menu_on_left_click(true);
@@ -161,24 +205,70 @@ fn setup_tray(app: &tauri::AppHandle) -> Result<(), String> {
 
     tray_builder
         .build(app)
-        .map_err(|err| format!("failed to create tray icon: {err}"))?;
+        .map_err(|err| AppError::Internal(format!("failed to create tray icon: {err}")))?;
+    app.manage(TrayCheckItems {
+        paused: item_pause,
+        click_through: item_click_through,
+    });
     Ok(())
 }
 
 fn main() {
-    let initial_settings = settings::load_settings_from_disk().unwrap_or_default();
+    let settings_warnings = settings::SettingsWarningState::default();
+    let initial_settings = match settings::load_settings_with_report() {
+        Ok(loaded) => {
+            if let Some(warning) = loaded.warning {
+                settings_warnings.push(warning);
+            }
+            loaded.settings
+        }
+        Err(_) => settings::AppSettings::default(),
+    };
+    let settings_warnings_for_setup = settings_warnings.clone();
     let initial_window_mode = WindowMode::from_raw(&initial_settings.window_mode);
 
     let runtime_dsp =
         telemetry::RuntimeDspState::new(telemetry::runtime_config_from_settings(&initial_settings));
+    runtime_dsp.set_capture_device_id(&telemetry::capture_target_device_id(&initial_settings));
     let runtime_dsp_for_setup = runtime_dsp.clone();
 
     let runtime_visual = telemetry::RuntimeVisualState::default();
+    runtime_visual.set_animate_tray(initial_settings.animate_tray);
     let runtime_visual_for_setup = runtime_visual.clone();
 
+    let spectrogram_history = telemetry::spectrogram::SpectrogramHistory::default();
+    let spectrogram_history_for_setup = spectrogram_history.clone();
+
+    let theme_state = theme::ThemeState::new(
+        initial_settings.theme.clone(),
+        initial_settings.emit_bin_colors,
+    );
+    let theme_state_for_setup = theme_state.clone();
+
+    let frame_delivery = telemetry::delivery::FrameDeliveryState::default();
+    let frame_delivery_for_setup = frame_delivery.clone();
+
+    let latest_level = telemetry::LatestLevelState::default();
+    let level_meter = telemetry::LevelMeterState::default();
+    let level_meter_for_setup = level_meter.clone();
+    let level_history = telemetry::level_history::LevelHistoryState::default();
+    let level_history_for_setup = level_history.clone();
+
+    let latency_probe = audio::latency::LatencyProbeState::default();
+    let latency_probe_for_setup = latency_probe.clone();
+
+    let capture_status = telemetry::CaptureStatusState::default();
+    let capture_status_for_setup = capture_status.clone();
+    let capture_control = telemetry::CaptureControlState::default();
+    let capture_control_for_setup = capture_control.clone();
+    let recording = audio::recorder::RecordingState::default();
+    let recording_for_setup = recording.clone();
+
     let window_behavior_state =
         WindowBehaviorState::new(initial_window_mode, initial_settings.click_through);
     let window_behavior_for_setup = window_behavior_state.clone();
+    let window_geometry_state = WindowGeometryState::default();
+    let window_geometry_for_setup = window_geometry_state.clone();
     let settings_for_setup = initial_settings.clone();
 
     // 启动实时分析事件流，并在 setup 阶段应用窗口相关初始设置。
@@ -186,22 +276,75 @@ fn main() {
         .manage(runtime_dsp)
         .manage(runtime_visual)
         .manage(window_behavior_state)
+        .manage(spectrogram_history)
+        .manage(theme_state)
+        .manage(settings_warnings)
+        .manage(frame_delivery)
+        .manage(level_meter)
+        .manage(level_history)
+        .manage(latency_probe)
+        .manage(capture_status)
+        .manage(capture_control)
+        .manage(recording)
+        .manage(window_geometry_state)
         .setup(move |app| {
             commands::apply_runtime_window_behavior(
                 app.handle(),
                 &settings_for_setup,
                 &window_behavior_for_setup,
+                &window_geometry_for_setup,
             )?;
+            desktop::window_geometry::start_geometry_writer(
+                app.handle().clone(),
+                window_geometry_for_setup.clone(),
+            );
 
             telemetry::start_analysis_emitter(
                 app.handle().clone(),
-                runtime_dsp_for_setup.clone(),
-                runtime_visual_for_setup.clone(),
+                telemetry::AnalysisShared {
+                    runtime_dsp: runtime_dsp_for_setup.clone(),
+                    runtime_visual: runtime_visual_for_setup.clone(),
+                    history: spectrogram_history_for_setup.clone(),
+                    theme_state: theme_state_for_setup.clone(),
+                    frame_delivery: frame_delivery_for_setup.clone(),
+                    latest_level: latest_level.clone(),
+                    level_meter: level_meter_for_setup.clone(),
+                    level_history: level_history_for_setup.clone(),
+                    latency_probe: latency_probe_for_setup.clone(),
+                    capture_status: capture_status_for_setup.clone(),
+                    capture_control: capture_control_for_setup.clone(),
+                    recording: recording_for_setup.clone(),
+                },
+            );
+            telemetry::hotplug::start_device_watcher(
+                app.handle().clone(),
+                capture_status_for_setup.clone(),
             );
 
             #[cfg(desktop)]
             {
                 setup_tray(app.handle())?;
+                if let Err(error) = desktop::hotkey::apply_pause_hotkey(
+                    app.handle(),
+                    &app.state::<desktop::hotkey::PauseHotkeyState>(),
+                    &settings_for_setup.pause_hotkey,
+                ) {
+                    eprintln!("failed to register pause hotkey: {error}");
+                }
+                desktop::click_through::start_hot_zone_tracker(
+                    app.handle().clone(),
+                    window_behavior_for_setup.clone(),
+                );
+                desktop::tray_activity::start_tray_activity(
+                    app.handle().clone(),
+                    runtime_visual_for_setup.clone(),
+                    latest_level.clone(),
+                );
+            }
+
+            // 关键行：已就绪的监听方可直接收到告警；前端也可通过命令补拉，避免启动时序丢失。
+            for warning in settings_warnings_for_setup.peek_all() {
+                let _ = app.emit(settings::SETTINGS_WARNING_EVENT, warning);
             }
 
             Ok(())
@@ -209,17 +352,65 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             commands::health_check,
             commands::list_audio_devices,
+            commands::get_capture_status,
+            commands::get_capture_info,
+            commands::get_current_levels,
+            commands::get_level_history,
+            commands::get_bin_frequencies,
+            commands::get_recent_devices,
+            commands::set_capture_device,
+            commands::stop_capture,
+            commands::start_capture,
             commands::list_monitors,
             commands::load_settings,
             commands::save_settings,
+            commands::list_presets,
+            commands::save_preset,
+            commands::load_preset,
+            commands::delete_preset,
+            commands::get_autostart_status,
             commands::set_window_mode,
             commands::set_target_monitor,
+            commands::snap_window,
             commands::set_click_through,
+            commands::set_window_opacity,
+            commands::set_interactive_regions,
             commands::set_visual_paused,
-        ]);
+            commands::export_spectrogram,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::start_frame_recording,
+            commands::stop_frame_recording,
+            commands::replay_frames,
+            commands::get_theme,
+            commands::set_theme,
+            commands::take_settings_warnings,
+            commands::benchmark_dsp,
+            commands::introspect_params,
+            commands::set_bin_count,
+            commands::set_window_function,
+            commands::set_eq,
+            commands::set_frame_bin_count,
+            commands::reset_dsp_adaptation,
+            commands::measure_latency,
+        ])
+        .on_window_event(|window, event| {
+            // 关键行：只记录主窗口的移动/缩放，按事件发生时的窗口模式归档，写盘由后台线程防抖。
+            if window.label() == "main"
+                && matches!(
+                    event,
+                    tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_)
+                )
+            {
+                let mode = window.state::<WindowBehaviorState>().get().mode;
+                window.state::<WindowGeometryState>().record(window, mode);
+            }
+        });
 
     #[cfg(desktop)]
     let builder = builder
+        .plugin(desktop::hotkey::plugin())
+        .manage(desktop::hotkey::PauseHotkeyState::default())
         .on_menu_event(|app, event| {
             handle_tray_menu_event(app, event.id().as_ref());
         })
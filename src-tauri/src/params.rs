@@ -0,0 +1,835 @@
+﻿use crate::audio::capture::{ChannelMode, DownmixMode};
+use crate::audio::dsp::{FrequencyScale, ScaleMode, Weighting, WindowFunction};
+use crate::desktop::window_mode::WindowMode;
+use crate::settings::AppSettings;
+use crate::telemetry::BinFormat;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// 数值参数范围：运行时收敛与参数自描述共用同一份常量，保证两者不会漂移。
+#[derive(Debug, Clone, Copy)]
+pub struct ParamRange {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl ParamRange {
+    /// 将数值收敛到范围内。
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.min, self.max)
+    }
+
+    /// 将整数参数收敛到范围内。
+    pub fn clamp_u64(&self, value: u64) -> u64 {
+        value.clamp(self.min as u64, self.max as u64)
+    }
+}
+
+pub const SMOOTHING_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 0.95,
+    step: 0.01,
+};
+pub const ATTACK_RELEASE_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 0.99,
+    step: 0.01,
+};
+pub const BEAT_SENSITIVITY_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 1.0,
+    step: 0.01,
+};
+pub const MIX_GAIN_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 4.0,
+    step: 0.05,
+};
+pub const DB_FLOOR_RANGE: ParamRange = ParamRange {
+    min: -140.0,
+    max: -20.0,
+    step: 1.0,
+};
+pub const PEAK_DECAY_RANGE: ParamRange = ParamRange {
+    min: 0.05,
+    max: 5.0,
+    step: 0.05,
+};
+pub const NOISE_GATE_THRESHOLD_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 0.1,
+    step: 0.0005,
+};
+/// 逐频段门限针对白化前的 FFT 幅度（满幅正弦约 0.23），底噪通常远低于 1e-3。
+pub const BIN_NOISE_GATE_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 0.01,
+    step: 0.0001,
+};
+pub const NOISE_GATE_HOLD_MS_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 10_000.0,
+    step: 50.0,
+};
+/// 下限约 1/3：中心权重不低于两侧，扩散只模糊而不反转频段结构；1 为不扩散。
+pub const DIFFUSION_RANGE: ParamRange = ParamRange {
+    min: 0.34,
+    max: 1.0,
+    step: 0.01,
+};
+/// 重叠分析的 hop（窗长的比例），0 表示关闭、每帧只分析末尾整窗；0.5 即 50% 重叠。
+pub const HOP_FRACTION_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 1.0,
+    step: 0.125,
+};
+/// 分析频率范围的上下限（Hz）；上限超过当前奈奎斯特频率时按奈奎斯特频率截断。
+pub const MIN_HZ_RANGE: ParamRange = ParamRange {
+    min: 1.0,
+    max: 2_000.0,
+    step: 1.0,
+};
+pub const MAX_HZ_RANGE: ParamRange = ParamRange {
+    min: 1_000.0,
+    max: 96_000.0,
+    step: 100.0,
+};
+/// 粉红噪声约为 -3 dB/倍频程，±6 足以覆盖常见的补偿需求。
+pub const TILT_DB_PER_OCTAVE_RANGE: ParamRange = ParamRange {
+    min: -6.0,
+    max: 6.0,
+    step: 0.5,
+};
+pub const EQ_GAIN_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 4.0,
+    step: 0.05,
+};
+pub const GAIN_RANGE: ParamRange = ParamRange {
+    min: 0.2,
+    max: 6.0,
+    step: 0.05,
+};
+pub const GLOBAL_MOTION_WEIGHT_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 2.0,
+    step: 0.05,
+};
+pub const GLOBAL_MOTION_GAMMA_RANGE: ParamRange = ParamRange {
+    min: 0.2,
+    max: 5.0,
+    step: 0.05,
+};
+pub const FFT_AVERAGES_RANGE: ParamRange = ParamRange {
+    min: 1.0,
+    max: 16.0,
+    step: 1.0,
+};
+pub const ZERO_PAD_FACTOR_RANGE: ParamRange = ParamRange {
+    min: 1.0,
+    max: 8.0,
+    step: 1.0,
+};
+pub const BIN_COUNT_RANGE: ParamRange = ParamRange {
+    min: 16.0,
+    max: 512.0,
+    step: 1.0,
+};
+pub const CAPTURE_HOLD_MS_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 5000.0,
+    step: 50.0,
+};
+/// 0 表示沿用设备默认采样率。
+pub const CAPTURE_SAMPLE_RATE_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 384_000.0,
+    step: 100.0,
+};
+/// 0 表示沿用设备默认缓冲大小。
+pub const CAPTURE_BUFFER_FRAMES_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 8_192.0,
+    step: 1.0,
+};
+pub const MAX_ANALYSIS_SAMPLE_RATE_RANGE: ParamRange = ParamRange {
+    min: 32_000.0,
+    max: 384_000.0,
+    step: 1_000.0,
+};
+/// 0 表示关闭固定分析采样率。
+pub const ANALYSIS_SAMPLE_RATE_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 192_000.0,
+    step: 100.0,
+};
+/// 0 表示不限制 IPC 负载。
+pub const IPC_BYTE_BUDGET_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 20_000_000.0,
+    step: 10_000.0,
+};
+pub const WINDOW_FADE_MS_RANGE: ParamRange = ParamRange {
+    min: 50.0,
+    max: 1_000.0,
+    step: 10.0,
+};
+/// 下限保留 0.1，避免覆盖层完全不可见后无从找回。
+pub const OVERLAY_OPACITY_RANGE: ParamRange = ParamRange {
+    min: 0.1,
+    max: 1.0,
+    step: 0.05,
+};
+pub const SILENCE_THRESHOLD_RANGE: ParamRange = ParamRange {
+    min: 0.0,
+    max: 0.1,
+    step: 0.0005,
+};
+pub const SILENCE_TIMEOUT_MS_RANGE: ParamRange = ParamRange {
+    min: 500.0,
+    max: 600_000.0,
+    step: 500.0,
+};
+pub const SILENCE_SCREENSAVER_AFTER_MS_RANGE: ParamRange = ParamRange {
+    min: 1_000.0,
+    max: 600_000.0,
+    step: 1_000.0,
+};
+
+/// 画质档位的合法取值：参数自描述据此渲染下拉框，设置校验据此把未知档位回退到默认值。
+pub const QUALITY_OPTIONS: [&str; 3] = ["ultra", "high", "balanced"];
+/// 窗口模式、频段格式、窗函数与频段刻度的合法取值，供前端渲染下拉框。
+const WINDOW_MODE_OPTIONS: [&str; 3] = ["normal", "desktopWidget", "overlay"];
+const BIN_FORMAT_OPTIONS: [&str; 2] = ["u16", "f32"];
+const WINDOW_FUNCTION_OPTIONS: [&str; 4] = ["hann", "hamming", "blackmanHarris", "flatTop"];
+const SCALE_MODE_OPTIONS: [&str; 2] = ["adaptive", "decibel"];
+const FREQUENCY_SCALE_OPTIONS: [&str; 4] = ["mixedLogLinear", "mel", "linear", "logarithmic"];
+const CHANNEL_MODE_OPTIONS: [&str; 3] = ["mono", "stereo", "midSide"];
+const DOWNMIX_MODE_OPTIONS: [&str; 4] = ["average", "sum", "max", "rms"];
+const WEIGHTING_OPTIONS: [&str; 3] = ["none", "aWeighting", "cWeighting"];
+
+/// 参数所属子系统，前端可据此分组展示。
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParamSubsystem {
+    Dsp,
+    Window,
+    Capture,
+    Visual,
+}
+
+/// 参数值类型，决定前端使用滑块、开关还是下拉框。
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParamKind {
+    Float,
+    Integer,
+    Boolean,
+    Enum,
+    String,
+}
+
+/// 单个运行时参数的自描述信息，`id` 与设置 JSON 的字段名一致。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamDescriptor {
+    pub id: &'static str,
+    pub kind: ParamKind,
+    pub subsystem: ParamSubsystem,
+    pub value: Value,
+    pub default: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<&'static str>>,
+}
+
+impl ParamDescriptor {
+    /// 带范围的数值参数。
+    fn ranged(
+        id: &'static str,
+        kind: ParamKind,
+        subsystem: ParamSubsystem,
+        value: Value,
+        default: Value,
+        range: ParamRange,
+    ) -> Self {
+        Self {
+            id,
+            kind,
+            subsystem,
+            value,
+            default,
+            min: Some(range.min),
+            max: Some(range.max),
+            step: Some(range.step),
+            options: None,
+        }
+    }
+
+    /// 无范围约束的参数（开关、字符串）。
+    fn plain(
+        id: &'static str,
+        kind: ParamKind,
+        subsystem: ParamSubsystem,
+        value: Value,
+        default: Value,
+    ) -> Self {
+        Self {
+            id,
+            kind,
+            subsystem,
+            value,
+            default,
+            min: None,
+            max: None,
+            step: None,
+            options: None,
+        }
+    }
+
+    /// 枚举参数，附带全部合法取值。
+    fn choice(
+        id: &'static str,
+        subsystem: ParamSubsystem,
+        value: Value,
+        default: Value,
+        options: &[&'static str],
+    ) -> Self {
+        Self {
+            options: Some(options.to_vec()),
+            ..Self::plain(id, ParamKind::Enum, subsystem, value, default)
+        }
+    }
+}
+
+/// 列出所有运行时可调参数及其当前值，新增设置项时在此同步登记。
+pub fn describe_params(current: &AppSettings) -> Vec<ParamDescriptor> {
+    let defaults = AppSettings::default();
+    let window_mode = WindowMode::from_raw(&current.window_mode);
+
+    vec![
+        ParamDescriptor::choice(
+            "quality",
+            ParamSubsystem::Visual,
+            json!(current.quality),
+            json!(defaults.quality),
+            &QUALITY_OPTIONS,
+        ),
+        ParamDescriptor::ranged(
+            "smoothing",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(SMOOTHING_RANGE.clamp(current.smoothing)),
+            json!(defaults.smoothing),
+            SMOOTHING_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "attack",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(ATTACK_RELEASE_RANGE.clamp(current.attack.unwrap_or(current.smoothing))),
+            json!(defaults.attack.unwrap_or(defaults.smoothing)),
+            ATTACK_RELEASE_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "release",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(ATTACK_RELEASE_RANGE.clamp(current.release.unwrap_or(current.smoothing))),
+            json!(defaults.release.unwrap_or(defaults.smoothing)),
+            ATTACK_RELEASE_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "gain",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(GAIN_RANGE.clamp(current.gain)),
+            json!(defaults.gain),
+            GAIN_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "beatSensitivity",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(BEAT_SENSITIVITY_RANGE.clamp(current.beat_sensitivity)),
+            json!(defaults.beat_sensitivity),
+            BEAT_SENSITIVITY_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "globalMotionRmsWeight",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(GLOBAL_MOTION_WEIGHT_RANGE.clamp(current.global_motion_rms_weight)),
+            json!(defaults.global_motion_rms_weight),
+            GLOBAL_MOTION_WEIGHT_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "globalMotionPeakWeight",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(GLOBAL_MOTION_WEIGHT_RANGE.clamp(current.global_motion_peak_weight)),
+            json!(defaults.global_motion_peak_weight),
+            GLOBAL_MOTION_WEIGHT_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "globalMotionGamma",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(GLOBAL_MOTION_GAMMA_RANGE.clamp(current.global_motion_gamma)),
+            json!(defaults.global_motion_gamma),
+            GLOBAL_MOTION_GAMMA_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "fftAverages",
+            ParamKind::Integer,
+            ParamSubsystem::Dsp,
+            json!(FFT_AVERAGES_RANGE.clamp_u64(current.fft_averages as u64)),
+            json!(defaults.fft_averages),
+            FFT_AVERAGES_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "zeroPadFactor",
+            ParamKind::Integer,
+            ParamSubsystem::Dsp,
+            json!(ZERO_PAD_FACTOR_RANGE.clamp_u64(current.zero_pad_factor as u64)),
+            json!(defaults.zero_pad_factor),
+            ZERO_PAD_FACTOR_RANGE,
+        ),
+        ParamDescriptor::choice(
+            "windowFunction",
+            ParamSubsystem::Dsp,
+            json!(WindowFunction::from_raw(&current.window_function)),
+            json!(defaults.window_function),
+            &WINDOW_FUNCTION_OPTIONS,
+        ),
+        ParamDescriptor::choice(
+            "scaleMode",
+            ParamSubsystem::Dsp,
+            json!(ScaleMode::from_raw(&current.scale_mode, current.db_floor).name()),
+            json!(defaults.scale_mode),
+            &SCALE_MODE_OPTIONS,
+        ),
+        ParamDescriptor::choice(
+            "frequencyScale",
+            ParamSubsystem::Dsp,
+            json!(FrequencyScale::from_raw(&current.frequency_scale)),
+            json!(defaults.frequency_scale),
+            &FREQUENCY_SCALE_OPTIONS,
+        ),
+        ParamDescriptor::ranged(
+            "minHz",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(MIN_HZ_RANGE.clamp(current.min_hz)),
+            json!(defaults.min_hz),
+            MIN_HZ_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "maxHz",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(MAX_HZ_RANGE.clamp(current.max_hz)),
+            json!(defaults.max_hz),
+            MAX_HZ_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "dbFloor",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(DB_FLOOR_RANGE.clamp(current.db_floor)),
+            json!(defaults.db_floor),
+            DB_FLOOR_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "binCount",
+            ParamKind::Integer,
+            ParamSubsystem::Dsp,
+            json!(BIN_COUNT_RANGE.clamp_u64(current.bin_count as u64)),
+            json!(defaults.bin_count),
+            BIN_COUNT_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "captureHoldMs",
+            ParamKind::Integer,
+            ParamSubsystem::Capture,
+            json!(CAPTURE_HOLD_MS_RANGE.clamp_u64(current.capture_hold_ms)),
+            json!(defaults.capture_hold_ms),
+            CAPTURE_HOLD_MS_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "captureHoldDecay",
+            ParamKind::Boolean,
+            ParamSubsystem::Capture,
+            json!(current.capture_hold_decay),
+            json!(defaults.capture_hold_decay),
+        ),
+        ParamDescriptor::plain(
+            "prefillSampleBuffer",
+            ParamKind::Boolean,
+            ParamSubsystem::Capture,
+            json!(current.prefill_sample_buffer),
+            json!(defaults.prefill_sample_buffer),
+        ),
+        ParamDescriptor::plain(
+            "dcBlocker",
+            ParamKind::Boolean,
+            ParamSubsystem::Dsp,
+            json!(current.dc_blocker),
+            json!(defaults.dc_blocker),
+        ),
+        ParamDescriptor::choice(
+            "weighting",
+            ParamSubsystem::Dsp,
+            json!(Weighting::from_raw(&current.weighting)),
+            json!(defaults.weighting),
+            &WEIGHTING_OPTIONS,
+        ),
+        ParamDescriptor::ranged(
+            "tiltDbPerOctave",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(TILT_DB_PER_OCTAVE_RANGE.clamp(current.tilt_db_per_octave)),
+            json!(defaults.tilt_db_per_octave),
+            TILT_DB_PER_OCTAVE_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "peakHold",
+            ParamKind::Boolean,
+            ParamSubsystem::Dsp,
+            json!(current.peak_hold),
+            json!(defaults.peak_hold),
+        ),
+        ParamDescriptor::ranged(
+            "diffusion",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(DIFFUSION_RANGE.clamp(current.diffusion)),
+            json!(defaults.diffusion),
+            DIFFUSION_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "hopFraction",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(HOP_FRACTION_RANGE.clamp(current.hop_fraction)),
+            json!(defaults.hop_fraction),
+            HOP_FRACTION_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "peakDecay",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(PEAK_DECAY_RANGE.clamp(current.peak_decay)),
+            json!(defaults.peak_decay),
+            PEAK_DECAY_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "noiseGateThreshold",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(NOISE_GATE_THRESHOLD_RANGE.clamp(current.noise_gate_threshold)),
+            json!(defaults.noise_gate_threshold),
+            NOISE_GATE_THRESHOLD_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "noiseGateHoldMs",
+            ParamKind::Integer,
+            ParamSubsystem::Dsp,
+            json!(NOISE_GATE_HOLD_MS_RANGE.clamp_u64(current.noise_gate_hold_ms)),
+            json!(defaults.noise_gate_hold_ms),
+            NOISE_GATE_HOLD_MS_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "binNoiseGate",
+            ParamKind::Float,
+            ParamSubsystem::Dsp,
+            json!(BIN_NOISE_GATE_RANGE.clamp(current.bin_noise_gate)),
+            json!(defaults.bin_noise_gate),
+            BIN_NOISE_GATE_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "pitchDetection",
+            ParamKind::Boolean,
+            ParamSubsystem::Dsp,
+            json!(current.pitch_detection),
+            json!(defaults.pitch_detection),
+        ),
+        ParamDescriptor::plain(
+            "chroma",
+            ParamKind::Boolean,
+            ParamSubsystem::Dsp,
+            json!(current.chroma),
+            json!(defaults.chroma),
+        ),
+        ParamDescriptor::plain(
+            "loudness",
+            ParamKind::Boolean,
+            ParamSubsystem::Dsp,
+            json!(current.loudness),
+            json!(defaults.loudness),
+        ),
+        ParamDescriptor::ranged(
+            "maxAnalysisSampleRate",
+            ParamKind::Integer,
+            ParamSubsystem::Capture,
+            json!(MAX_ANALYSIS_SAMPLE_RATE_RANGE.clamp_u64(current.max_analysis_sample_rate as u64)),
+            json!(defaults.max_analysis_sample_rate),
+            MAX_ANALYSIS_SAMPLE_RATE_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "analysisSampleRate",
+            ParamKind::Integer,
+            ParamSubsystem::Capture,
+            json!(ANALYSIS_SAMPLE_RATE_RANGE.clamp_u64(current.analysis_sample_rate as u64)),
+            json!(defaults.analysis_sample_rate),
+            ANALYSIS_SAMPLE_RATE_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "captureDeviceId",
+            ParamKind::String,
+            ParamSubsystem::Capture,
+            json!(current.capture_device_id),
+            json!(defaults.capture_device_id),
+        ),
+        ParamDescriptor::ranged(
+            "captureSampleRate",
+            ParamKind::Integer,
+            ParamSubsystem::Capture,
+            json!(CAPTURE_SAMPLE_RATE_RANGE.clamp_u64(current.capture_sample_rate as u64)),
+            json!(defaults.capture_sample_rate),
+            CAPTURE_SAMPLE_RATE_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "captureBufferFrames",
+            ParamKind::Integer,
+            ParamSubsystem::Capture,
+            json!(CAPTURE_BUFFER_FRAMES_RANGE.clamp_u64(current.capture_buffer_frames as u64)),
+            json!(defaults.capture_buffer_frames),
+            CAPTURE_BUFFER_FRAMES_RANGE,
+        ),
+        ParamDescriptor::choice(
+            "channelMode",
+            ParamSubsystem::Capture,
+            json!(ChannelMode::from_raw(&current.channel_mode)),
+            json!(defaults.channel_mode),
+            &CHANNEL_MODE_OPTIONS,
+        ),
+        ParamDescriptor::choice(
+            "downmixMode",
+            ParamSubsystem::Capture,
+            json!(DownmixMode::from_raw(&current.downmix_mode)),
+            json!(defaults.downmix_mode),
+            &DOWNMIX_MODE_OPTIONS,
+        ),
+        ParamDescriptor::plain(
+            "mixDefaultInput",
+            ParamKind::Boolean,
+            ParamSubsystem::Capture,
+            json!(current.mix_default_input),
+            json!(defaults.mix_default_input),
+        ),
+        ParamDescriptor::ranged(
+            "mixPrimaryGain",
+            ParamKind::Float,
+            ParamSubsystem::Capture,
+            json!(MIX_GAIN_RANGE.clamp(current.mix_primary_gain)),
+            json!(defaults.mix_primary_gain),
+            MIX_GAIN_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "mixInputGain",
+            ParamKind::Float,
+            ParamSubsystem::Capture,
+            json!(MIX_GAIN_RANGE.clamp(current.mix_input_gain)),
+            json!(defaults.mix_input_gain),
+            MIX_GAIN_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "outputDeviceId",
+            ParamKind::String,
+            ParamSubsystem::Capture,
+            json!(current.output_device_id),
+            json!(defaults.output_device_id),
+        ),
+        ParamDescriptor::choice(
+            "windowMode",
+            ParamSubsystem::Window,
+            json!(window_mode),
+            json!(defaults.window_mode),
+            &WINDOW_MODE_OPTIONS,
+        ),
+        ParamDescriptor::plain(
+            "clickThrough",
+            ParamKind::Boolean,
+            ParamSubsystem::Window,
+            json!(current.click_through),
+            json!(defaults.click_through),
+        ),
+        ParamDescriptor::plain(
+            "targetMonitorId",
+            ParamKind::String,
+            ParamSubsystem::Window,
+            json!(current.target_monitor_id),
+            json!(defaults.target_monitor_id),
+        ),
+        ParamDescriptor::plain(
+            "modeTransitionFade",
+            ParamKind::Boolean,
+            ParamSubsystem::Window,
+            json!(current.mode_transition_fade),
+            json!(defaults.mode_transition_fade),
+        ),
+        ParamDescriptor::plain(
+            "windowFade",
+            ParamKind::Boolean,
+            ParamSubsystem::Window,
+            json!(current.window_fade),
+            json!(defaults.window_fade),
+        ),
+        ParamDescriptor::ranged(
+            "windowFadeMs",
+            ParamKind::Integer,
+            ParamSubsystem::Window,
+            json!(WINDOW_FADE_MS_RANGE.clamp_u64(current.window_fade_ms)),
+            json!(defaults.window_fade_ms),
+            WINDOW_FADE_MS_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "overlayOpacity",
+            ParamKind::Float,
+            ParamSubsystem::Window,
+            json!(OVERLAY_OPACITY_RANGE.clamp(current.overlay_opacity)),
+            json!(defaults.overlay_opacity),
+            OVERLAY_OPACITY_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "launchAtStartup",
+            ParamKind::Boolean,
+            ParamSubsystem::Window,
+            json!(current.launch_at_startup),
+            json!(defaults.launch_at_startup),
+        ),
+        ParamDescriptor::plain(
+            "emitBinColors",
+            ParamKind::Boolean,
+            ParamSubsystem::Visual,
+            json!(current.emit_bin_colors),
+            json!(defaults.emit_bin_colors),
+        ),
+        ParamDescriptor::choice(
+            "binFormat",
+            ParamSubsystem::Visual,
+            json!(BinFormat::from_raw(&current.bin_format)),
+            json!(defaults.bin_format),
+            &BIN_FORMAT_OPTIONS,
+        ),
+        ParamDescriptor::ranged(
+            "ipcByteBudgetPerSec",
+            ParamKind::Integer,
+            ParamSubsystem::Visual,
+            json!(IPC_BYTE_BUDGET_RANGE.clamp_u64(current.ipc_byte_budget_per_sec)),
+            json!(defaults.ipc_byte_budget_per_sec),
+            IPC_BYTE_BUDGET_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "silenceScreensaver",
+            ParamKind::Boolean,
+            ParamSubsystem::Visual,
+            json!(current.silence_screensaver),
+            json!(defaults.silence_screensaver),
+        ),
+        ParamDescriptor::ranged(
+            "silenceScreensaverAfterMs",
+            ParamKind::Integer,
+            ParamSubsystem::Visual,
+            json!(
+                SILENCE_SCREENSAVER_AFTER_MS_RANGE.clamp_u64(current.silence_screensaver_after_ms)
+            ),
+            json!(defaults.silence_screensaver_after_ms),
+            SILENCE_SCREENSAVER_AFTER_MS_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "silenceAutoPause",
+            ParamKind::Boolean,
+            ParamSubsystem::Visual,
+            json!(current.silence_auto_pause),
+            json!(defaults.silence_auto_pause),
+        ),
+        ParamDescriptor::ranged(
+            "silenceThreshold",
+            ParamKind::Float,
+            ParamSubsystem::Visual,
+            json!(SILENCE_THRESHOLD_RANGE.clamp(current.silence_threshold)),
+            json!(defaults.silence_threshold),
+            SILENCE_THRESHOLD_RANGE,
+        ),
+        ParamDescriptor::ranged(
+            "silenceTimeoutMs",
+            ParamKind::Integer,
+            ParamSubsystem::Visual,
+            json!(SILENCE_TIMEOUT_MS_RANGE.clamp_u64(current.silence_timeout_ms)),
+            json!(defaults.silence_timeout_ms),
+            SILENCE_TIMEOUT_MS_RANGE,
+        ),
+        ParamDescriptor::plain(
+            "animateTray",
+            ParamKind::Boolean,
+            ParamSubsystem::Visual,
+            json!(current.animate_tray),
+            json!(defaults.animate_tray),
+        ),
+        ParamDescriptor::plain(
+            "pauseHotkey",
+            ParamKind::String,
+            ParamSubsystem::Visual,
+            json!(current.pause_hotkey),
+            json!(defaults.pause_hotkey),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_descriptor_has_a_concrete_default() {
+        for descriptor in describe_params(&AppSettings::default()) {
+            assert!(
+                !descriptor.default.is_null(),
+                "{} default is null",
+                descriptor.id
+            );
+        }
+    }
+
+    #[test]
+    fn attack_and_release_default_to_smoothing() {
+        let defaults = AppSettings::default();
+        let descriptors = describe_params(&defaults);
+        for id in ["attack", "release"] {
+            let descriptor = descriptors.iter().find(|d| d.id == id).unwrap();
+            assert_eq!(descriptor.default, json!(defaults.smoothing), "{id}");
+        }
+    }
+
+    #[test]
+    fn gain_and_smoothing_are_listed_with_their_ranges() {
+        let descriptors = describe_params(&AppSettings::default());
+        for (id, range) in [("gain", GAIN_RANGE), ("smoothing", SMOOTHING_RANGE)] {
+            let descriptor = descriptors
+                .iter()
+                .find(|d| d.id == id)
+                .unwrap_or_else(|| panic!("{id} is not listed"));
+            assert_eq!(descriptor.min, Some(range.min), "{id}");
+            assert_eq!(descriptor.max, Some(range.max), "{id}");
+            assert_eq!(descriptor.step, Some(range.step), "{id}");
+        }
+    }
+}
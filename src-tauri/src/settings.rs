@@ -1,67 +1,945 @@
-﻿use serde::{Deserialize, Serialize};
+﻿use crate::audio::dsp::{DEFAULT_DIFFUSION, DEFAULT_MAX_HZ, DEFAULT_MIN_HZ};
+use crate::desktop::window_geometry::WindowGeometry;
+use crate::desktop::window_mode::{WindowMode, DEFAULT_WINDOW_FADE_MS};
+use crate::error::AppError;
+use crate::params::{
+    ParamRange, ANALYSIS_SAMPLE_RATE_RANGE, ATTACK_RELEASE_RANGE, BEAT_SENSITIVITY_RANGE,
+    BIN_COUNT_RANGE, BIN_NOISE_GATE_RANGE, CAPTURE_BUFFER_FRAMES_RANGE, CAPTURE_HOLD_MS_RANGE,
+    CAPTURE_SAMPLE_RATE_RANGE, DB_FLOOR_RANGE, DIFFUSION_RANGE, EQ_GAIN_RANGE, FFT_AVERAGES_RANGE,
+    GAIN_RANGE, GLOBAL_MOTION_GAMMA_RANGE, GLOBAL_MOTION_WEIGHT_RANGE, HOP_FRACTION_RANGE,
+    IPC_BYTE_BUDGET_RANGE, MAX_ANALYSIS_SAMPLE_RATE_RANGE, MAX_HZ_RANGE, MIN_HZ_RANGE,
+    MIX_GAIN_RANGE, NOISE_GATE_HOLD_MS_RANGE, NOISE_GATE_THRESHOLD_RANGE, OVERLAY_OPACITY_RANGE,
+    PEAK_DECAY_RANGE, QUALITY_OPTIONS, SILENCE_SCREENSAVER_AFTER_MS_RANGE, SILENCE_THRESHOLD_RANGE,
+    SILENCE_TIMEOUT_MS_RANGE, SMOOTHING_RANGE, TILT_DB_PER_OCTAVE_RANGE, WINDOW_FADE_MS_RANGE,
+    ZERO_PAD_FACTOR_RANGE,
+};
+use crate::theme::Theme;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
 
 const SETTINGS_FILE_NAME: &str = "settings.json";
+/// 后端修改设置后推送的事件，载荷为写盘后的完整设置；前端据此刷新快照，避免用旧快照覆盖后端的改动。
+pub const SETTINGS_CHANGED_EVENT: &str = "app:settings_changed";
+/// 设置告警事件，载荷为告警文本；前端也可通过 `take_settings_warnings` 补拉。
+pub const SETTINGS_WARNING_EVENT: &str = "app:settings_warning";
+/// 设置文件的进程内写锁：所有“读盘 → 修改 → 写盘”都在锁内完成，并发写入方不会互相覆盖。
+static SETTINGS_WRITE_LOCK: Mutex<()> = Mutex::new(());
+/// 当前程序理解的设置结构版本，字段改名或改类型时递增，并在 [`migrate`] 中补一步升级。
+///
+/// - 0：无版本字段的最早格式；
+/// - 1：引入 `version` 字段；
+/// - 2：布尔 `stereo` 改为 `channelMode`、布尔 `aWeighting` 改为 `weighting`。
+pub const SETTINGS_VERSION: u32 = 2;
+/// 最近使用设备列表的最大长度。
+pub const MAX_RECENT_DEVICES: usize = 8;
+
+/// 最近成功用于采集的设备，`name` 为最后一次使用时的设备名。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentDevice {
+    pub id: String,
+    pub name: String,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
 pub struct AppSettings {
+    /// 设置结构版本，读盘时由 `migrate` 逐版本升级并改写为 [`SETTINGS_VERSION`]。
+    pub version: u32,
     pub quality: String,
     pub smoothing: f32,
+    /// 频段上升/回落的独立平滑系数，未设置时沿用 `smoothing`。
+    pub attack: Option<f32>,
+    pub release: Option<f32>,
     pub gain: f32,
+    /// 起音（鼓点）检测灵敏度 0..1，越大越容易触发 `audio:beat` 事件。
+    pub beat_sensitivity: f32,
     pub click_through: bool,
     pub launch_at_startup: bool,
     pub window_mode: String,
     pub target_monitor_id: String,
+    /// 各窗口模式最后一次的窗口位置与尺寸，由窗口移动/缩放事件写入，切换到该模式时恢复。
+    pub window_geometry: BTreeMap<WindowMode, WindowGeometry>,
+    /// 采集设备（`list_audio_devices` 的 `id`），为空或无法解析时使用默认设备。
+    #[serde(alias = "targetDeviceId")]
+    pub capture_device_id: String,
+    /// 采集采样率与固定缓冲帧数，0 表示沿用设备默认值；设备不支持时回退默认采样率。
+    pub capture_sample_rate: u32,
+    pub capture_buffer_frames: u32,
+    /// 声道模式（`mono`、`stereo`、`midSide`）：非单声道时分析帧额外附带左右或中置/侧向频段，默认仅输出单声道。
+    pub channel_mode: String,
+    /// 多声道折叠为单声道的逐声道权重（按设备声道顺序），为空时各声道平均；长度与声道数不符时同样平均。
+    pub downmix_weights: Vec<f32>,
+    /// 折叠方式（`average`、`sum`、`max`、`rms`）：平均会让相关的立体声内容比求和低约 6 dB，
+    /// 觉得画面偏安静时可改为求和（夹到 `[-1, 1]`）；默认平均。
+    pub downmix_mode: String,
+    /// 混合采集：在当前采集来源（默认为系统输出回环）之外同时采集默认输入设备（麦克风），
+    /// 两路按各自增益相加后再分析。
+    pub mix_default_input: bool,
+    pub mix_primary_gain: f32,
+    pub mix_input_gain: f32,
+    pub theme: Theme,
+    pub emit_bin_colors: bool,
+    pub capture_hold_ms: u64,
+    pub capture_hold_decay: bool,
+    pub global_motion_rms_weight: f32,
+    pub global_motion_peak_weight: f32,
+    pub global_motion_gamma: f32,
+    /// 频谱层面的平均窗口数，1 为不平均；与逐帧显示平滑相互独立。
+    pub fft_averages: usize,
+    /// 分析窗补零倍数，1 为不补零；FFT 长度取窗长乘以该值（向上取 2 的幂）。
+    pub zero_pad_factor: usize,
+    /// 分析窗函数：`hann`（默认）、`hamming` 或 `blackmanHarris`。
+    pub window_function: String,
+    /// 频段刻度：`adaptive`（默认，自适应均衡）或 `decibel`（真实 dBFS），以及分贝刻度的下限。
+    pub scale_mode: String,
+    pub db_floor: f32,
+    /// 频段频率映射：`mixedLogLinear`（默认）、`mel`、`linear` 或 `logarithmic`。
+    pub frequency_scale: String,
+    /// 频段映射的频率范围（Hz），范围外的频点不参与映射；上限超过奈奎斯特频率时按奈奎斯特截断。
+    pub min_hz: f32,
+    pub max_hz: f32,
+    /// 分析帧频段数（柱子数量），超宽屏可调高，低性能档位可调低。
+    pub bin_count: usize,
+    pub mode_transition_fade: bool,
+    /// 托盘显示/隐藏窗口时淡入淡出，以及单程时长（毫秒）。
+    pub window_fade: bool,
+    pub window_fade_ms: u64,
+    /// 悬浮覆盖层模式下的窗口不透明度（0.1..=1.0），其它模式始终完全不透明。
+    pub overlay_opacity: f32,
+    /// 分析采样率上限，设备采样率超过时按整数倍抽取，控制高采样率下的 CPU 与延迟。
+    pub max_analysis_sample_rate: u32,
+    /// 固定分析采样率：抽取后再线性插值到该采样率，使频段映射不随设备采样率变化；0 表示关闭。
+    pub analysis_sample_rate: u32,
+    /// 托盘图标是否随当前音量电平明暗变化，窗口隐藏时也能看到音频活动。
+    pub animate_tray: bool,
+    /// 暂停/恢复可视化的全局快捷键（如 `CommandOrControl+Shift+P`），空字符串表示不注册。
+    pub pause_hotkey: String,
+    /// 采集正常但持续静音时是否过渡到缓慢扫频图案，以及进入前的静音时长。
+    pub silence_screensaver: bool,
+    pub silence_screensaver_after_ms: u64,
+    /// 静音自动暂停：RMS 持续低于阈值超过设定时长后发送一帧归零帧并停止发帧，有声音时立即恢复。
+    pub silence_auto_pause: bool,
+    pub silence_threshold: f32,
+    pub silence_timeout_ms: u64,
+    /// 分析帧频段格式：`u16`（默认，量化到 0..1023）或 `f32`（0..1 浮点，完整精度）。
+    pub bin_format: String,
+    /// 分析帧 IPC 每秒字节预算，超出时依次丢弃颜色提示、减少频段数、降低发帧频率；0 为不限制。
+    pub ipc_byte_budget_per_sec: u64,
+    /// 测试音等输出功能使用的输出设备（`"output:{name}"`），为空或设备不存在时使用默认输出。
+    pub output_device_id: String,
+    /// 启动时用静音预填充分析窗口，首帧无需等待整窗真实样本，避免启动空白。
+    pub prefill_sample_buffer: bool,
+    /// 分析前滤除直流偏置（约 10 Hz 一阶高通），避免廉价麦克风的直流偏置抬高 RMS 与最低频段。
+    pub dc_blocker: bool,
+    /// 频率计权（`none`、`aWeighting` 或 `cWeighting`）：压缩前按频段中心频率乘以计权增益，
+    /// 让频段高度更接近人耳感知的响度平衡；默认不计权。
+    pub weighting: String,
+    /// 频谱倾斜（dB/倍频程，以 1 kHz 为中心）：正值提亮高频，可抵消粉红噪声类内容“下沉”的观感；0 为不倾斜。
+    pub tilt_db_per_octave: f32,
+    /// 峰值保持标记与其回落速度（每秒下降的电平，满刻度为 1），默认关闭。
+    pub peak_hold: bool,
+    pub peak_decay: f32,
+    /// 噪声门：RMS 低于阈值持续超过保持时长后频段渐降到零，压住无声时被基线均衡放大的底噪抖动；阈值 0 为关闭。
+    pub noise_gate_threshold: f32,
+    pub noise_gate_hold_ms: u64,
+    /// 逐频段噪声门：白化前幅度低于阈值的频段直接置零，压住静音时低频柱的电源底噪闪烁；0 为关闭。
+    pub bin_noise_gate: f32,
+    /// 相邻频段扩散的中心权重，两侧各取 `(1 - diffusion) / 2`；柱子较少时调高可减少空间模糊，1 为不扩散。
+    pub diffusion: f32,
+    /// 重叠分析的 hop（窗长的比例，如 0.5 为 50% 重叠）：每帧对自上一帧以来按 hop 推进的全部窗口取平均，
+    /// 快速内容不再因只看末尾一窗而在帧间跳变；0 为关闭。
+    pub hop_fraction: f32,
+    /// 可视化图形均衡：等距控制点的幅度倍数（低频在前），按频段线性插值，为空表示平直。
+    pub eq_gains: Vec<f32>,
+    /// 每帧附带自相关估计的基频（调音器类功能），会增加分析开销，默认关闭。
+    pub pitch_detection: bool,
+    /// 每帧附带十二音级色度（C..B，0..1），用于调性/和弦相关的可视化，默认关闭。
+    pub chroma: bool,
+    /// 每帧附带 K 计权积分响度（LUFS，BS.1770 门限），约每 400ms 刷新，比 RMS 开销大，默认关闭。
+    pub loudness: bool,
+    /// 最近成功采集过的设备，最近使用的在前；由后端维护，前端保存设置时不会覆盖。
+    pub recent_devices: Vec<RecentDevice>,
+    /// 命名 DSP 预设与最近一次加载/保存的预设名；由预设命令维护，前端保存设置时不会覆盖。
+    pub presets: BTreeMap<String, DspPreset>,
+    pub active_preset: String,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            version: SETTINGS_VERSION,
             quality: "ultra".to_string(),
             smoothing: 0.58,
+            attack: None,
+            release: None,
             gain: 1.8,
+            beat_sensitivity: 0.5,
             click_through: false,
             launch_at_startup: false,
             window_mode: "normal".to_string(),
             target_monitor_id: String::new(),
+            window_geometry: BTreeMap::new(),
+            capture_device_id: String::new(),
+            capture_sample_rate: 0,
+            capture_buffer_frames: 0,
+            channel_mode: "mono".to_string(),
+            downmix_weights: Vec::new(),
+            downmix_mode: "average".to_string(),
+            mix_default_input: false,
+            mix_primary_gain: 1.0,
+            mix_input_gain: 1.0,
+            theme: Theme::default(),
+            emit_bin_colors: false,
+            capture_hold_ms: 300,
+            capture_hold_decay: true,
+            global_motion_rms_weight: 0.8,
+            global_motion_peak_weight: 0.6,
+            global_motion_gamma: 1.0,
+            fft_averages: 1,
+            zero_pad_factor: 1,
+            window_function: "hann".to_string(),
+            scale_mode: "adaptive".to_string(),
+            db_floor: -80.0,
+            frequency_scale: "mixedLogLinear".to_string(),
+            min_hz: DEFAULT_MIN_HZ,
+            max_hz: DEFAULT_MAX_HZ,
+            bin_count: 64,
+            mode_transition_fade: false,
+            window_fade: false,
+            window_fade_ms: DEFAULT_WINDOW_FADE_MS,
+            overlay_opacity: 1.0,
+            max_analysis_sample_rate: 96_000,
+            analysis_sample_rate: 0,
+            animate_tray: false,
+            pause_hotkey: String::new(),
+            silence_screensaver: false,
+            silence_screensaver_after_ms: 10_000,
+            silence_auto_pause: false,
+            silence_threshold: 0.002,
+            silence_timeout_ms: 5_000,
+            bin_format: "u16".to_string(),
+            ipc_byte_budget_per_sec: 0,
+            output_device_id: String::new(),
+            prefill_sample_buffer: true,
+            dc_blocker: true,
+            weighting: "none".to_string(),
+            tilt_db_per_octave: 0.0,
+            peak_hold: false,
+            peak_decay: 0.8,
+            noise_gate_threshold: 0.0,
+            bin_noise_gate: 0.0,
+            noise_gate_hold_ms: 500,
+            diffusion: DEFAULT_DIFFUSION,
+            hop_fraction: 0.0,
+            eq_gains: Vec::new(),
+            pitch_detection: false,
+            chroma: false,
+            loudness: false,
+            recent_devices: Vec::new(),
+            presets: BTreeMap::new(),
+            active_preset: String::new(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// 把设备移到最近使用列表最前，并截断到 `MAX_RECENT_DEVICES`。
+    pub fn touch_recent_device(&mut self, id: &str, name: &str) {
+        self.recent_devices.retain(|device| device.id != id);
+        self.recent_devices.insert(
+            0,
+            RecentDevice {
+                id: id.to_string(),
+                name: name.to_string(),
+            },
+        );
+        self.recent_devices.truncate(MAX_RECENT_DEVICES);
+    }
+
+    /// 按当前设置截取 DSP 预设。
+    pub fn capture_preset(&self) -> DspPreset {
+        DspPreset {
+            quality: self.quality.clone(),
+            smoothing: self.smoothing,
+            gain: self.gain,
+            bin_count: self.bin_count,
         }
     }
+
+    /// 返回校验后的设置：数值字段收敛到参数自描述中的范围，画质档位与窗口模式规范为已知取值。
+    ///
+    /// 读盘与写盘都会经过这里，手工改坏的设置文件不会原样保留；字符串类的声道模式、窗函数等仍由运行时解析兜底。
+    pub fn validated(mut self) -> Self {
+        let defaults = Self::default();
+        self.quality = normalize_quality(&self.quality, &defaults.quality);
+        self.window_mode = WindowMode::from_raw(self.window_mode.trim())
+            .as_str()
+            .to_string();
+
+        self.smoothing = clamp_f32(SMOOTHING_RANGE, self.smoothing, defaults.smoothing);
+        self.attack = self
+            .attack
+            .filter(|value| value.is_finite())
+            .map(|value| ATTACK_RELEASE_RANGE.clamp(value));
+        self.release = self
+            .release
+            .filter(|value| value.is_finite())
+            .map(|value| ATTACK_RELEASE_RANGE.clamp(value));
+        self.gain = clamp_f32(GAIN_RANGE, self.gain, defaults.gain);
+        self.beat_sensitivity = clamp_f32(
+            BEAT_SENSITIVITY_RANGE,
+            self.beat_sensitivity,
+            defaults.beat_sensitivity,
+        );
+        self.capture_sample_rate =
+            CAPTURE_SAMPLE_RATE_RANGE.clamp_u64(self.capture_sample_rate as u64) as u32;
+        self.capture_buffer_frames =
+            CAPTURE_BUFFER_FRAMES_RANGE.clamp_u64(self.capture_buffer_frames as u64) as u32;
+        self.mix_primary_gain = clamp_f32(
+            MIX_GAIN_RANGE,
+            self.mix_primary_gain,
+            defaults.mix_primary_gain,
+        );
+        self.mix_input_gain =
+            clamp_f32(MIX_GAIN_RANGE, self.mix_input_gain, defaults.mix_input_gain);
+        self.capture_hold_ms = CAPTURE_HOLD_MS_RANGE.clamp_u64(self.capture_hold_ms);
+        self.global_motion_rms_weight = clamp_f32(
+            GLOBAL_MOTION_WEIGHT_RANGE,
+            self.global_motion_rms_weight,
+            defaults.global_motion_rms_weight,
+        );
+        self.global_motion_peak_weight = clamp_f32(
+            GLOBAL_MOTION_WEIGHT_RANGE,
+            self.global_motion_peak_weight,
+            defaults.global_motion_peak_weight,
+        );
+        self.global_motion_gamma = clamp_f32(
+            GLOBAL_MOTION_GAMMA_RANGE,
+            self.global_motion_gamma,
+            defaults.global_motion_gamma,
+        );
+        self.fft_averages = FFT_AVERAGES_RANGE.clamp_u64(self.fft_averages as u64) as usize;
+        self.zero_pad_factor =
+            ZERO_PAD_FACTOR_RANGE.clamp_u64(self.zero_pad_factor as u64) as usize;
+        self.db_floor = clamp_f32(DB_FLOOR_RANGE, self.db_floor, defaults.db_floor);
+        self.bin_count = BIN_COUNT_RANGE.clamp_u64(self.bin_count as u64) as usize;
+        self.window_fade_ms = WINDOW_FADE_MS_RANGE.clamp_u64(self.window_fade_ms);
+        self.overlay_opacity = clamp_f32(
+            OVERLAY_OPACITY_RANGE,
+            self.overlay_opacity,
+            defaults.overlay_opacity,
+        );
+        self.max_analysis_sample_rate =
+            MAX_ANALYSIS_SAMPLE_RATE_RANGE.clamp_u64(self.max_analysis_sample_rate as u64) as u32;
+        self.analysis_sample_rate =
+            ANALYSIS_SAMPLE_RATE_RANGE.clamp_u64(self.analysis_sample_rate as u64) as u32;
+        self.silence_screensaver_after_ms =
+            SILENCE_SCREENSAVER_AFTER_MS_RANGE.clamp_u64(self.silence_screensaver_after_ms);
+        self.silence_threshold = clamp_f32(
+            SILENCE_THRESHOLD_RANGE,
+            self.silence_threshold,
+            defaults.silence_threshold,
+        );
+        self.silence_timeout_ms = SILENCE_TIMEOUT_MS_RANGE.clamp_u64(self.silence_timeout_ms);
+        self.ipc_byte_budget_per_sec =
+            IPC_BYTE_BUDGET_RANGE.clamp_u64(self.ipc_byte_budget_per_sec);
+        self.peak_decay = clamp_f32(PEAK_DECAY_RANGE, self.peak_decay, defaults.peak_decay);
+        self.noise_gate_threshold = clamp_f32(
+            NOISE_GATE_THRESHOLD_RANGE,
+            self.noise_gate_threshold,
+            defaults.noise_gate_threshold,
+        );
+        self.noise_gate_hold_ms = NOISE_GATE_HOLD_MS_RANGE.clamp_u64(self.noise_gate_hold_ms);
+        self.bin_noise_gate = clamp_f32(
+            BIN_NOISE_GATE_RANGE,
+            self.bin_noise_gate,
+            defaults.bin_noise_gate,
+        );
+        self.diffusion = clamp_f32(DIFFUSION_RANGE, self.diffusion, defaults.diffusion);
+        self.tilt_db_per_octave = clamp_f32(
+            TILT_DB_PER_OCTAVE_RANGE,
+            self.tilt_db_per_octave,
+            defaults.tilt_db_per_octave,
+        );
+        self.hop_fraction = clamp_f32(HOP_FRACTION_RANGE, self.hop_fraction, defaults.hop_fraction);
+        self.min_hz = clamp_f32(MIN_HZ_RANGE, self.min_hz, defaults.min_hz);
+        self.max_hz = clamp_f32(MAX_HZ_RANGE, self.max_hz, defaults.max_hz);
+        // 关键行：上下限颠倒时整体回退默认范围，而不是猜测用户想保留哪一端。
+        if self.min_hz >= self.max_hz {
+            self.min_hz = defaults.min_hz;
+            self.max_hz = defaults.max_hz;
+        }
+        // 关键行：只收敛每个控制点，不截断长度；超出上限的曲线仍按平直处理，与运行时一致。
+        for gain in &mut self.eq_gains {
+            *gain = clamp_f32(EQ_GAIN_RANGE, *gain, 1.0);
+        }
+
+        for preset in self.presets.values_mut() {
+            preset.quality = normalize_quality(&preset.quality, &defaults.quality);
+            preset.smoothing = clamp_f32(SMOOTHING_RANGE, preset.smoothing, defaults.smoothing);
+            preset.gain = clamp_f32(GAIN_RANGE, preset.gain, defaults.gain);
+            preset.bin_count = BIN_COUNT_RANGE.clamp_u64(preset.bin_count as u64) as usize;
+        }
+        self
+    }
+
+    /// 把预设中的参数写回设置，其余字段保持不变。
+    pub fn apply_preset(&mut self, preset: &DspPreset) {
+        self.quality = preset.quality.clone();
+        self.smoothing = preset.smoothing;
+        self.gain = preset.gain;
+        self.bin_count = preset.bin_count;
+    }
+}
+
+/// 浮点参数收敛到范围内；非有限值（NaN、无穷）回退到默认值。
+fn clamp_f32(range: ParamRange, value: f32, fallback: f32) -> f32 {
+    if value.is_finite() {
+        range.clamp(value)
+    } else {
+        fallback
+    }
+}
+
+/// 画质档位忽略大小写与首尾空白，未知或空字符串回退到 `fallback`。
+fn normalize_quality(raw: &str, fallback: &str) -> String {
+    let normalized = raw.trim().to_ascii_lowercase();
+    if QUALITY_OPTIONS.contains(&normalized.as_str()) {
+        normalized
+    } else {
+        fallback.to_string()
+    }
+}
+
+/// 规范化预设名：去除首尾空白，空名称视为非法参数。
+pub fn normalize_preset_name(raw: &str) -> Result<String, AppError> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidArgument(
+            "preset name must not be empty".to_string(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// 命名 DSP 预设：只截取画面风格相关参数，切换预设不影响窗口、采集等其他设置。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DspPreset {
+    pub quality: String,
+    pub smoothing: f32,
+    pub gain: f32,
+    pub bin_count: usize,
+}
+
+/// 设置告警队列：启动阶段产生的告警先暂存，待前端就绪后主动拉取。
+#[derive(Clone, Default)]
+pub struct SettingsWarningState {
+    inner: Arc<Mutex<Vec<String>>>,
+}
+
+impl SettingsWarningState {
+    /// 追加一条待提示的设置告警。
+    pub fn push(&self, warning: String) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.push(warning);
+        }
+    }
+
+    /// 读取当前待提示告警但不清空，用于启动阶段的事件广播。
+    pub fn peek_all(&self) -> Vec<String> {
+        self.inner
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    /// 取出并清空所有待提示告警，保证每条只提示一次。
+    pub fn take_all(&self) -> Vec<String> {
+        self.inner
+            .lock()
+            .map(|mut guard| std::mem::take(&mut *guard))
+            .unwrap_or_default()
+    }
 }
 
 /// 解析设置目录并自动创建，统一使用 `%APPDATA%/tt-audio-lab`。
-fn settings_dir() -> Result<PathBuf, String> {
-    let app_data =
-        std::env::var("APPDATA").map_err(|err| format!("APPDATA is not available: {err}"))?;
+fn settings_dir() -> Result<PathBuf, AppError> {
+    let app_data = std::env::var("APPDATA")
+        .map_err(|err| AppError::SettingsIo(format!("APPDATA is not available: {err}")))?;
     let dir = PathBuf::from(app_data).join("tt-audio-lab");
-    fs::create_dir_all(&dir)
-        .map_err(|err| format!("failed to create settings directory: {err}"))?;
+    fs::create_dir_all(&dir).map_err(|err| {
+        AppError::SettingsIo(format!("failed to create settings directory: {err}"))
+    })?;
     Ok(dir)
 }
 
 /// 设置文件路径：`%APPDATA%/tt-audio-lab/settings.json`。
-fn settings_path() -> Result<PathBuf, String> {
+fn settings_path() -> Result<PathBuf, AppError> {
     Ok(settings_dir()?.join(SETTINGS_FILE_NAME))
 }
 
+/// 设置加载结果：附带需要提示用户的告警（例如降级后遇到新版本配置）。
+pub struct LoadedSettings {
+    pub settings: AppSettings,
+    pub warning: Option<String>,
+}
+
 /// 加载设置，文件不存在时返回默认设置，保证首次运行可用。
-pub fn load_settings_from_disk() -> Result<AppSettings, String> {
+pub fn load_settings_from_disk() -> Result<AppSettings, AppError> {
+    load_settings_with_report().map(|loaded| loaded.settings)
+}
+
+/// 加载设置并报告告警，规则见 [`load_settings_at`]；在写锁内执行，备份与写回不会与写入方交错。
+pub fn load_settings_with_report() -> Result<LoadedSettings, AppError> {
     let path = settings_path()?;
+    let _guard = lock_settings();
+    load_settings_at(&path)
+}
+
+/// 加载指定路径的设置：
+/// - 文件版本高于当前程序时，先备份原文件（后续保存不会覆盖新版配置），再尽力按当前结构读取仍认识的字段，
+///   并立即写回，之后的写入方读到的就是这份设置而不是默认值；
+/// - 文件无法解析（JSON 损坏或字段类型不符）时备份原文件并使用默认设置，写入方不会因坏文件永久失败。
+///
+/// 两种情况都会返回告警。调用方需持有写锁。
+fn load_settings_at(path: &Path) -> Result<LoadedSettings, AppError> {
     if !path.exists() {
-        return Ok(AppSettings::default());
+        return Ok(LoadedSettings {
+            settings: AppSettings::default(),
+            warning: None,
+        });
+    }
+
+    let raw = fs::read_to_string(path)
+        .map_err(|err| AppError::SettingsIo(format!("failed to read settings: {err}")))?;
+    let value = match serde_json::from_str::<serde_json::Value>(&raw) {
+        Ok(value) => value,
+        Err(err) => return recover_unreadable_file(path, &format!("invalid json: {err}")),
+    };
+
+    let file_version = settings_file_version(&value);
+    if file_version > SETTINGS_VERSION {
+        let backup_path = backup_settings_file(path, &format!("v{file_version}"))?;
+        // 关键行：新版本文件只做尽力读取，字段类型对不上时退回默认设置而不是报错。
+        let (settings, outcome) = match migrate(value) {
+            Ok(settings) => (settings, "recognized fields loaded"),
+            Err(err) => {
+                eprintln!("failed to read newer settings file: {err}");
+                (AppSettings::default(), "defaults loaded")
+            }
+        };
+        // 关键行：原文件已移走，立即写回读取结果，否则首个写入方会在空文件上从默认值开始。
+        save_settings_at(path, &settings)?;
+        let warning = format!(
+            "settings version {file_version} is newer than supported version {SETTINGS_VERSION}; \
+             original file preserved at {} and {outcome}",
+            backup_path.display()
+        );
+        eprintln!("{warning}");
+        return Ok(LoadedSettings {
+            settings,
+            warning: Some(warning),
+        });
     }
 
-    let raw = fs::read_to_string(&path).map_err(|err| format!("failed to read settings: {err}"))?;
-    serde_json::from_str::<AppSettings>(&raw)
-        .map_err(|err| format!("failed to parse settings json: {err}"))
+    match migrate(value) {
+        Ok(settings) => Ok(LoadedSettings {
+            settings,
+            warning: None,
+        }),
+        Err(AppError::SettingsParse(reason)) => recover_unreadable_file(path, &reason),
+        Err(err) => Err(err),
+    }
+}
+
+/// 备份无法解析的设置文件并以默认设置继续，返回带告警的加载结果。
+fn recover_unreadable_file(path: &Path, reason: &str) -> Result<LoadedSettings, AppError> {
+    let backup_path = backup_settings_file(path, "corrupt")?;
+    let warning = format!(
+        "settings file could not be read ({reason}); original file preserved at {} and defaults loaded",
+        backup_path.display()
+    );
+    eprintln!("{warning}");
+    Ok(LoadedSettings {
+        settings: AppSettings::default(),
+        warning: Some(warning),
+    })
 }
 
-/// 保存设置为格式化 JSON，便于本地排障和手工调整参数。
-pub fn save_settings_to_disk(settings: &AppSettings) -> Result<(), String> {
+/// 把任意版本的原始设置 JSON 逐版本升级到 [`SETTINGS_VERSION`] 的结构后反序列化并校验。
+///
+/// 每一步只处理比它旧的文件；高于当前版本的文件不做升级，直接按当前结构读取认识的字段。
+fn migrate(mut raw: serde_json::Value) -> Result<AppSettings, AppError> {
+    let file_version = settings_file_version(&raw);
+    if file_version < 2 {
+        migrate_v1_to_v2(&mut raw);
+    }
+
+    let mut settings = serde_json::from_value::<AppSettings>(raw)
+        .map_err(|err| AppError::SettingsParse(format!("failed to parse settings json: {err}")))?
+        .validated();
+    // 关键行：升级后统一标记为当前版本，下次保存即写出新结构。
+    settings.version = SETTINGS_VERSION;
+    Ok(settings)
+}
+
+/// 版本 2 的升级：布尔 `stereo` 开关转为 `channelMode`、布尔 `aWeighting` 转为 `weighting`，
+/// 新字段已存在时以新字段为准。
+fn migrate_v1_to_v2(value: &mut serde_json::Value) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    if let Some(a_weighting) = object.remove("aWeighting") {
+        if !object.contains_key("weighting") {
+            let weighting = if a_weighting.as_bool().unwrap_or(false) {
+                "aWeighting"
+            } else {
+                "none"
+            };
+            object.insert("weighting".to_string(), serde_json::Value::from(weighting));
+        }
+    }
+    if let Some(stereo) = object.remove("stereo") {
+        if !object.contains_key("channelMode") {
+            let mode = if stereo.as_bool().unwrap_or(false) {
+                "stereo"
+            } else {
+                "mono"
+            };
+            object.insert("channelMode".to_string(), serde_json::Value::from(mode));
+        }
+    }
+}
+
+/// 读取设置文件中的版本号，缺失时视为无版本字段的旧格式（0）。
+fn settings_file_version(value: &serde_json::Value) -> u32 {
+    value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .map_or(0, |version| version.min(u32::MAX as u64) as u32)
+}
+
+/// 原样移走设置文件（`label` 标明原因，如新版本号或 `corrupt`），后续保存不会覆盖它；同名备份已存在时追加时间戳。
+fn backup_settings_file(path: &Path, label: &str) -> Result<PathBuf, AppError> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| AppError::SettingsIo("settings path has no parent directory".to_string()))?;
+    let mut backup_path = dir.join(format!("settings.{label}.backup.json"));
+    if backup_path.exists() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        backup_path = dir.join(format!("settings.{label}.{timestamp}.backup.json"));
+    }
+
+    fs::rename(path, &backup_path)
+        .map_err(|err| AppError::SettingsIo(format!("failed to back up settings file: {err}")))?;
+    Ok(backup_path)
+}
+
+/// 获取设置写锁。写锁只保护文件内容，持锁线程 panic 后数据仍是完整的 JSON，继续使用即可。
+fn lock_settings() -> MutexGuard<'static, ()> {
+    SETTINGS_WRITE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 在写锁内读取磁盘设置、交给 `apply` 修改后校验写回，返回写盘后的设置；`apply` 出错时不写盘。
+/// 读盘时产生的告警（例如备份了损坏的文件）随结果一并返回。
+///
+/// 所有修改设置文件的代码都应经过这里，不要自行组合读盘与写盘。
+pub fn update(
+    apply: impl FnOnce(&mut AppSettings) -> Result<(), AppError>,
+) -> Result<LoadedSettings, AppError> {
     let path = settings_path()?;
-    let content = serde_json::to_string_pretty(settings)
-        .map_err(|err| format!("failed to serialize settings: {err}"))?;
-    fs::write(path, content).map_err(|err| format!("failed to write settings file: {err}"))?;
+    let _guard = lock_settings();
+    update_at(&path, apply)
+}
+
+/// [`update`] 的实现，调用方需持有写锁。
+fn update_at(
+    path: &Path,
+    apply: impl FnOnce(&mut AppSettings) -> Result<(), AppError>,
+) -> Result<LoadedSettings, AppError> {
+    let LoadedSettings {
+        mut settings,
+        warning,
+    } = load_settings_at(path)?;
+    apply(&mut settings)?;
+    let settings = settings.validated();
+    save_settings_at(path, &settings)?;
+    Ok(LoadedSettings { settings, warning })
+}
+
+/// 同 [`update`]，写盘后通过 [`SETTINGS_CHANGED_EVENT`] 把完整设置推送给前端；
+/// 读盘告警加入 [`SettingsWarningState`] 并通过 [`SETTINGS_WARNING_EVENT`] 推送。
+pub fn update_and_emit(
+    app: &AppHandle,
+    apply: impl FnOnce(&mut AppSettings) -> Result<(), AppError>,
+) -> Result<AppSettings, AppError> {
+    let LoadedSettings { settings, warning } = update(apply)?;
+    if let Some(warning) = warning {
+        if let Some(warnings) = app.try_state::<SettingsWarningState>() {
+            warnings.push(warning.clone());
+        }
+        let _ = app.emit(SETTINGS_WARNING_EVENT, warning);
+    }
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, &settings);
+    Ok(settings)
+}
+
+/// 记录一次成功的设备采集：更新最近使用列表后写回并通知前端。
+pub fn record_recent_device(app: &AppHandle, id: &str, name: &str) -> Result<(), AppError> {
+    update_and_emit(app, |settings| {
+        settings.touch_recent_device(id, name);
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+/// 校验后保存设置为格式化 JSON，便于本地排障和手工调整参数；调用方需持有写锁，见 [`update`]。
+fn save_settings_at(path: &Path, settings: &AppSettings) -> Result<(), AppError> {
+    let content = serde_json::to_string_pretty(&settings.clone().validated())
+        .map_err(|err| AppError::SettingsParse(format!("failed to serialize settings: {err}")))?;
+    fs::write(path, content)
+        .map_err(|err| AppError::SettingsIo(format!("failed to write settings file: {err}")))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_f32_clamps_and_replaces_non_finite() {
+        assert_eq!(clamp_f32(GAIN_RANGE, 100.0, 1.8), GAIN_RANGE.max);
+        assert_eq!(clamp_f32(GAIN_RANGE, -1.0, 1.8), GAIN_RANGE.min);
+        assert_eq!(clamp_f32(GAIN_RANGE, 2.5, 1.8), 2.5);
+        assert_eq!(clamp_f32(GAIN_RANGE, f32::NAN, 1.8), 1.8);
+        assert_eq!(clamp_f32(GAIN_RANGE, f32::INFINITY, 1.8), 1.8);
+    }
+
+    #[test]
+    fn normalize_quality_accepts_known_tiers_only() {
+        assert_eq!(normalize_quality("  High ", "ultra"), "high");
+        assert_eq!(normalize_quality("balanced", "ultra"), "balanced");
+        assert_eq!(normalize_quality("", "ultra"), "ultra");
+        assert_eq!(normalize_quality("cinematic", "ultra"), "ultra");
+    }
+
+    #[test]
+    fn touch_recent_device_moves_used_device_to_front() {
+        let mut settings = AppSettings::default();
+        settings.touch_recent_device("a", "Mic A");
+        settings.touch_recent_device("b", "Mic B");
+        settings.touch_recent_device("a", "Mic A (USB)");
+
+        // 关键行：再次使用的设备移到最前且不重复，名称更新为最后一次使用时的名称。
+        let ids = settings
+            .recent_devices
+            .iter()
+            .map(|device| device.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(settings.recent_devices[0].name, "Mic A (USB)");
+    }
+
+    #[test]
+    fn touch_recent_device_caps_the_list() {
+        let mut settings = AppSettings::default();
+        for index in 0..MAX_RECENT_DEVICES + 3 {
+            settings.touch_recent_device(&format!("dev-{index}"), "Device");
+        }
+
+        assert_eq!(settings.recent_devices.len(), MAX_RECENT_DEVICES);
+        let newest = format!("dev-{}", MAX_RECENT_DEVICES + 2);
+        assert_eq!(settings.recent_devices[0].id, newest);
+        assert!(settings
+            .recent_devices
+            .iter()
+            .all(|device| device.id != "dev-0"));
+    }
+
+    #[test]
+    fn validated_keeps_defaults_unchanged() {
+        let defaults = AppSettings::default();
+        let validated = AppSettings::default().validated();
+        assert_eq!(
+            serde_json::to_value(&validated).unwrap(),
+            serde_json::to_value(&defaults).unwrap()
+        );
+    }
+
+    #[test]
+    fn validated_repairs_out_of_range_and_unknown_values() {
+        let defaults = AppSettings::default();
+        let settings = AppSettings {
+            quality: "cinematic".to_string(),
+            window_mode: "fullscreen".to_string(),
+            smoothing: f32::NAN,
+            attack: Some(f32::NAN),
+            release: Some(5.0),
+            gain: 100.0,
+            bin_count: 4,
+            eq_gains: vec![-1.0, f32::NAN, 10.0],
+            ..AppSettings::default()
+        }
+        .validated();
+
+        assert_eq!(settings.quality, defaults.quality);
+        assert_eq!(settings.window_mode, "normal");
+        assert_eq!(settings.smoothing, defaults.smoothing);
+        assert_eq!(settings.attack, None);
+        assert_eq!(settings.release, Some(ATTACK_RELEASE_RANGE.max));
+        assert_eq!(settings.gain, GAIN_RANGE.max);
+        assert_eq!(settings.bin_count, BIN_COUNT_RANGE.min as usize);
+        assert_eq!(
+            settings.eq_gains,
+            [EQ_GAIN_RANGE.min, 1.0, EQ_GAIN_RANGE.max]
+        );
+    }
+
+    #[test]
+    fn validated_normalizes_known_window_mode() {
+        let settings = AppSettings {
+            window_mode: " overlay ".to_string(),
+            ..AppSettings::default()
+        }
+        .validated();
+        assert_eq!(settings.window_mode, "overlay");
+    }
+
+    #[test]
+    fn validated_resets_inverted_frequency_range() {
+        let defaults = AppSettings::default();
+        let settings = AppSettings {
+            min_hz: 1_500.0,
+            max_hz: 1_200.0,
+            ..AppSettings::default()
+        }
+        .validated();
+        assert_eq!(
+            (settings.min_hz, settings.max_hz),
+            (defaults.min_hz, defaults.max_hz)
+        );
+    }
+
+    #[test]
+    fn migrate_converts_legacy_boolean_switches() {
+        for legacy in [
+            serde_json::json!({ "stereo": true, "aWeighting": true }),
+            serde_json::json!({ "version": 1, "stereo": true, "aWeighting": true }),
+        ] {
+            let settings = migrate(legacy).unwrap();
+            assert_eq!(settings.channel_mode, "stereo");
+            assert_eq!(settings.weighting, "aWeighting");
+            assert_eq!(settings.version, SETTINGS_VERSION);
+        }
+
+        // 关键行：新字段已存在时以新字段为准，旧开关只被移除。
+        let settings = migrate(serde_json::json!({
+            "version": 1,
+            "stereo": true,
+            "channelMode": "midSide",
+            "aWeighting": true,
+            "weighting": "cWeighting",
+        }))
+        .unwrap();
+        assert_eq!(settings.channel_mode, "midSide");
+        assert_eq!(settings.weighting, "cWeighting");
+    }
+
+    #[test]
+    fn validated_repairs_presets() {
+        let mut settings = AppSettings::default();
+        settings.presets.insert(
+            "broken".to_string(),
+            DspPreset {
+                quality: String::new(),
+                smoothing: f32::INFINITY,
+                gain: 0.0,
+                bin_count: 10_000,
+            },
+        );
+        let preset = settings.validated().presets.remove("broken").unwrap();
+        let defaults = AppSettings::default();
+        assert_eq!(preset.quality, defaults.quality);
+        assert_eq!(preset.smoothing, defaults.smoothing);
+        assert_eq!(preset.gain, GAIN_RANGE.min);
+        assert_eq!(preset.bin_count, BIN_COUNT_RANGE.max as usize);
+    }
+
+    /// 每个测试独占的临时设置目录，返回其中的 `settings.json` 路径。
+    fn temp_settings_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "tt-audio-lab-settings-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(SETTINGS_FILE_NAME)
+    }
+
+    #[test]
+    fn corrupt_file_does_not_block_update() {
+        let path = temp_settings_path("corrupt");
+        for corrupt in ["{ not json", r#"{"version": 2, "gain": "loud"}"#] {
+            fs::write(&path, corrupt).unwrap();
+            let updated = update_at(&path, |settings| {
+                settings.gain = 2.5;
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(updated.settings.gain, 2.5);
+            assert!(updated.warning.is_some(), "{corrupt}");
+
+            // 关键行：坏文件原样保留为备份，新文件可以正常读回。
+            let backups = fs::read_dir(path.parent().unwrap())
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().contains("corrupt"))
+                .map(|entry| fs::read_to_string(entry.path()).unwrap())
+                .collect::<Vec<_>>();
+            assert!(backups.iter().any(|backup| backup == corrupt));
+            let reloaded = load_settings_at(&path).unwrap();
+            assert_eq!(reloaded.settings.gain, 2.5);
+            assert!(reloaded.warning.is_none());
+        }
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn newer_version_file_is_backed_up_and_recognized_fields_persist() {
+        let path = temp_settings_path("forward");
+        let future = format!(
+            r#"{{"version": {}, "gain": 3.5, "futureField": true}}"#,
+            SETTINGS_VERSION + 1
+        );
+        fs::write(&path, &future).unwrap();
+        let loaded = load_settings_at(&path).unwrap();
+        assert_eq!(loaded.settings.gain, 3.5);
+        assert!(loaded.warning.is_some());
+
+        // 关键行：原文件原样保留为备份，读取结果已写回，后续写入方不会从默认值开始。
+        let backup = path
+            .parent()
+            .unwrap()
+            .join(format!("settings.v{}.backup.json", SETTINGS_VERSION + 1));
+        assert_eq!(fs::read_to_string(backup).unwrap(), future);
+        let updated = update_at(&path, |settings| {
+            settings.touch_recent_device("input:mic", "Mic");
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(updated.settings.gain, 3.5);
+        assert!(updated.warning.is_none());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
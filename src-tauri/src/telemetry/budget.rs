@@ -0,0 +1,257 @@
+use super::{AnalysisFrame, FrameBins};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// IPC 预算调整事件名：调整等级变化时推送一次，前端据此提示当前降级状态。
+pub const IPC_BUDGET_ADAPTED_EVENT: &str = "audio:ipc_budget_adapted";
+/// 预算不足时频段数最多减半到该值，再往下就改为降低发帧频率。
+const MIN_BUDGET_BIN_COUNT: usize = 16;
+
+/// 预算不足时可整体丢弃的可选字段，按丢弃顺序排列：体积大且可由前端自行推算的在前，
+/// 小而独立的特征值在后。全部丢弃后仍超预算才减少频段数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OptionalField {
+    /// 逐频段颜色提示（`binColors`）。
+    BinColors,
+    /// 峰值保持频段（`peakBins`）。
+    PeakBins,
+    /// 立体声左右声道频段（`binsLeft`/`binsRight`），成对丢弃。
+    ChannelBins,
+    /// 十二音级色度（`chroma`）。
+    Chroma,
+    /// 积分响度（`lufs`）。
+    Lufs,
+    /// 基频估计（`pitchHz`）。
+    PitchHz,
+}
+
+const DROP_ORDER: [OptionalField; 6] = [
+    OptionalField::BinColors,
+    OptionalField::PeakBins,
+    OptionalField::ChannelBins,
+    OptionalField::Chroma,
+    OptionalField::Lufs,
+    OptionalField::PitchHz,
+];
+
+impl OptionalField {
+    /// 从帧中移除该字段，返回帧中原本是否带有它。
+    fn take_from(self, frame: &mut AnalysisFrame) -> bool {
+        match self {
+            Self::BinColors => frame.bin_colors.take().is_some(),
+            Self::PeakBins => frame.peak_bins.take().is_some(),
+            Self::ChannelBins => {
+                let left = frame.bins_left.take().is_some();
+                let right = frame.bins_right.take().is_some();
+                left || right
+            }
+            Self::Chroma => frame.chroma.take().is_some(),
+            Self::Lufs => frame.lufs.take().is_some(),
+            Self::PitchHz => frame.pitch_hz.take().is_some(),
+        }
+    }
+}
+
+/// 当前生效的 IPC 预算调整。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpcBudgetAdaptation {
+    /// 按丢弃顺序列出被丢弃的可选字段。
+    pub dropped_fields: Vec<OptionalField>,
+    /// 降低后的频段数；`None` 表示保持分析器原始频段数。
+    pub bin_count: Option<usize>,
+    /// 实际发帧间隔（毫秒），大于设置值表示已降低发帧频率。
+    pub emit_interval_ms: u64,
+    /// 调整后单帧的估算序列化字节数。
+    pub frame_bytes: u64,
+}
+
+/// IPC 字节预算调节器：估算每帧序列化体积，按“可选字段 -> 频段数 -> 发帧频率”的顺序逐级降载。
+///
+/// 每帧都从完整帧重新评估，负载下降后自动恢复，不需要额外的回升逻辑。
+#[derive(Default)]
+pub(super) struct IpcBudgetGovernor {
+    last: Option<IpcBudgetAdaptation>,
+}
+
+impl IpcBudgetGovernor {
+    /// 按预算调整帧内容并返回下一帧应使用的发帧间隔；`budget_per_sec` 为 0 表示不限制。
+    pub(super) fn apply(
+        &mut self,
+        app: &AppHandle,
+        frame: &mut AnalysisFrame,
+        budget_per_sec: u64,
+        emit_interval_ms: u64,
+    ) -> u64 {
+        if budget_per_sec == 0 {
+            if self.last.take().is_some() {
+                let _ = app.emit(IPC_BUDGET_ADAPTED_EVENT, None::<IpcBudgetAdaptation>);
+            }
+            return emit_interval_ms;
+        }
+
+        let adaptation = fit_frame_to_budget(frame, budget_per_sec, emit_interval_ms);
+        let interval = adaptation.emit_interval_ms;
+        let adapted = !adaptation.dropped_fields.is_empty()
+            || adaptation.bin_count.is_some()
+            || adaptation.emit_interval_ms > emit_interval_ms;
+        let current = adapted.then_some(adaptation);
+
+        // 关键行：只比较降载等级，单帧体积的细微波动不触发事件。
+        let level = |value: &Option<IpcBudgetAdaptation>| {
+            value.as_ref().map(|value| {
+                (
+                    value.dropped_fields.clone(),
+                    value.bin_count,
+                    value.emit_interval_ms,
+                )
+            })
+        };
+        if level(&current) != level(&self.last) {
+            let _ = app.emit(IPC_BUDGET_ADAPTED_EVENT, current.clone());
+        }
+        self.last = current;
+        interval
+    }
+}
+
+/// 逐级降载直到 `单帧字节数 × 每秒帧数` 不超过预算，返回实际采用的调整。
+///
+/// 按订阅方定制的帧也经过这里，只取其中的内容裁剪，发帧间隔由规范帧决定。
+pub(super) fn fit_frame_to_budget(
+    frame: &mut AnalysisFrame,
+    budget_per_sec: u64,
+    emit_interval_ms: u64,
+) -> IpcBudgetAdaptation {
+    let interval = emit_interval_ms.max(1);
+    let fits = |bytes: u64| bytes * 1000 / interval <= budget_per_sec;
+    let mut adaptation = IpcBudgetAdaptation {
+        dropped_fields: Vec::new(),
+        bin_count: None,
+        emit_interval_ms: interval,
+        frame_bytes: estimate_frame_bytes(frame),
+    };
+
+    // 关键行：先逐个丢弃可选字段，每丢一个重新估算，够用即停，不多丢。
+    for field in DROP_ORDER {
+        if fits(adaptation.frame_bytes) {
+            break;
+        }
+        if field.take_from(frame) {
+            adaptation.dropped_fields.push(field);
+            adaptation.frame_bytes = estimate_frame_bytes(frame);
+        }
+    }
+
+    while !fits(adaptation.frame_bytes) {
+        let current = bin_len(&frame.bins);
+        let next = (current / 2).max(MIN_BUDGET_BIN_COUNT);
+        if next >= current {
+            break;
+        }
+        frame.bins = frame.bins.resample(next);
+        adaptation.bin_count = Some(next);
+        adaptation.frame_bytes = estimate_frame_bytes(frame);
+    }
+
+    if !fits(adaptation.frame_bytes) {
+        // 关键行：最后一级按预算反推最小发帧间隔，向上取整保证不超预算。
+        adaptation.emit_interval_ms = (adaptation.frame_bytes * 1000).div_ceil(budget_per_sec);
+    }
+    adaptation
+}
+
+/// 估算帧的 JSON 序列化体积（字节）。
+fn estimate_frame_bytes(frame: &AnalysisFrame) -> u64 {
+    serde_json::to_vec(frame).map_or(0, |bytes| bytes.len() as u64)
+}
+
+fn bin_len(bins: &FrameBins) -> usize {
+    match bins {
+        FrameBins::U16(bins) => bins.len(),
+        FrameBins::F32(levels) => levels.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::FrameSource;
+
+    const BINS: usize = 256;
+
+    /// 开启全部可选特征的帧：颜色、立体声、峰值保持、色度、响度与音高。
+    fn full_frame() -> AnalysisFrame {
+        let levels = vec![0.5f32; BINS];
+        AnalysisFrame {
+            timestamp_ms: 0,
+            device_id: "loopback:default".to_string(),
+            source: FrameSource::Loopback,
+            bins: FrameBins::F32(levels.clone()),
+            rms: 0.1,
+            peak: 0.2,
+            centroid: 0.3,
+            latency_estimate_ms: 10.0,
+            silent: false,
+            clip_count: 0,
+            bins_left: Some(FrameBins::F32(levels.clone())),
+            bins_right: Some(FrameBins::F32(levels.clone())),
+            peak_bins: Some(FrameBins::F32(levels)),
+            pitch_hz: Some(440.0),
+            chroma: Some([0.5; 12]),
+            lufs: Some(-14.0),
+            bin_colors: Some(vec![[255, 128, 0]; BINS]),
+        }
+    }
+
+    #[test]
+    fn generous_budget_keeps_the_full_frame() {
+        let mut frame = full_frame();
+        let adaptation = fit_frame_to_budget(&mut frame, 100_000_000, 16);
+        assert!(adaptation.dropped_fields.is_empty());
+        assert_eq!(adaptation.bin_count, None);
+        assert!(frame.bin_colors.is_some() && frame.pitch_hz.is_some());
+    }
+
+    #[test]
+    fn optional_fields_are_dropped_before_bins_are_halved() {
+        let mut frame = full_frame();
+        // 关键行：预算只够主频段本身，可选字段必须全部让路，但主频段不应被减半。
+        let core = {
+            let mut core = full_frame();
+            for field in DROP_ORDER {
+                field.take_from(&mut core);
+            }
+            estimate_frame_bytes(&core)
+        };
+        let adaptation = fit_frame_to_budget(&mut frame, core * 1000 / 16, 16);
+        assert_eq!(adaptation.dropped_fields, DROP_ORDER);
+        assert_eq!(adaptation.bin_count, None);
+        assert_eq!(bin_len(&frame.bins), BINS);
+        assert!(frame.bins_left.is_none() && frame.peak_bins.is_none() && frame.lufs.is_none());
+    }
+
+    #[test]
+    fn colors_go_first_and_shedding_stops_once_it_fits() {
+        let mut frame = full_frame();
+        let without_colors = {
+            let mut frame = full_frame();
+            frame.bin_colors = None;
+            estimate_frame_bytes(&frame)
+        };
+        let adaptation = fit_frame_to_budget(&mut frame, without_colors * 1000 / 16, 16);
+        assert_eq!(adaptation.dropped_fields, [OptionalField::BinColors]);
+        assert!(frame.peak_bins.is_some() && frame.chroma.is_some());
+    }
+
+    #[test]
+    fn tight_budget_halves_bins_then_slows_the_frame_rate() {
+        let mut frame = full_frame();
+        let adaptation = fit_frame_to_budget(&mut frame, 1_000, 16);
+        assert_eq!(adaptation.dropped_fields, DROP_ORDER);
+        assert_eq!(adaptation.bin_count, Some(MIN_BUDGET_BIN_COUNT));
+        assert!(adaptation.emit_interval_ms > 16);
+        assert!(adaptation.frame_bytes * 1000 / adaptation.emit_interval_ms <= 1_000);
+    }
+}
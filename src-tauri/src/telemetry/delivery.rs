@@ -0,0 +1,368 @@
+use super::{budget, AnalysisFrame, FrameBins};
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, EventTarget};
+
+/// 分析帧事件名，所有发帧路径共用。
+pub const ANALYSIS_FRAME_EVENT: &str = "audio:analysis_frame";
+/// 单个订阅方允许覆盖的频段数范围。
+pub const MIN_SUBSCRIBER_BIN_COUNT: usize = 4;
+pub const MAX_SUBSCRIBER_BIN_COUNT: usize = 1024;
+/// 回放时相邻两帧的最大等待时长（毫秒），录制中途暂停造成的长间隔不原样等待。
+const MAX_REPLAY_GAP_MS: u64 = 1_000;
+
+/// 进行中的帧录制：JSONL 写入器与已写入帧数。
+struct FrameRecording {
+    path: String,
+    writer: BufWriter<File>,
+    frames: u64,
+}
+
+/// 按订阅方定制频段数：键为窗口标签，值为该窗口期望的频段数。
+///
+/// 覆盖只保存在内存中，应用重启或订阅方主动清除后恢复规范频段数。
+///
+/// 同时负责帧录制与回放：实时链路与模拟链路都经由 [`emit_analysis_frame`] 推送，在此录制即可覆盖所有来源。
+#[derive(Clone, Default)]
+pub struct FrameDeliveryState {
+    overrides: Arc<Mutex<HashMap<String, usize>>>,
+    recording: Arc<Mutex<Option<FrameRecording>>>,
+    /// 回放进行中：分析线程照常运行，但实时帧不再推送，避免与回放帧交错。
+    replaying: Arc<AtomicBool>,
+}
+
+impl FrameDeliveryState {
+    /// 设置或清除（`None`）指定窗口的频段数覆盖。
+    pub fn set_bin_count(&self, label: &str, bin_count: Option<usize>) -> Result<(), AppError> {
+        if let Some(count) = bin_count {
+            if !(MIN_SUBSCRIBER_BIN_COUNT..=MAX_SUBSCRIBER_BIN_COUNT).contains(&count) {
+                return Err(AppError::InvalidArgument(format!(
+                    "bin count must be within {MIN_SUBSCRIBER_BIN_COUNT}..={MAX_SUBSCRIBER_BIN_COUNT}, got {count}"
+                )));
+            }
+        }
+
+        let mut guard = self
+            .overrides
+            .lock()
+            .map_err(|_| AppError::Internal("frame delivery state is poisoned".to_string()))?;
+        match bin_count {
+            Some(count) => {
+                guard.insert(label.to_string(), count);
+            }
+            None => {
+                guard.remove(label);
+            }
+        }
+        Ok(())
+    }
+
+    /// 开始把推送的每个分析帧按一行一个 JSON 对象写入 `path`，返回写入路径；已有录制进行时返回错误。
+    pub fn start_recording(&self, path: &str) -> Result<String, AppError> {
+        let trimmed_path = path.trim();
+        if trimmed_path.is_empty() {
+            return Err(AppError::InvalidArgument(
+                "frame recording path must not be empty".to_string(),
+            ));
+        }
+        let mut guard = self
+            .recording
+            .lock()
+            .map_err(|_| AppError::Internal("frame recording state is poisoned".to_string()))?;
+        if guard.is_some() {
+            return Err(AppError::InvalidArgument(
+                "a frame recording is already in progress".to_string(),
+            ));
+        }
+
+        let output_path = Path::new(trimmed_path);
+        if let Some(parent) = output_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|err| {
+                AppError::Export(format!("failed to create frame recording directory: {err}"))
+            })?;
+        }
+        let file = File::create(output_path)
+            .map_err(|err| AppError::Export(format!("failed to create frame recording: {err}")))?;
+        *guard = Some(FrameRecording {
+            path: trimmed_path.to_string(),
+            writer: BufWriter::new(file),
+            frames: 0,
+        });
+        Ok(trimmed_path.to_string())
+    }
+
+    /// 停止帧录制并刷新文件，返回写入的帧数；没有进行中的录制时返回错误。
+    pub fn stop_recording(&self) -> Result<u64, AppError> {
+        let recording = self
+            .recording
+            .lock()
+            .map_err(|_| AppError::Internal("frame recording state is poisoned".to_string()))?
+            .take()
+            .ok_or_else(|| {
+                AppError::InvalidArgument("no frame recording in progress".to_string())
+            })?;
+        let FrameRecording {
+            path,
+            mut writer,
+            frames,
+        } = recording;
+        writer.flush().map_err(|err| {
+            AppError::Export(format!("failed to flush frame recording {path}: {err}"))
+        })?;
+        Ok(frames)
+    }
+
+    /// 录制一帧；写入失败时结束录制，不影响推送。
+    fn record(&self, frame: &AnalysisFrame) {
+        // 关键行：只尝试加锁，命令线程正在启停录制时宁可漏录一帧，也不阻塞分析线程。
+        let Ok(mut guard) = self.recording.try_lock() else {
+            return;
+        };
+        let Some(recording) = guard.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut recording.writer, frame)
+            .map_err(|err| err.to_string())
+            .and_then(|()| {
+                recording
+                    .writer
+                    .write_all(b"\n")
+                    .map_err(|err| err.to_string())
+            });
+        match written {
+            Ok(()) => recording.frames += 1,
+            Err(err) => {
+                eprintln!("frame recording {} stopped: {err}", recording.path);
+                *guard = None;
+            }
+        }
+    }
+
+    /// 按原始时间间隔把 JSONL 中的分析帧重新推送到 `audio:analysis_frame`，返回回放的帧数。
+    ///
+    /// 回放期间实时帧暂停推送；回放帧原样发给全部订阅方，不应用按窗口的频段数覆盖。阻塞到回放结束。
+    pub fn replay(&self, app: &AppHandle, path: &str) -> Result<u64, AppError> {
+        let file = File::open(path.trim()).map_err(|err| {
+            AppError::InvalidArgument(format!("failed to open frame recording: {err}"))
+        })?;
+        if self
+            .replaying
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(AppError::InvalidArgument(
+                "a frame replay is already in progress".to_string(),
+            ));
+        }
+        let result = replay_lines(app, BufReader::new(file));
+        self.replaying.store(false, Ordering::Release);
+        result
+    }
+
+    /// 读取当前全部覆盖的快照。
+    fn snapshot(&self) -> HashMap<String, usize> {
+        self.overrides
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// 推送分析帧：无覆盖的订阅方收到规范帧，有覆盖的窗口单独收到重采样后的帧。
+///
+/// 定制帧只能投递给以窗口作用域监听的订阅方；全局监听（`EventTarget::Any`）始终收到规范帧。
+/// 规范帧已由调用方按 IPC 预算降载；定制帧的频段数可能高于规范帧，按同一预算（`budget_per_sec`，
+/// 0 为不限制）与实际发帧间隔 `emit_interval_ms` 再裁剪一次。
+pub(super) fn emit_analysis_frame(
+    app: &AppHandle,
+    delivery: &FrameDeliveryState,
+    frame: AnalysisFrame,
+    budget_per_sec: u64,
+    emit_interval_ms: u64,
+) {
+    if delivery.replaying.load(Ordering::Acquire) {
+        return;
+    }
+    delivery.record(&frame);
+    let overrides = delivery.snapshot();
+    if overrides.is_empty() {
+        let _ = app.emit(ANALYSIS_FRAME_EVENT, frame);
+        return;
+    }
+
+    let _ = app.emit_filter(ANALYSIS_FRAME_EVENT, &frame, |target| {
+        target_label(target).is_none_or(|label| !overrides.contains_key(label))
+    });
+
+    for (label, bin_count) in overrides {
+        let mut tailored = tailor_frame(&frame, bin_count);
+        if budget_per_sec > 0 {
+            budget::fit_frame_to_budget(&mut tailored, budget_per_sec, emit_interval_ms);
+        }
+        let _ = app.emit_to(
+            EventTarget::WebviewWindow { label },
+            ANALYSIS_FRAME_EVENT,
+            tailored,
+        );
+    }
+}
+
+/// 逐行解析并推送回放帧，按相邻帧 `timestampMs` 之差等待；空行跳过，非法行返回错误。
+fn replay_lines(app: &AppHandle, reader: impl BufRead) -> Result<u64, AppError> {
+    let mut previous_ts = None;
+    let mut frames = 0u64;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| {
+            AppError::InvalidArgument(format!("failed to read frame recording: {err}"))
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str::<serde_json::Value>(&line).map_err(|err| {
+            AppError::InvalidArgument(format!(
+                "frame recording line {} is not valid json: {err}",
+                index + 1
+            ))
+        })?;
+
+        let timestamp_ms = frame.get("timestampMs").and_then(serde_json::Value::as_u64);
+        if let (Some(previous), Some(current)) = (previous_ts, timestamp_ms) {
+            let gap_ms = current.saturating_sub(previous).min(MAX_REPLAY_GAP_MS);
+            thread::sleep(Duration::from_millis(gap_ms));
+        }
+        previous_ts = timestamp_ms.or(previous_ts);
+
+        app.emit(ANALYSIS_FRAME_EVENT, frame)
+            .map_err(|err| AppError::Internal(format!("failed to emit replay frame: {err}")))?;
+        frames += 1;
+    }
+    Ok(frames)
+}
+
+/// 提取带标签事件目标的标签。
+fn target_label(target: &EventTarget) -> Option<&str> {
+    match target {
+        EventTarget::AnyLabel { label }
+        | EventTarget::Window { label }
+        | EventTarget::Webview { label }
+        | EventTarget::WebviewWindow { label } => Some(label),
+        _ => None,
+    }
+}
+
+/// 生成指定频段数的帧副本；颜色提示按最近邻取样，保持与频段一一对应。
+fn tailor_frame(frame: &AnalysisFrame, bin_count: usize) -> AnalysisFrame {
+    let source_len = match &frame.bins {
+        FrameBins::U16(bins) => bins.len(),
+        FrameBins::F32(levels) => levels.len(),
+    };
+    let bin_colors = frame.bin_colors.as_ref().map(|colors| {
+        (0..bin_count)
+            .filter_map(|index| {
+                let source = if bin_count > 1 {
+                    (index as f32 * source_len.saturating_sub(1) as f32 / (bin_count - 1) as f32)
+                        .round() as usize
+                } else {
+                    0
+                };
+                colors.get(source).copied()
+            })
+            .collect()
+    });
+
+    AnalysisFrame {
+        bins: frame.bins.resample(bin_count),
+        bins_left: frame
+            .bins_left
+            .as_ref()
+            .map(|bins| bins.resample(bin_count)),
+        bins_right: frame
+            .bins_right
+            .as_ref()
+            .map(|bins| bins.resample(bin_count)),
+        peak_bins: frame
+            .peak_bins
+            .as_ref()
+            .map(|bins| bins.resample(bin_count)),
+        device_id: frame.device_id.clone(),
+        bin_colors,
+        ..*frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::FrameSource;
+
+    const SOURCE_BINS: usize = 64;
+
+    fn ramp_frame() -> AnalysisFrame {
+        let levels = (0..SOURCE_BINS)
+            .map(|index| index as f32 / (SOURCE_BINS - 1) as f32)
+            .collect::<Vec<_>>();
+        AnalysisFrame {
+            timestamp_ms: 0,
+            device_id: "mic".to_string(),
+            source: FrameSource::Realtime,
+            bins: FrameBins::F32(levels),
+            rms: 0.1,
+            peak: 0.2,
+            centroid: 0.3,
+            latency_estimate_ms: 10.0,
+            silent: false,
+            clip_count: 0,
+            bins_left: None,
+            bins_right: None,
+            peak_bins: Some(FrameBins::U16((0..SOURCE_BINS as u16).collect())),
+            pitch_hz: None,
+            chroma: None,
+            lufs: None,
+            bin_colors: Some((0..SOURCE_BINS as u8).map(|index| [index, 0, 0]).collect()),
+        }
+    }
+
+    fn assert_tailored(bin_count: usize) {
+        let tailored = tailor_frame(&ramp_frame(), bin_count);
+
+        let FrameBins::F32(levels) = &tailored.bins else {
+            panic!("bins changed format");
+        };
+        assert_eq!(levels.len(), bin_count);
+        // 关键行：首尾频段对齐，中间线性插值，斜坡保持单调。
+        assert_eq!(levels[0], 0.0);
+        assert_eq!(levels[bin_count - 1], 1.0);
+        assert!(levels.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        let Some(FrameBins::U16(peaks)) = &tailored.peak_bins else {
+            panic!("peak bins changed format");
+        };
+        assert_eq!(peaks.len(), bin_count);
+        assert_eq!(peaks[bin_count - 1], SOURCE_BINS as u16 - 1);
+
+        let colors = tailored.bin_colors.as_ref().unwrap();
+        assert_eq!(colors.len(), bin_count);
+        assert_eq!(colors[0], [0, 0, 0]);
+        assert_eq!(colors[bin_count - 1], [SOURCE_BINS as u8 - 1, 0, 0]);
+    }
+
+    #[test]
+    fn tailor_frame_downsamples_64_to_32() {
+        assert_tailored(32);
+    }
+
+    #[test]
+    fn tailor_frame_upsamples_64_to_128() {
+        assert_tailored(128);
+    }
+}
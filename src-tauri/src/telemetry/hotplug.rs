@@ -0,0 +1,121 @@
+use super::CaptureStatusState;
+use crate::audio::capture::{self, AudioDeviceInfo};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 设备列表变化事件名。
+pub const DEVICES_CHANGED_EVENT: &str = "audio:devices_changed";
+/// 设备列表轮询间隔。
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `audio:devices_changed` 事件负载。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DevicesChanged {
+    devices: Vec<AudioDeviceInfo>,
+    /// 当前正在采集的设备（可选设备标识），模拟数据阶段为 `None`。
+    capturing_device_id: Option<String>,
+}
+
+/// 设备列表防抖：记录上次推送的列表与待确认的变化，插拔过程中的中间状态不会触发事件。
+struct DeviceListDebounce {
+    emitted: Vec<AudioDeviceInfo>,
+    pending: Option<Vec<AudioDeviceInfo>>,
+}
+
+impl DeviceListDebounce {
+    fn new(initial: Vec<AudioDeviceInfo>) -> Self {
+        Self {
+            emitted: initial,
+            pending: None,
+        }
+    }
+
+    /// 记录一次轮询结果；变化已稳定时返回需要推送的新列表。
+    fn observe(&mut self, current: Vec<AudioDeviceInfo>) -> Option<Vec<AudioDeviceInfo>> {
+        if current == self.emitted {
+            self.pending = None;
+            return None;
+        }
+        // 关键行：插拔过程中枚举结果会连续变化，连续两次轮询一致才推送，合并为一次事件。
+        if self.pending.as_ref() != Some(&current) {
+            self.pending = Some(current);
+            return None;
+        }
+
+        self.pending = None;
+        self.emitted = current.clone();
+        Some(current)
+    }
+}
+
+/// 启动设备热插拔监视线程：轮询设备列表，变化稳定后向前端推送新列表。
+pub fn start_device_watcher(app: AppHandle, capture_status: CaptureStatusState) {
+    thread::spawn(move || {
+        let mut debounce =
+            DeviceListDebounce::new(capture::list_audio_devices().unwrap_or_default());
+
+        loop {
+            thread::sleep(DEVICE_POLL_INTERVAL);
+            let Some(devices) = debounce.observe(capture::list_audio_devices().unwrap_or_default())
+            else {
+                continue;
+            };
+
+            let capturing_device_id = capture_status
+                .get()
+                .filter(|status| !status.is_mock)
+                .map(|status| capture::selectable_device_id(&status.device_id));
+            let _ = app.emit(
+                DEVICES_CHANGED_EVENT,
+                DevicesChanged {
+                    devices,
+                    capturing_device_id,
+                },
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices(names: &[&str]) -> Vec<AudioDeviceInfo> {
+        names
+            .iter()
+            .map(|name| AudioDeviceInfo {
+                id: format!("output:{name}"),
+                name: name.to_string(),
+                direction: "output".to_string(),
+                is_default: false,
+                default_sample_rate: 48_000,
+                channels: 2,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stable_change_is_reported_once_after_two_polls() {
+        let mut debounce = DeviceListDebounce::new(devices(&["Speakers"]));
+        let plugged = devices(&["Speakers", "Headset"]);
+
+        assert_eq!(debounce.observe(plugged.clone()), None);
+        assert_eq!(debounce.observe(plugged.clone()), Some(plugged.clone()));
+        assert_eq!(debounce.observe(plugged), None);
+    }
+
+    #[test]
+    fn flapping_enumeration_is_not_reported() {
+        let mut debounce = DeviceListDebounce::new(devices(&["Speakers"]));
+
+        // 关键行：插拔瞬间列表来回变化，每次轮询都不同或回到原状时都不推送。
+        assert_eq!(debounce.observe(devices(&[])), None);
+        assert_eq!(debounce.observe(devices(&["Headset"])), None);
+        assert_eq!(debounce.observe(devices(&["Speakers"])), None);
+        assert_eq!(debounce.observe(devices(&["Headset"])), None);
+        assert_eq!(debounce.observe(devices(&["Speakers"])), None);
+    }
+}
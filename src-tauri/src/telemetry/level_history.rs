@@ -0,0 +1,56 @@
+use crate::audio::ring_buffer::RingBuffer;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+/// 电平历史容量：按 10ms 左右的采集数据块计约 6 秒，足够绘制滚动响度曲线。
+const LEVEL_HISTORY_CAPACITY: usize = 600;
+
+/// 单个数据块的电平记录。
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelEntry {
+    pub timestamp_ms: u64,
+    pub rms: f32,
+    pub peak: f32,
+}
+
+/// 电平滚动历史：与实时电平表同步写入（每个数据块一条），供 VU 表等组件按需拉取，无需订阅分析帧。
+#[derive(Clone)]
+pub struct LevelHistoryState {
+    inner: Arc<Mutex<RingBuffer<LevelEntry>>>,
+}
+
+impl Default for LevelHistoryState {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RingBuffer::new(LEVEL_HISTORY_CAPACITY))),
+        }
+    }
+}
+
+impl LevelHistoryState {
+    /// 追加一条电平记录，满时覆盖最老记录。
+    pub(super) fn record(&self, timestamp_ms: u64, rms: f32, peak: f32) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.push(LevelEntry {
+                timestamp_ms,
+                rms,
+                peak,
+            });
+        }
+    }
+
+    /// 按时间先后返回最近 `count` 条记录；不足时返回现有全部记录。
+    pub fn latest(&self, count: usize) -> Vec<LevelEntry> {
+        self.inner
+            .lock()
+            .map(|guard| {
+                guard
+                    .iter()
+                    .skip(guard.len().saturating_sub(count))
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
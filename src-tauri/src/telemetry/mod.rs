@@ -1,29 +1,302 @@
-﻿use crate::audio::capture::{self, CaptureChunk};
-use crate::audio::dsp::{DspParams, SpectrumAnalyzer};
+﻿use crate::audio::capture::{
+    self, CaptureChunk, CaptureConfig, CaptureRuntime, ChannelMode, Downmix, DownmixMode,
+    DownmixWeights,
+};
+use crate::audio::dsp::{
+    self, BeatDetector, DcBlocker, DspParams, EqCurve, FrequencyRange, FrequencyScale, ScaleMode,
+    SpectrumAnalyzer, SpectrumFrame, Weighting, WindowFunction,
+};
+use crate::audio::latency::{LatencyProbeState, ProbeCapture};
+use crate::audio::loudness::LoudnessMeter;
+use crate::audio::mixer::SourceMixer;
+use crate::audio::recorder::RecordingState;
+use crate::audio::resample::{self, AnalysisRateConverter};
+use crate::audio::ring_buffer::RingBuffer;
+use crate::error::AppError;
+use crate::params::{
+    ANALYSIS_SAMPLE_RATE_RANGE, ATTACK_RELEASE_RANGE, BEAT_SENSITIVITY_RANGE, BIN_COUNT_RANGE,
+    BIN_NOISE_GATE_RANGE, CAPTURE_BUFFER_FRAMES_RANGE, CAPTURE_HOLD_MS_RANGE,
+    CAPTURE_SAMPLE_RATE_RANGE, DB_FLOOR_RANGE, DIFFUSION_RANGE, EQ_GAIN_RANGE, FFT_AVERAGES_RANGE,
+    GAIN_RANGE, GLOBAL_MOTION_GAMMA_RANGE, GLOBAL_MOTION_WEIGHT_RANGE, HOP_FRACTION_RANGE,
+    IPC_BYTE_BUDGET_RANGE, MAX_ANALYSIS_SAMPLE_RATE_RANGE, MAX_HZ_RANGE, MIN_HZ_RANGE,
+    MIX_GAIN_RANGE, NOISE_GATE_HOLD_MS_RANGE, NOISE_GATE_THRESHOLD_RANGE, PEAK_DECAY_RANGE,
+    SILENCE_SCREENSAVER_AFTER_MS_RANGE, SILENCE_THRESHOLD_RANGE, SILENCE_TIMEOUT_MS_RANGE,
+    SMOOTHING_RANGE, TILT_DB_PER_OCTAVE_RANGE, ZERO_PAD_FACTOR_RANGE,
+};
 use crate::settings;
+use crate::theme::{ThemeSnapshot, ThemeState};
 use serde::Serialize;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter};
 
+mod budget;
+pub mod delivery;
+pub mod hotplug;
+pub mod level_history;
+mod overlap;
+mod pacing;
+mod screensaver;
+mod silence;
+pub mod spectrogram;
+mod stereo;
+
+use budget::IpcBudgetGovernor;
+use delivery::FrameDeliveryState;
+use level_history::LevelHistoryState;
+use overlap::OverlapCursor;
+use pacing::FramePacer;
+use screensaver::SilenceScreensaver;
+use silence::{SilenceAction, SilencePause};
+use spectrogram::SpectrogramHistory;
+use stereo::StereoAnalysis;
+
+/// 分析器窗口长度，实时链路、模拟链路与基准测试共用；频段数来自设置。
+const ANALYZER_WINDOW_SIZE: usize = 1024;
+/// 基准测试允许的迭代次数范围，避免过长阻塞或统计样本过少。
+pub const MIN_BENCHMARK_ITERATIONS: usize = 1;
+pub const MAX_BENCHMARK_ITERATIONS: usize = 100_000;
+/// 超过该时长未收到采集数据即视为断流，开始进入保持阶段。
+const DROPOUT_DETECT_MS: u64 = 100;
+/// 基准测试合成信号假定的采样率。
+const BENCHMARK_SAMPLE_RATE: f32 = 48_000.0;
+/// 采集断开后的最大重连次数，全部失败才回退模拟数据。
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// 重连退避的初始与最大等待时长（毫秒），每次失败后翻倍。
+const RECONNECT_BACKOFF_INITIAL_MS: u64 = 500;
+const RECONNECT_BACKOFF_MAX_MS: u64 = 8_000;
+/// 两次起音事件之间的最小间隔（毫秒），约等于 600 BPM 的十六分音符，足以去除同一鼓点的重复触发。
+const BEAT_MIN_INTERVAL_MS: u64 = 100;
+/// 电平表数据超过该时长（毫秒）未更新即视为无信号；回环采集在系统静音时不再回调，需靠过期归零。
+const LEVEL_METER_MAX_AGE_MS: u64 = 250;
+
 #[derive(Debug, Clone, Copy)]
 pub struct RuntimeDspConfig {
-    pub smoothing: f32,
+    pub attack: f32,
+    pub release: f32,
     pub gain: f32,
+    pub beat_sensitivity: f32,
     pub emit_interval_ms: u64,
+    pub capture_hold_ms: u64,
+    pub capture_hold_decay: bool,
+    pub global_motion_rms_weight: f32,
+    pub global_motion_peak_weight: f32,
+    pub global_motion_gamma: f32,
+    pub fft_averages: usize,
+    pub zero_pad_factor: usize,
+    pub window_function: WindowFunction,
+    pub scale_mode: ScaleMode,
+    pub frequency_scale: FrequencyScale,
+    pub frequency_range: FrequencyRange,
+    pub bin_count: usize,
+    pub max_analysis_sample_rate: u32,
+    pub analysis_sample_rate: u32,
+    pub silence_screensaver: bool,
+    pub silence_screensaver_after_ms: u64,
+    pub silence_auto_pause: bool,
+    pub silence_threshold: f32,
+    pub silence_timeout_ms: u64,
+    pub bin_format: BinFormat,
+    pub prefill_sample_buffer: bool,
+    pub dc_blocker: bool,
+    pub weighting: Weighting,
+    pub tilt_db_per_octave: f32,
+    pub peak_hold: bool,
+    pub peak_decay: f32,
+    pub noise_gate_threshold: f32,
+    pub noise_gate_hold_ms: u64,
+    pub bin_noise_gate: f32,
+    pub diffusion: f32,
+    pub hop_fraction: f32,
+    pub eq: Option<EqCurve>,
+    pub pitch_detection: bool,
+    pub chroma: bool,
+    pub loudness: bool,
+    pub ipc_byte_budget_per_sec: u64,
+    /// 混合采集时主来源与默认输入的增益，可实时调整，不触发采集重建。
+    pub mix_primary_gain: f32,
+    pub mix_input_gain: f32,
+    /// 采集流配置，变化时分析线程重建采集。
+    pub capture: CaptureConfig,
 }
 
 #[derive(Clone)]
 pub struct RuntimeDspState {
     inner: Arc<Mutex<RuntimeDspConfig>>,
+    /// 自适应状态重置请求：命令层置位，分析线程在下一次分析前消费。
+    adaptation_reset: Arc<AtomicBool>,
+    /// 目标采集设备标识，空字符串表示使用默认设备；不是 `Copy`，因此不放进 `RuntimeDspConfig`。
+    capture_device_id: Arc<Mutex<String>>,
 }
 
-/// 可视化运行时状态：用于暂停/恢复前端分析帧推送。
+/// 可视化运行时状态：用于暂停/恢复前端分析帧推送，以及托盘电平动画开关。
 #[derive(Clone, Default)]
 pub struct RuntimeVisualState {
     paused: Arc<AtomicBool>,
+    animate_tray: Arc<AtomicBool>,
+}
+
+/// 最近一次推送帧的电平快照。
+#[derive(Debug, Clone, Copy)]
+pub struct LevelSnapshot {
+    pub timestamp_ms: u64,
+    pub rms: f32,
+}
+
+/// 最近电平共享状态：分析线程在发帧点写入，托盘等非前端消费方读取。
+#[derive(Clone, Default)]
+pub struct LatestLevelState {
+    inner: Arc<Mutex<Option<LevelSnapshot>>>,
+}
+
+/// 实时电平表状态：分析线程每收到一个数据块就写入 RMS/峰值，不受发帧节流与暂停影响。
+///
+/// 全部字段为原子量，读写都不加锁，前端可高频轮询。
+#[derive(Clone, Default)]
+pub struct LevelMeterState {
+    rms_bits: Arc<AtomicU32>,
+    peak_bits: Arc<AtomicU32>,
+    timestamp_ms: Arc<AtomicU64>,
+}
+
+/// 当前生效的采集格式，供前端展示“正在采集：扬声器 @ 48kHz”。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStatus {
+    pub device_id: String,
+    pub device_name: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub is_mock: bool,
+    pub source: FrameSource,
+    /// 采集回调缓冲时长估计（毫秒）：请求了固定缓冲帧数时按请求值计算，否则按实际收到的数据块长度更新；
+    /// 尚未收到数据块时为 0。
+    pub estimated_buffer_ms: f32,
+}
+
+/// 采集状态共享：分析线程在采集会话启动、重连或回退模拟数据时写入，采集停止时清空，命令层读取。
+#[derive(Clone, Default)]
+pub struct CaptureStatusState {
+    inner: Arc<Mutex<Option<CaptureStatus>>>,
+}
+
+/// 采集启停控制：命令层切换，分析线程在停止时释放采集流并阻塞等待重新启动，不占用音频设备。
+#[derive(Clone, Default)]
+pub struct CaptureControlState {
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+}
+
+/// 分析帧频段格式：默认量化为 0..=1023 的 `u16` 以压缩 IPC 负载；
+/// `f32` 直接发送 0..1 浮点，保留完整精度，适合 WebGL 着色器，但 JSON 体积约为两到三倍。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinFormat {
+    U16,
+    F32,
+}
+
+impl BinFormat {
+    /// 解析设置中的格式字符串，非法值回退默认 `u16`。
+    pub fn from_raw(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "f32" => Self::F32,
+            _ => Self::U16,
+        }
+    }
+
+    /// 按格式编码频段，只填充一种表示，避免负载翻倍。
+    fn encode(self, levels: &[f32]) -> FrameBins {
+        match self {
+            Self::U16 => FrameBins::U16(dsp::quantize_levels(levels)),
+            Self::F32 => FrameBins::F32(levels.iter().map(|level| level.clamp(0.0, 1.0)).collect()),
+        }
+    }
+}
+
+/// 分析帧中的频段数组，序列化为 `bins`（u16）或 `binsF32`（f32）其中之一。
+#[derive(Debug, Clone, Serialize)]
+enum FrameBins {
+    #[serde(rename = "bins")]
+    U16(Vec<u16>),
+    #[serde(rename = "binsF32")]
+    F32(Vec<f32>),
+}
+
+impl FrameBins {
+    /// 重采样到指定频段数，保持原有编码格式。
+    fn resample(&self, target: usize) -> Self {
+        match self {
+            Self::U16(bins) => Self::U16(dsp::resample_bins(bins, target)),
+            Self::F32(levels) => Self::F32(dsp::resample_levels(levels, target)),
+        }
+    }
+}
+
+/// 分析帧的数据来源：`loopback` 为系统播放声音回环，`realtime` 为其他真实设备，`mock` 为模拟数据。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameSource {
+    Realtime,
+    Loopback,
+    Mock,
+}
+
+impl FrameSource {
+    /// 按采集会话的设备标识区分回环与普通真实采集。
+    fn for_device(device_id: &str) -> Self {
+        if device_id.starts_with(capture::LOOPBACK_ID_PREFIX) {
+            Self::Loopback
+        } else {
+            Self::Realtime
+        }
+    }
+}
+
+/// 起音检测事件名，独立于分析帧推送，前端可直接订阅做闪光等瞬时效果。
+const BEAT_EVENT: &str = "audio:beat";
+/// 采集启停事件名：分析线程真正释放或重新获取采集设备后推送 `bool`。
+const CAPTURE_RUNNING_EVENT: &str = "audio:capture_running";
+/// 断开重连事件名：每次重连尝试前推送 `CaptureReconnecting`，重连成功建立采集后推送 `CaptureRecovered`。
+const CAPTURE_RECONNECTING_EVENT: &str = "audio:capture_reconnecting";
+const CAPTURE_RECOVERED_EVENT: &str = "audio:capture_recovered";
+/// 频段中心频率事件名：频段数、映射方式、频率范围或分析采样率变化后推送新的 `Vec<f32>`，
+/// 内容与 `get_bin_frequencies` 一致，前端据此刷新频率轴而无需轮询。
+const BIN_FREQUENCIES_EVENT: &str = "audio:bin_frequencies";
+
+/// `audio:capture_reconnecting` 事件负载：第几次尝试、总尝试次数与本次尝试前的等待时长。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptureReconnecting {
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+}
+
+/// `audio:capture_recovered` 事件负载：成功时的尝试序号与实际采集设备。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptureRecovered {
+    attempt: u32,
+    device_id: String,
+}
+
+/// `audio:beat` 事件负载：检测到起音的时间戳与 0..1 强度。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BeatEvent {
+    timestamp_ms: u64,
+    strength: f32,
+}
+
+/// `audio:source_changed` 事件负载：真实采集失败转入模拟数据时推送一次。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceChange {
+    source: FrameSource,
+    /// 导致回退的真实采集错误。
+    error: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -31,10 +304,177 @@ pub struct RuntimeVisualState {
 struct AnalysisFrame {
     timestamp_ms: u64,
     device_id: String,
-    bins: Vec<u16>,
+    source: FrameSource,
+    #[serde(flatten)]
+    bins: FrameBins,
     rms: f32,
     peak: f32,
+    /// 频谱质心（0..1，相对奈奎斯特频率），可用于“音色亮度 -> 颜色”映射；静音时为 0。
+    centroid: f32,
     latency_estimate_ms: f32,
+    /// 静音自动暂停前的最后一帧（频段已归零），之后直到有声音前不再发帧。
+    silent: bool,
+    /// 自上一帧以来输入端削波的样本数（折叠前逐声道统计）；`gain` 作用于频谱，不会造成此处的削波。
+    clip_count: u32,
+    /// 立体声模式下的左右声道频段（中侧模式下依次为中置、侧向），编码格式与 `bins` 相同；
+    /// 单声道模式或设备为单声道时省略。
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_channel_bins"
+    )]
+    bins_left: Option<FrameBins>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_channel_bins"
+    )]
+    bins_right: Option<FrameBins>,
+    /// 逐频段峰值保持电平，编码格式与 `bins` 相同；仅在开启峰值保持时附带。
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_channel_bins"
+    )]
+    peak_bins: Option<FrameBins>,
+    /// 开启音高检测时估计的基频（Hz）；未开启、静音或置信度不足时省略。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pitch_hz: Option<f32>,
+    /// 开启色度输出时的十二音级能量（C 在前，0..1）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chroma: Option<[f32; 12]>,
+    /// 开启响度测量时的 K 计权积分响度（LUFS），约每 400ms 刷新；尚无有效门限块时省略。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lufs: Option<f32>,
+    /// 逐频段颜色提示，仅在设置开启时附带，避免默认增加 IPC 负载。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bin_colors: Option<Vec<[u8; 3]>>,
+}
+
+/// 声道频段直接序列化为数组，格式由 `bins` 的字段名体现，不再重复区分。
+fn serialize_channel_bins<S: serde::Serializer>(
+    bins: &Option<FrameBins>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bins {
+        Some(FrameBins::U16(bins)) => bins.serialize(serializer),
+        Some(FrameBins::F32(levels)) => levels.serialize(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 模拟链路假定的采样率，用于计算模拟频段的中心频率。
+const MOCK_SAMPLE_RATE: u32 = 48_000;
+/// 模拟链路分析帧与采集状态中使用的设备标识。
+const MOCK_DEVICE_ID: &str = "mock-device";
+
+/// 逐频段颜色缓存：主题或频段频率不变时复用，避免每帧重复查色。
+#[derive(Default)]
+struct BinColorCache {
+    snapshot: Option<ThemeSnapshot>,
+    frequencies: Vec<f32>,
+    colors: Vec<[u8; 3]>,
+}
+
+impl BinColorCache {
+    /// 根据最新主题快照返回颜色提示；未开启时返回 `None`。
+    fn resolve(&mut self, theme_state: &ThemeState, frequencies: &[f32]) -> Option<Vec<[u8; 3]>> {
+        let snapshot = theme_state.get();
+        if !snapshot.emit_bin_colors {
+            return None;
+        }
+
+        if self.snapshot.as_ref() != Some(&snapshot) || self.frequencies != frequencies {
+            self.colors = snapshot.theme.bin_colors(frequencies);
+            self.frequencies = frequencies.to_vec();
+            self.snapshot = Some(snapshot);
+        }
+
+        Some(self.colors.clone())
+    }
+}
+
+/// DSP 基准测试结果：单次 `analyze` 耗时统计（微秒）与理论帧率上限。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DspBenchmarkReport {
+    pub iterations: usize,
+    pub bin_count: usize,
+    pub window_size: usize,
+    pub min_us: f64,
+    pub avg_us: f64,
+    pub max_us: f64,
+    pub frames_per_second: f64,
+}
+
+/// 短暂断流保持：记录最近一帧真实分析结果，断流期间重复发送，避免画面突然跳到模拟波形。
+#[derive(Default)]
+struct DropoutHold {
+    last_frame: Option<SpectrumFrame>,
+}
+
+impl DropoutHold {
+    /// 记录最近一次真实分析结果。
+    fn remember(&mut self, frame: &SpectrumFrame) {
+        self.last_frame = Some(frame.clone());
+    }
+
+    /// 返回断流期间应发送的保持帧；未断流或超过保持时长时返回 `None`。
+    fn held_frame(&self, silence_ms: u64, hold_ms: u64, decay: bool) -> Option<SpectrumFrame> {
+        let frame = self.last_frame.as_ref()?;
+        if silence_ms <= DROPOUT_DETECT_MS {
+            return None;
+        }
+
+        let in_hold_ms = silence_ms - DROPOUT_DETECT_MS;
+        if in_hold_ms > hold_ms {
+            return None;
+        }
+
+        // 关键行：开启衰减时在保持期内线性淡出，保持期结束时正好归零。
+        let factor = if decay && hold_ms > 0 {
+            1.0 - in_hold_ms as f32 / hold_ms as f32
+        } else {
+            1.0
+        };
+        Some(SpectrumFrame {
+            levels: frame.levels.iter().map(|level| level * factor).collect(),
+            rms: frame.rms * factor,
+            peak: frame.peak * factor,
+            centroid: frame.centroid,
+            // 保持期重复的是旧频谱，不代表当前有可辨认的音高。
+            pitch_hz: None,
+            chroma: frame.chroma,
+        })
+    }
+}
+
+impl RuntimeDspConfig {
+    /// 提取分析器所需的 DSP 参数。
+    fn dsp_params(&self) -> DspParams {
+        DspParams {
+            attack: self.attack,
+            release: self.release,
+            gain: self.gain,
+            global_motion_rms_weight: self.global_motion_rms_weight,
+            global_motion_peak_weight: self.global_motion_peak_weight,
+            global_motion_gamma: self.global_motion_gamma,
+            fft_averages: self.fft_averages,
+            zero_pad_factor: self.zero_pad_factor,
+            window_function: self.window_function,
+            scale_mode: self.scale_mode,
+            frequency_scale: self.frequency_scale,
+            frequency_range: self.frequency_range,
+            weighting: self.weighting,
+            tilt_db_per_octave: self.tilt_db_per_octave,
+            peak_hold: self.peak_hold,
+            peak_decay: self.peak_decay,
+            noise_gate_threshold: self.noise_gate_threshold,
+            noise_gate_hold_ms: self.noise_gate_hold_ms,
+            bin_noise_gate: self.bin_noise_gate,
+            eq: self.eq,
+            pitch_detection: self.pitch_detection,
+            chroma: self.chroma,
+            diffusion: self.diffusion,
+        }
+    }
 }
 
 impl RuntimeDspState {
@@ -42,6 +482,8 @@ impl RuntimeDspState {
     pub fn new(config: RuntimeDspConfig) -> Self {
         Self {
             inner: Arc::new(Mutex::new(config)),
+            adaptation_reset: Arc::new(AtomicBool::new(false)),
+            capture_device_id: Arc::new(Mutex::new(String::new())),
         }
     }
 
@@ -50,11 +492,7 @@ impl RuntimeDspState {
         self.inner
             .lock()
             .map(|guard| *guard)
-            .unwrap_or(RuntimeDspConfig {
-                smoothing: 0.58,
-                gain: 1.8,
-                emit_interval_ms: quality_emit_interval_ms("ultra"),
-            })
+            .unwrap_or_else(|_| runtime_config_from_settings(&settings::AppSettings::default()))
     }
 
     /// 更新运行时 DSP 参数，使滑块调节可以立刻生效。
@@ -63,6 +501,38 @@ impl RuntimeDspState {
             *guard = config;
         }
     }
+
+    /// 请求重置分析器自适应状态（基线、平滑缓存、断流保持帧），不改变 DSP 参数。
+    pub fn request_adaptation_reset(&self) {
+        self.adaptation_reset.store(true, Ordering::Relaxed);
+    }
+
+    /// 取出并清除重置请求，分析线程据此决定是否重置。
+    fn take_adaptation_reset(&self) -> bool {
+        self.adaptation_reset.swap(false, Ordering::Relaxed)
+    }
+
+    /// 更新目标采集设备，分析线程检测到变化后重新建立采集。
+    pub fn set_capture_device_id(&self, device_id: &str) {
+        if let Ok(mut guard) = self.capture_device_id.lock() {
+            device_id.trim().clone_into(&mut guard);
+        }
+    }
+
+    /// 读取当前目标采集设备标识。
+    fn capture_device_id(&self) -> String {
+        self.capture_device_id
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    /// 目标采集设备是否已不同于 `current`。
+    fn capture_device_changed(&self, current: &str) -> bool {
+        self.capture_device_id
+            .lock()
+            .is_ok_and(|guard| guard.as_str() != current)
+    }
 }
 
 impl RuntimeVisualState {
@@ -75,17 +545,231 @@ impl RuntimeVisualState {
     pub fn is_paused(&self) -> bool {
         self.paused.load(Ordering::Relaxed)
     }
+
+    /// 设置托盘图标是否随电平变化。
+    pub fn set_animate_tray(&self, enabled: bool) {
+        self.animate_tray.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 查询托盘电平动画是否开启。
+    pub fn animate_tray(&self) -> bool {
+        self.animate_tray.load(Ordering::Relaxed)
+    }
+}
+
+impl CaptureStatusState {
+    fn set(&self, status: CaptureStatus) {
+        if let Ok(mut guard) = self.inner.lock() {
+            *guard = Some(status);
+        }
+    }
+
+    /// 采集停止时清空，停止期间不再报告已释放的设备与格式。
+    fn clear(&self) {
+        if let Ok(mut guard) = self.inner.lock() {
+            *guard = None;
+        }
+    }
+
+    /// 读取当前采集状态；分析线程尚未完成首次启动或采集已停止时返回 `None`。
+    pub fn get(&self) -> Option<CaptureStatus> {
+        self.inner.lock().ok().and_then(|guard| guard.clone())
+    }
+
+    /// 按实际收到的数据块长度更新缓冲时长估计。
+    fn set_buffer_ms(&self, buffer_ms: f32) {
+        if let Ok(mut guard) = self.inner.lock() {
+            if let Some(status) = guard.as_mut() {
+                status.estimated_buffer_ms = buffer_ms;
+            }
+        }
+    }
+}
+
+impl CaptureControlState {
+    /// 请求停止采集：分析线程在下一次轮询时结束当前会话并释放设备。
+    pub fn stop(&self) {
+        let (lock, _) = &*self.stopped;
+        if let Ok(mut guard) = lock.lock() {
+            *guard = true;
+        }
+    }
+
+    /// 请求恢复采集，唤醒等待中的分析线程。
+    pub fn start(&self) {
+        let (lock, condvar) = &*self.stopped;
+        if let Ok(mut guard) = lock.lock() {
+            *guard = false;
+        }
+        condvar.notify_all();
+    }
+
+    /// 当前是否处于停止状态。
+    pub fn is_stopped(&self) -> bool {
+        let (lock, _) = &*self.stopped;
+        lock.lock().map(|guard| *guard).unwrap_or(false)
+    }
+
+    /// 阻塞直到采集被重新启动。
+    fn wait_until_started(&self) {
+        let (lock, condvar) = &*self.stopped;
+        if let Ok(guard) = lock.lock() {
+            drop(condvar.wait_while(guard, |stopped| *stopped));
+        }
+    }
+}
+
+impl LatestLevelState {
+    /// 记录最新一帧的电平。
+    fn record(&self, timestamp_ms: u64, rms: f32) {
+        if let Ok(mut guard) = self.inner.lock() {
+            *guard = Some(LevelSnapshot { timestamp_ms, rms });
+        }
+    }
+
+    /// 读取不超过 `max_age_ms` 的最新电平；过期（暂停或无音频）时返回 `None`。
+    pub fn fresh(&self, max_age_ms: u64) -> Option<LevelSnapshot> {
+        let snapshot = self.inner.lock().ok().and_then(|guard| *guard)?;
+        (now_timestamp_ms().saturating_sub(snapshot.timestamp_ms) <= max_age_ms).then_some(snapshot)
+    }
+}
+
+impl LevelMeterState {
+    /// 记录最新数据块的电平。
+    fn record(&self, timestamp_ms: u64, rms: f32, peak: f32) {
+        self.rms_bits.store(rms.to_bits(), Ordering::Relaxed);
+        self.peak_bits.store(peak.to_bits(), Ordering::Relaxed);
+        self.timestamp_ms.store(timestamp_ms, Ordering::Relaxed);
+    }
+
+    /// 读取当前 `(rms, peak)`；超过 `LEVEL_METER_MAX_AGE_MS` 未更新（无音频或采集中断）时返回零电平。
+    pub fn current(&self) -> (f32, f32) {
+        let age = now_timestamp_ms().saturating_sub(self.timestamp_ms.load(Ordering::Relaxed));
+        if age > LEVEL_METER_MAX_AGE_MS {
+            return (0.0, 0.0);
+        }
+        (
+            f32::from_bits(self.rms_bits.load(Ordering::Relaxed)),
+            f32::from_bits(self.peak_bits.load(Ordering::Relaxed)),
+        )
+    }
 }
 
 /// 从持久化设置构建 DSP 初始参数。
 pub fn runtime_config_from_settings(settings: &settings::AppSettings) -> RuntimeDspConfig {
     RuntimeDspConfig {
-        smoothing: settings.smoothing.clamp(0.0, 0.95),
-        gain: settings.gain.clamp(0.2, 6.0),
+        attack: smoothing_coefficient(settings.attack, settings.smoothing),
+        release: smoothing_coefficient(settings.release, settings.smoothing),
+        gain: GAIN_RANGE.clamp(settings.gain),
+        beat_sensitivity: BEAT_SENSITIVITY_RANGE.clamp(settings.beat_sensitivity),
         emit_interval_ms: quality_emit_interval_ms(&settings.quality),
+        capture_hold_ms: CAPTURE_HOLD_MS_RANGE.clamp_u64(settings.capture_hold_ms),
+        capture_hold_decay: settings.capture_hold_decay,
+        global_motion_rms_weight: GLOBAL_MOTION_WEIGHT_RANGE
+            .clamp(settings.global_motion_rms_weight),
+        global_motion_peak_weight: GLOBAL_MOTION_WEIGHT_RANGE
+            .clamp(settings.global_motion_peak_weight),
+        global_motion_gamma: GLOBAL_MOTION_GAMMA_RANGE.clamp(settings.global_motion_gamma),
+        fft_averages: FFT_AVERAGES_RANGE.clamp_u64(settings.fft_averages as u64) as usize,
+        zero_pad_factor: ZERO_PAD_FACTOR_RANGE.clamp_u64(settings.zero_pad_factor as u64) as usize,
+        window_function: WindowFunction::from_raw(&settings.window_function),
+        scale_mode: ScaleMode::from_raw(
+            &settings.scale_mode,
+            DB_FLOOR_RANGE.clamp(settings.db_floor),
+        ),
+        frequency_scale: FrequencyScale::from_raw(&settings.frequency_scale),
+        frequency_range: frequency_range(settings.min_hz, settings.max_hz),
+        bin_count: BIN_COUNT_RANGE.clamp_u64(settings.bin_count as u64) as usize,
+        max_analysis_sample_rate: MAX_ANALYSIS_SAMPLE_RATE_RANGE
+            .clamp_u64(settings.max_analysis_sample_rate as u64)
+            as u32,
+        analysis_sample_rate: ANALYSIS_SAMPLE_RATE_RANGE
+            .clamp_u64(settings.analysis_sample_rate as u64) as u32,
+        silence_screensaver: settings.silence_screensaver,
+        silence_screensaver_after_ms: SILENCE_SCREENSAVER_AFTER_MS_RANGE
+            .clamp_u64(settings.silence_screensaver_after_ms),
+        silence_auto_pause: settings.silence_auto_pause,
+        silence_threshold: SILENCE_THRESHOLD_RANGE.clamp(settings.silence_threshold),
+        silence_timeout_ms: SILENCE_TIMEOUT_MS_RANGE.clamp_u64(settings.silence_timeout_ms),
+        bin_format: BinFormat::from_raw(&settings.bin_format),
+        prefill_sample_buffer: settings.prefill_sample_buffer,
+        dc_blocker: settings.dc_blocker,
+        weighting: Weighting::from_raw(&settings.weighting),
+        tilt_db_per_octave: TILT_DB_PER_OCTAVE_RANGE.clamp(settings.tilt_db_per_octave),
+        peak_hold: settings.peak_hold,
+        peak_decay: PEAK_DECAY_RANGE.clamp(settings.peak_decay),
+        noise_gate_threshold: NOISE_GATE_THRESHOLD_RANGE.clamp(settings.noise_gate_threshold),
+        noise_gate_hold_ms: NOISE_GATE_HOLD_MS_RANGE.clamp_u64(settings.noise_gate_hold_ms),
+        bin_noise_gate: BIN_NOISE_GATE_RANGE.clamp(settings.bin_noise_gate),
+        diffusion: DIFFUSION_RANGE.clamp(settings.diffusion),
+        hop_fraction: HOP_FRACTION_RANGE.clamp(settings.hop_fraction),
+        eq: eq_curve(&settings.eq_gains),
+        pitch_detection: settings.pitch_detection,
+        chroma: settings.chroma,
+        loudness: settings.loudness,
+        ipc_byte_budget_per_sec: IPC_BYTE_BUDGET_RANGE.clamp_u64(settings.ipc_byte_budget_per_sec),
+        mix_primary_gain: MIX_GAIN_RANGE.clamp(settings.mix_primary_gain),
+        mix_input_gain: MIX_GAIN_RANGE.clamp(settings.mix_input_gain),
+        capture: CaptureConfig {
+            sample_rate: nonzero_u32(
+                CAPTURE_SAMPLE_RATE_RANGE.clamp_u64(settings.capture_sample_rate as u64),
+            ),
+            buffer_frames: nonzero_u32(
+                CAPTURE_BUFFER_FRAMES_RANGE.clamp_u64(settings.capture_buffer_frames as u64),
+            ),
+            channel_mode: ChannelMode::from_raw(&settings.channel_mode),
+            downmix: Downmix {
+                mode: DownmixMode::from_raw(&settings.downmix_mode),
+                weights: DownmixWeights::from_slice(&settings.downmix_weights),
+            },
+            mix_default_input: settings.mix_default_input,
+        },
     }
 }
 
+/// 按设置解析采集目标设备：显式选择的采集设备优先；开启混合采集且未选择设备时，
+/// 主来源回环 `output_device_id` 指定的输出设备；都未设置时为空，使用默认输出回环。
+pub fn capture_target_device_id(settings: &settings::AppSettings) -> String {
+    let capture_device_id = settings.capture_device_id.trim();
+    if capture_device_id.is_empty() && settings.mix_default_input {
+        settings.output_device_id.trim().to_string()
+    } else {
+        capture_device_id.to_string()
+    }
+}
+
+/// 未单独设置 attack/release 时沿用旧的统一平滑系数，保持升级前的手感。
+fn smoothing_coefficient(value: Option<f32>, smoothing: f32) -> f32 {
+    ATTACK_RELEASE_RANGE.clamp(value.unwrap_or_else(|| SMOOTHING_RANGE.clamp(smoothing)))
+}
+
+/// 把设置中的均衡倍数夹到合法范围后构建曲线；平直或无效时为 `None`。
+pub fn eq_curve(gains: &[f32]) -> Option<EqCurve> {
+    let clamped = gains
+        .iter()
+        .map(|gain| EQ_GAIN_RANGE.clamp(*gain))
+        .collect::<Vec<_>>();
+    EqCurve::from_slice(&clamped)
+}
+
+/// 把设置中的频率上下限夹到合法范围；上下限颠倒时回退默认范围，与设置校验一致。
+fn frequency_range(min_hz: f32, max_hz: f32) -> FrequencyRange {
+    let range = FrequencyRange {
+        min_hz: MIN_HZ_RANGE.clamp(min_hz),
+        max_hz: MAX_HZ_RANGE.clamp(max_hz),
+    };
+    if range.min_hz < range.max_hz {
+        range
+    } else {
+        FrequencyRange::default()
+    }
+}
+
+/// 设置中用 0 表示“沿用设备默认值”，这里转换为 `None`。
+fn nonzero_u32(value: u64) -> Option<u32> {
+    (value > 0).then_some(value as u32)
+}
+
 /// 将画质档位映射到 IPC 发帧节流间隔（毫秒）。
 fn quality_emit_interval_ms(raw_quality: &str) -> u64 {
     let normalized = raw_quality.trim().to_ascii_lowercase();
@@ -100,90 +784,505 @@ fn quality_emit_interval_ms(raw_quality: &str) -> u64 {
     }
 }
 
-/// 启动分析事件流：优先真实采集，失败时自动回退模拟数据。
-pub fn start_analysis_emitter(
-    app: AppHandle,
-    runtime_dsp: RuntimeDspState,
-    runtime_visual: RuntimeVisualState,
-) {
-    thread::spawn(move || {
-        if let Err(error) =
-            run_realtime_analysis_loop(app.clone(), runtime_dsp.clone(), runtime_visual.clone())
-        {
+/// 分析线程与命令层共享的状态集合，新增共享状态时扩展字段即可，不必改动线程入口签名。
+#[derive(Clone)]
+pub struct AnalysisShared {
+    pub runtime_dsp: RuntimeDspState,
+    pub runtime_visual: RuntimeVisualState,
+    pub history: SpectrogramHistory,
+    pub theme_state: ThemeState,
+    pub frame_delivery: FrameDeliveryState,
+    pub latest_level: LatestLevelState,
+    pub level_meter: LevelMeterState,
+    pub level_history: LevelHistoryState,
+    pub latency_probe: LatencyProbeState,
+    pub capture_status: CaptureStatusState,
+    pub capture_control: CaptureControlState,
+    pub recording: RecordingState,
+}
+
+/// 启动分析事件流：优先真实采集，失败时自动回退模拟数据；采集设备或流配置变化时重新建立采集。
+///
+/// 采集被停止时线程在此挂起，不持有任何采集流，恢复后按当前配置重新建立采集。
+pub fn start_analysis_emitter(app: AppHandle, shared: AnalysisShared) {
+    thread::spawn(move || loop {
+        if shared.capture_control.is_stopped() {
+            shared.latency_probe.set_capture(None);
+            shared.capture_status.clear();
+            shared.recording.interrupt();
+            let _ = app.emit(CAPTURE_RUNNING_EVENT, false);
+            shared.capture_control.wait_until_started();
+            let _ = app.emit(CAPTURE_RUNNING_EVENT, true);
+        }
+        if let Err(error) = run_realtime_analysis_loop(app.clone(), shared.clone()) {
             eprintln!("realtime audio loop failed, fallback to mock emitter: {error}");
-            run_mock_analysis_loop(app, runtime_dsp, runtime_visual);
+            shared.latency_probe.set_capture(None);
+            shared.recording.interrupt();
+            run_mock_analysis_loop(app.clone(), shared.clone(), error.to_string());
         }
     });
 }
 
-/// 实时链路：采集线程 -> 样本缓存 -> 频谱分析 -> 向前端推送事件。
-fn run_realtime_analysis_loop(
-    app: AppHandle,
-    runtime_dsp: RuntimeDspState,
-    runtime_visual: RuntimeVisualState,
-) -> Result<(), String> {
+/// 采集会话的结束原因。
+enum CaptureSessionEnd {
+    /// 采集设备或流配置已变化，应按新配置重新启动。
+    ConfigChanged,
+    /// 设备断开且在保持时长内未恢复。
+    Disconnected,
+    /// 采集被命令层停止，应释放设备并等待重新启动。
+    Stopped,
+}
+
+/// 实时链路：运行采集会话，设备断开时按退避策略重连，重连全部失败才返回错误交给上层回退。
+///
+/// 返回 `Ok` 表示采集设备或流配置已变化或采集被停止，调用方据此重新启动或挂起。
+fn run_realtime_analysis_loop(app: AppHandle, shared: AnalysisShared) -> Result<(), AppError> {
+    // 关键行：首次启动失败直接返回，由调用方立即回退模拟数据；只有运行中断开才进入重连。
+    let mut session = run_capture_session(&app, &shared, None);
+    loop {
+        match session? {
+            CaptureSessionEnd::ConfigChanged | CaptureSessionEnd::Stopped => return Ok(()),
+            // 关键行：走到这里时上一个会话已返回，其持有的采集流已全部释放，重建不会与旧流争用设备。
+            CaptureSessionEnd::Disconnected => {
+                shared.latency_probe.set_capture(None);
+                session = reconnect_capture_session(&app, &shared);
+            }
+        }
+    }
+}
+
+/// 断开后等待并重新建立采集：每次失败退避时长翻倍，成功后继续运行新会话直到其结束。
+fn reconnect_capture_session(
+    app: &AppHandle,
+    shared: &AnalysisShared,
+) -> Result<CaptureSessionEnd, AppError> {
+    let capture_device_id = shared.runtime_dsp.capture_device_id();
+    let capture_config = shared.runtime_dsp.get().capture;
+    let mut backoff_ms = RECONNECT_BACKOFF_INITIAL_MS;
+    let mut last_error = None;
+
+    for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+        let _ = app.emit(
+            CAPTURE_RECONNECTING_EVENT,
+            CaptureReconnecting {
+                attempt,
+                max_attempts: MAX_RECONNECT_ATTEMPTS,
+                delay_ms: backoff_ms,
+            },
+        );
+        thread::sleep(Duration::from_millis(backoff_ms));
+        if shared.capture_control.is_stopped() {
+            return Ok(CaptureSessionEnd::Stopped);
+        }
+        // 关键行：等待期间用户切换了设备或流配置时不再重连旧配置，直接按新配置重启。
+        if shared
+            .runtime_dsp
+            .capture_device_changed(&capture_device_id)
+            || shared.runtime_dsp.get().capture != capture_config
+        {
+            return Ok(CaptureSessionEnd::ConfigChanged);
+        }
+        match run_capture_session(app, shared, Some(attempt)) {
+            Ok(end) => return Ok(end),
+            Err(err) => {
+                eprintln!(
+                    "audio capture reconnect attempt {attempt}/{MAX_RECONNECT_ATTEMPTS} failed: {err}"
+                );
+                last_error = Some(err);
+            }
+        }
+        backoff_ms = (backoff_ms * 2).min(RECONNECT_BACKOFF_MAX_MS);
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| AppError::Capture("audio capture reconnect failed".to_string())))
+}
+
+/// 混合采集：额外打开默认输入设备并把其流句柄并入主会话；打开失败或主采集本身已回退到该设备时不混合。
+fn start_input_mixer(
+    runtime: &mut CaptureRuntime,
+    capture_config: CaptureConfig,
+) -> Option<SourceMixer> {
+    let (input_tx, input_rx) = mpsc::channel::<CaptureChunk>();
+    let input = match capture::start_default_input_capture(input_tx, capture_config) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("default input unavailable, capturing without mix: {err}");
+            return None;
+        }
+    };
+    // 关键行：回环不可用降级到麦克风时主采集与附加来源是同一设备，重复叠加只会让电平翻倍。
+    if input.device_id == runtime.device_id {
+        return None;
+    }
+
+    let mixer = SourceMixer::new(input_rx, input.sample_rate, runtime.sample_rate);
+    runtime.attach(input);
+    Some(mixer)
+}
+
+/// 向样本缓存预填充一整窗静音并同步推进重叠游标，首个发帧周期即可分析，不必等满一窗真实样本。
+fn prefill_with_silence(buffer: &mut RingBuffer<f32>, cursor: &mut OverlapCursor, silence: &[f32]) {
+    buffer.extend_from_slice(silence);
+    cursor.advance(silence.len());
+}
+
+/// 单次采集会话：采集线程 -> 样本缓存 -> 频谱分析 -> 向前端推送事件。
+///
+/// 启动失败返回错误；运行后只会因配置变化或设备断开而结束。
+/// `reconnect_attempt` 为断开重连的尝试序号，采集建立成功后据此推送恢复事件。
+fn run_capture_session(
+    app: &AppHandle,
+    shared: &AnalysisShared,
+    reconnect_attempt: Option<u32>,
+) -> Result<CaptureSessionEnd, AppError> {
+    let AnalysisShared {
+        runtime_dsp,
+        runtime_visual,
+        history,
+        theme_state,
+        frame_delivery,
+        latest_level,
+        level_meter,
+        level_history,
+        latency_probe,
+        capture_status,
+        capture_control,
+        recording,
+    } = shared.clone();
     let (chunk_tx, chunk_rx) = mpsc::channel::<CaptureChunk>();
-    let runtime = capture::start_loopback_capture(chunk_tx)?;
+    let capture_device_id = runtime_dsp.capture_device_id();
+    let capture_config = runtime_dsp.get().capture;
+    let mut runtime =
+        capture::start_loopback_capture(chunk_tx, Some(&capture_device_id), capture_config)?;
+    // 关键行：`runtime` 持有全部采集流句柄，须存活到会话结束，否则系统采集会停止。
+    let mut mixer = if capture_config.mix_default_input {
+        start_input_mixer(&mut runtime, capture_config)
+    } else {
+        None
+    };
+    latency_probe.set_capture(Some(ProbeCapture {
+        device_id: runtime.device_id.clone(),
+        sample_rate: runtime.sample_rate,
+    }));
+    let source = FrameSource::for_device(&runtime.device_id);
+    let buffer_ms = |frames: u32| frames as f32 * 1000.0 / runtime.sample_rate.max(1) as f32;
+    capture_status.set(CaptureStatus {
+        device_id: runtime.device_id.clone(),
+        device_name: runtime.device_name.clone(),
+        sample_rate: runtime.sample_rate,
+        channels: runtime.channels,
+        is_mock: false,
+        source,
+        estimated_buffer_ms: capture_config.buffer_frames.map_or(0.0, buffer_ms),
+    });
+    // 关键行：未固定缓冲帧数时由驱动协商，只能从实际数据块长度反推；仅在长度变化时写入共享状态。
+    let mut observed_chunk_frames = capture_config.buffer_frames.unwrap_or(0);
+    let selectable_id = capture::selectable_device_id(&runtime.device_id);
+    if let Err(err) = settings::record_recent_device(app, &selectable_id, &runtime.device_name) {
+        eprintln!(
+            "failed to record recent device {}: {err}",
+            runtime.device_id
+        );
+    }
+    // 关键行：指定设备无法解析时会回退默认设备，这里广播实际生效的设备标识供前端同步。
+    let _ = app.emit("app:capture_device_changed", &selectable_id);
+    if let Some(reason) = &runtime.fallback_reason {
+        // 关键行：降级到麦克风时通知前端，避免用户误以为画面来自系统播放声音。
+        let _ = app.emit("audio:capture_fallback", reason);
+    }
+    if let Some(attempt) = reconnect_attempt {
+        let _ = app.emit(
+            CAPTURE_RECOVERED_EVENT,
+            CaptureRecovered {
+                attempt,
+                device_id: selectable_id.clone(),
+            },
+        );
+    }
 
     let initial = runtime_dsp.get();
     let mut last_config = initial;
+    let mut rate_converter = AnalysisRateConverter::new(
+        runtime.sample_rate,
+        initial.max_analysis_sample_rate,
+        initial.analysis_sample_rate,
+    );
+    // 关键行：频率轴、Mel 映射与 A 计权必须使用转换后的有效采样率，否则高采样率设备的频段会整体偏移。
     let mut analyzer = SpectrumAnalyzer::new(
-        64,
-        1024,
-        DspParams {
-            smoothing: initial.smoothing,
-            gain: initial.gain,
-        },
+        initial.bin_count,
+        ANALYZER_WINDOW_SIZE,
+        rate_converter.output_rate(),
+        initial.dsp_params(),
     );
 
-    let mut sample_buffer = Vec::<f32>::with_capacity(8192);
+    // 关键行：样本缓存为固定容量环形队列（8 窗），写满后覆盖最老样本，不再整体搬移；
+    // 新数据块先转换到 `converted` 做直流阻断与电平统计，再写入环形队列。
+    let mut sample_buffer = RingBuffer::<f32>::new(analyzer.required_samples() * 8);
+    let mut converted = Vec::<f32>::with_capacity(8192);
+    let mut window_samples = Vec::<f32>::with_capacity(analyzer.required_samples());
+    let silence = vec![0.0f32; analyzer.required_samples()];
+    let mut overlap_cursor = OverlapCursor::new(analyzer.required_samples());
+    // 关键行：预填充整窗静音，首个发帧周期即可分析；分析始终取末尾整窗，
+    // 真实样本累计满一窗后静音即被挤出窗口，不再影响 RMS/峰值。
+    if initial.prefill_sample_buffer {
+        prefill_with_silence(&mut sample_buffer, &mut overlap_cursor, &silence);
+    }
     let mut latest_capture_ts = now_timestamp_ms();
+    let mut clip_count = 0u32;
     let mut last_emit_ts = 0u64;
+    // 关键行：声道模式属于采集流配置，切换时整个会话重建，这里只需按会话初值决定是否启用及是否做中侧变换。
+    let mut stereo = capture_config.channel_mode.keeps_channels().then(|| {
+        StereoAnalysis::new(
+            runtime.sample_rate,
+            initial.max_analysis_sample_rate,
+            initial.analysis_sample_rate,
+            initial.bin_count,
+            ANALYZER_WINDOW_SIZE,
+            initial.dsp_params(),
+            capture_config.channel_mode == ChannelMode::MidSide,
+        )
+    });
+    let mut dc_blocker = DcBlocker::new(rate_converter.output_rate());
+    // 响度表只在开启时存在，关闭即丢弃累计的门限块，重新开启时从头测量。
+    let mut loudness_meter: Option<LoudnessMeter> = None;
+    let mut analysis_rates = (
+        initial.max_analysis_sample_rate,
+        initial.analysis_sample_rate,
+    );
+    let mut bin_frequencies = analyzer.bin_frequencies(rate_converter.output_rate());
+    // 最近一次推送给前端的频率轴，每次采集会话开始时为空，保证新会话至少推送一次。
+    let mut announced_frequencies = Vec::new();
+    let mut color_cache = BinColorCache::default();
+    let mut budget = IpcBudgetGovernor::default();
+    let mut governed_interval_ms = initial.emit_interval_ms;
+    let mut pacer = FramePacer::default();
 
-    // 持有流句柄，避免采集对象被释放后回调停止。
-    let _stream_guard = runtime.stream;
+    let mut beat_detector = BeatDetector::new(initial.beat_sensitivity, BEAT_MIN_INTERVAL_MS);
+    let mut dropout_hold = DropoutHold::default();
+    let mut screensaver = SilenceScreensaver::default();
+    let mut silence_pause = SilencePause::default();
+    let mut last_chunk_at = Instant::now();
+    let mut disconnected_at: Option<Instant> = None;
 
     loop {
-        match chunk_rx.recv_timeout(Duration::from_millis(20)) {
+        let received = match chunk_rx.recv_timeout(Duration::from_millis(20)) {
             Ok(chunk) => {
-                latest_capture_ts = chunk.timestamp_ms;
-                sample_buffer.extend_from_slice(&chunk.samples);
-
-                let max_buffer = analyzer.required_samples() * 8;
-                if sample_buffer.len() > max_buffer {
-                    let drain_count = sample_buffer.len() - analyzer.required_samples() * 4;
-                    sample_buffer.drain(0..drain_count);
+                let frames = chunk.samples.len() as u32;
+                if frames > 0 && frames != observed_chunk_frames {
+                    observed_chunk_frames = frames;
+                    capture_status.set_buffer_ms(buffer_ms(frames));
                 }
+                Some(chunk)
             }
-            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
             Err(mpsc::RecvTimeoutError::Disconnected) => {
-                return Err("audio capture channel disconnected".to_string());
+                // 关键行：通道断开后 recv 会立即返回，这里主动休眠避免空转。
+                disconnected_at.get_or_insert_with(Instant::now);
+                thread::sleep(Duration::from_millis(20));
+                None
+            }
+        };
+        // 关键行：混合采集以主来源数据块为时钟；主来源暂无数据时单独输出积压的默认输入样本。
+        let received = match mixer.as_mut() {
+            Some(mixer) => {
+                let mix_config = runtime_dsp.get();
+                match received {
+                    Some(mut chunk) => {
+                        mixer.mix(
+                            &mut chunk,
+                            mix_config.mix_primary_gain,
+                            mix_config.mix_input_gain,
+                        );
+                        Some(chunk)
+                    }
+                    None => mixer.take_orphaned(mix_config.mix_input_gain, now_timestamp_ms()),
+                }
+            }
+            None => received,
+        };
+
+        if let Some(chunk) = received {
+            latest_capture_ts = chunk.timestamp_ms;
+            clip_count = clip_count.saturating_add(chunk.clipped);
+            last_chunk_at = Instant::now();
+            latency_probe.feed(&chunk.samples);
+            recording.tee(&chunk.samples, runtime.sample_rate);
+
+            let configured = runtime_dsp.get();
+            let configured_rates = (
+                configured.max_analysis_sample_rate,
+                configured.analysis_sample_rate,
+            );
+            if configured_rates != analysis_rates {
+                let (max_rate, target_rate) = configured_rates;
+                rate_converter =
+                    AnalysisRateConverter::new(runtime.sample_rate, max_rate, target_rate);
+                dc_blocker = DcBlocker::new(rate_converter.output_rate());
+                loudness_meter = None;
+                if let Some(stereo) = stereo.as_mut() {
+                    stereo.set_rate_conversion(runtime.sample_rate, max_rate, target_rate);
+                }
+                analysis_rates = configured_rates;
+                analyzer.set_sample_rate(rate_converter.output_rate());
+                bin_frequencies = analyzer.bin_frequencies(rate_converter.output_rate());
+                beat_detector.reset();
+                // 关键行：缓存中是旧采样率的样本，与新样本拼接会让分析窗口跨两种时间刻度，整体丢弃后重新计数。
+                sample_buffer = RingBuffer::new(analyzer.required_samples() * 8);
+                overlap_cursor = OverlapCursor::new(analyzer.required_samples());
+                if configured.prefill_sample_buffer {
+                    prefill_with_silence(&mut sample_buffer, &mut overlap_cursor, &silence);
+                }
+            }
+            converted.clear();
+            rate_converter.process(&chunk.samples, &mut converted);
+            // 关键行：直流阻断是有状态的流式滤波，只能作用于新追加的样本，不能在分析窗口上重复执行。
+            if configured.dc_blocker {
+                dc_blocker.process(&mut converted);
+            } else {
+                dc_blocker.reset();
+            }
+            if configured.loudness {
+                // 关键行：K 计权滤波同样是流式有状态的，只处理新追加的样本。
+                loudness_meter
+                    .get_or_insert_with(|| LoudnessMeter::new(rate_converter.output_rate()))
+                    .process(&converted);
+            } else {
+                loudness_meter = None;
+            }
+            let (chunk_rms, chunk_peak) = (
+                dsp::calculate_rms(&converted),
+                dsp::calculate_peak(&converted),
+            );
+            level_meter.record(chunk.timestamp_ms, chunk_rms, chunk_peak);
+            level_history.record(chunk.timestamp_ms, chunk_rms, chunk_peak);
+            sample_buffer.extend_from_slice(&converted);
+            overlap_cursor.advance(converted.len());
+            if let (Some(stereo), Some((left, right))) = (stereo.as_mut(), &chunk.stereo) {
+                stereo.push(left, right, configured.dc_blocker);
             }
         }
 
         let now_ts = now_timestamp_ms();
         let current_config = runtime_dsp.get();
-        if now_ts.saturating_sub(last_emit_ts) < current_config.emit_interval_ms {
+
+        // 关键行：采集设备或流配置变化时返回，由调用方按新配置重建采集流。
+        if runtime_dsp.capture_device_changed(&capture_device_id)
+            || current_config.capture != capture_config
+        {
+            return Ok(CaptureSessionEnd::ConfigChanged);
+        }
+
+        // 关键行：返回即丢弃 `runtime`，其持有的采集流随之释放，系统音频设备可以进入空闲。
+        if capture_control.is_stopped() {
+            return Ok(CaptureSessionEnd::Stopped);
+        }
+
+        // 关键行：设备拔出时通道不会关闭，需结合流回调置位的错误标志判定断开。
+        if runtime.stream_failed() {
+            disconnected_at.get_or_insert_with(Instant::now);
+        }
+
+        // 断开后先保持最后一帧，超过保持时长仍未恢复才交给上层重连。
+        if disconnected_at.is_some_and(|since| {
+            since.elapsed() >= Duration::from_millis(current_config.capture_hold_ms)
+        }) {
+            return Ok(CaptureSessionEnd::Disconnected);
+        }
+
+        // 关键行：IPC 预算与耗时节奏调节都可能把发帧间隔调大，节流取其中最大值。
+        let target_interval_ms = current_config.emit_interval_ms.max(governed_interval_ms);
+        let emit_interval_ms = pacer.interval(target_interval_ms);
+        if now_ts.saturating_sub(last_emit_ts) < emit_interval_ms {
             continue;
         }
 
         if sample_buffer.len() < analyzer.required_samples() {
             continue;
         }
+        let frame_started = Instant::now();
 
         // 关键行：每次推送前读取运行时参数，保证平滑、增益、发帧频率都“实时生效”。
-        if (current_config.smoothing - last_config.smoothing).abs() > f32::EPSILON
-            || (current_config.gain - last_config.gain).abs() > f32::EPSILON
-        {
-            analyzer.set_params(DspParams {
-                smoothing: current_config.smoothing,
-                gain: current_config.gain,
-            });
+        if current_config.dsp_params() != last_config.dsp_params() {
+            analyzer.set_params(current_config.dsp_params());
+            if let Some(stereo) = stereo.as_mut() {
+                stereo.set_params(current_config.dsp_params());
+            }
+            // 关键行：窗函数变化会让整条幅度谱突变，清空起音检测状态避免误报一次鼓点。
+            if current_config.window_function != last_config.window_function {
+                beat_detector.reset();
+            }
+            if current_config.frequency_scale != last_config.frequency_scale
+                || current_config.frequency_range != last_config.frequency_range
+                || current_config.zero_pad_factor != last_config.zero_pad_factor
+            {
+                bin_frequencies = analyzer.bin_frequencies(rate_converter.output_rate());
+            }
             last_config = current_config;
         }
 
-        let frame_window_start = sample_buffer.len() - analyzer.required_samples();
-        let analysis = analyzer.analyze(&sample_buffer[frame_window_start..]);
+        // 关键行：频段数变化时就地重采样分析器缓存，并同步频率轴、清空旧长度的保持帧，避免新旧长度混用。
+        if current_config.bin_count != analyzer.bin_count() {
+            analyzer.set_bin_count(current_config.bin_count);
+            if let Some(stereo) = stereo.as_mut() {
+                stereo.set_bin_count(current_config.bin_count);
+            }
+            bin_frequencies = analyzer.bin_frequencies(rate_converter.output_rate());
+            dropout_hold = DropoutHold::default();
+            beat_detector.reset();
+        }
+
+        // 关键行：重置请求由分析线程自己消费，避免命令线程与 analyze 并发修改分析器。
+        if runtime_dsp.take_adaptation_reset() {
+            analyzer.reset_state();
+            if let Some(meter) = loudness_meter.as_mut() {
+                meter.reset();
+            }
+            if let Some(stereo) = stereo.as_mut() {
+                stereo.reset_state();
+            }
+            dropout_hold = DropoutHold::default();
+            beat_detector.reset();
+        }
+        beat_detector.set_sensitivity(current_config.beat_sensitivity);
+        analyzer.set_frame_interval(emit_interval_ms);
+        if let Some(stereo) = stereo.as_mut() {
+            stereo.set_frame_interval(emit_interval_ms);
+        }
+
+        let silence_ms = last_chunk_at.elapsed().as_millis() as u64;
+        let mut stereo_levels = None;
+        let mut beat = None;
+        let mut analysis = match dropout_hold.held_frame(
+            silence_ms,
+            current_config.capture_hold_ms,
+            current_config.capture_hold_decay,
+        ) {
+            Some(held) => held,
+            None => {
+                let required = analyzer.required_samples();
+                let latest = analyzer.latest_samples();
+                // 关键行：开启重叠分析时按 hop 推进读游标，对自上一帧以来的全部窗口取平均；否则只分析末尾整窗。
+                let analysis = match overlap::hop_samples(current_config.hop_fraction, required) {
+                    Some(hop) => {
+                        let windows = overlap_cursor.windows(&sample_buffer, required, latest, hop);
+                        analyzer.analyze_windows(&windows)
+                    }
+                    None => {
+                        overlap_cursor.follow_tail();
+                        // 关键行：开启音高检测时多取历史样本，最低可测基频才能覆盖到 50Hz。
+                        window_samples.resize(latest, 0.0);
+                        let copied = sample_buffer.copy_latest(latest, &mut window_samples);
+                        analyzer.analyze(&window_samples[..copied])
+                    }
+                };
+                dropout_hold.remember(&analysis);
+                // 关键行：只对真实分析结果做起音检测，保持帧是旧数据的重复，不应触发鼓点。
+                beat = beat_detector.process(analyzer.spectrum(), now_ts);
+                stereo_levels = stereo.as_mut().and_then(StereoAnalysis::analyze);
+                analysis
+            }
+        };
 
         // 延迟估算：采样到当前推送的时间差 + 当前发送节流间隔。
         let latency_ms =
@@ -193,62 +1292,509 @@ fn run_realtime_analysis_loop(
             continue;
         }
 
-        let frame = AnalysisFrame {
+        let silence = silence_pause.check(
+            analysis.rms,
+            current_config.silence_auto_pause,
+            current_config.silence_threshold,
+            current_config.silence_timeout_ms,
+        );
+        if silence == SilenceAction::Skip {
+            // 关键行：暂停期间仍按发帧节奏分析以便及时发现声音恢复，只是不推送。
+            last_emit_ts = now_ts;
+            continue;
+        }
+        let silent = silence == SilenceAction::EmitSilent;
+        if silent {
+            analysis.levels.fill(0.0);
+            analysis.rms = 0.0;
+            analysis.peak = 0.0;
+            analysis.centroid = 0.0;
+            analysis.pitch_hz = None;
+            if let Some(chroma) = &mut analysis.chroma {
+                chroma.fill(0.0);
+            }
+            stereo_levels = None;
+        } else {
+            if let Some(strength) = beat {
+                let _ = app.emit(
+                    BEAT_EVENT,
+                    BeatEvent {
+                        timestamp_ms: now_ts,
+                        strength,
+                    },
+                );
+            }
+            screensaver.apply(
+                &mut analysis,
+                current_config.silence_screensaver,
+                current_config.silence_screensaver_after_ms,
+            );
+        }
+        if announced_frequencies != bin_frequencies {
+            let _ = app.emit(BIN_FREQUENCIES_EVENT, &bin_frequencies);
+            announced_frequencies.clone_from(&bin_frequencies);
+        }
+        let peak_levels =
+            analyzer.update_peak_hold(&analysis.levels, now_ts.saturating_sub(last_emit_ts));
+        history.push(now_ts, &analysis.quantized_bins());
+        latest_level.record(now_ts, analysis.rms);
+        let mut frame = AnalysisFrame {
             timestamp_ms: now_ts,
             device_id: runtime.device_id.clone(),
-            bins: analysis.bins,
+            source,
+            bins: current_config.bin_format.encode(&analysis.levels),
             rms: analysis.rms,
             peak: analysis.peak,
+            centroid: analysis.centroid,
+            pitch_hz: analysis.pitch_hz,
+            chroma: analysis.chroma,
+            lufs: loudness_meter.as_ref().and_then(LoudnessMeter::integrated),
             latency_estimate_ms: latency_ms,
+            silent,
+            clip_count,
+            bins_left: stereo_levels
+                .as_ref()
+                .map(|[left, _]| current_config.bin_format.encode(left)),
+            bins_right: stereo_levels
+                .as_ref()
+                .map(|[_, right]| current_config.bin_format.encode(right)),
+            peak_bins: peak_levels.map(|peaks| current_config.bin_format.encode(&peaks)),
+            bin_colors: color_cache.resolve(&theme_state, &bin_frequencies),
         };
+        governed_interval_ms = budget.apply(
+            app,
+            &mut frame,
+            current_config.ipc_byte_budget_per_sec,
+            current_config.emit_interval_ms,
+        );
 
-        let _ = app.emit("audio:analysis_frame", frame);
+        delivery::emit_analysis_frame(
+            app,
+            &frame_delivery,
+            frame,
+            current_config.ipc_byte_budget_per_sec,
+            governed_interval_ms,
+        );
+        pacer.record(
+            app,
+            frame_started.elapsed().as_secs_f32() * 1000.0,
+            target_interval_ms,
+        );
         last_emit_ts = now_ts;
+        clip_count = 0;
     }
 }
 
 /// 模拟链路：真实采集不可用时提供可预测波形，便于前端验证渲染逻辑。
-fn run_mock_analysis_loop(
-    app: AppHandle,
-    runtime_dsp: RuntimeDspState,
-    runtime_visual: RuntimeVisualState,
-) {
+///
+/// `realtime_error` 为导致回退的真实采集错误，进入时随 `audio:source_changed` 推送一次。
+/// 采集设备或流配置变化时返回，交由调用方重新尝试真实采集。
+fn run_mock_analysis_loop(app: AppHandle, shared: AnalysisShared, realtime_error: String) {
+    let AnalysisShared {
+        runtime_dsp,
+        runtime_visual,
+        history,
+        theme_state,
+        frame_delivery,
+        latest_level,
+        level_meter,
+        level_history,
+        capture_status,
+        capture_control,
+        ..
+    } = shared;
     let mut phase: f32 = 0.0;
+    let mut mock_bin_count = 0;
+    let mut mock_scale = FrequencyScale::default();
+    let mut mock_range = FrequencyRange::default();
+    let mut mock_frequencies = Vec::new();
+    let mut color_cache = BinColorCache::default();
+    let mut budget = IpcBudgetGovernor::default();
+    let capture_device_id = runtime_dsp.capture_device_id();
+    let capture_config = runtime_dsp.get().capture;
+    capture_status.set(CaptureStatus {
+        device_id: MOCK_DEVICE_ID.to_string(),
+        device_name: "Simulated audio".to_string(),
+        sample_rate: MOCK_SAMPLE_RATE,
+        channels: 1,
+        is_mock: true,
+        source: FrameSource::Mock,
+        estimated_buffer_ms: runtime_dsp.get().emit_interval_ms as f32,
+    });
+    let _ = app.emit(
+        "audio:source_changed",
+        SourceChange {
+            source: FrameSource::Mock,
+            error: realtime_error,
+        },
+    );
 
     loop {
-        let emit_interval_ms = runtime_dsp.get().emit_interval_ms;
+        let config = runtime_dsp.get();
+        if runtime_dsp.capture_device_changed(&capture_device_id)
+            || config.capture != capture_config
+            || capture_control.is_stopped()
+        {
+            return;
+        }
+
+        let emit_interval_ms = config.emit_interval_ms;
 
         if runtime_visual.is_paused() {
             thread::sleep(Duration::from_millis(emit_interval_ms));
             continue;
         }
 
+        if config.bin_count != mock_bin_count
+            || config.frequency_scale != mock_scale
+            || config.frequency_range != mock_range
+        {
+            mock_bin_count = config.bin_count;
+            mock_scale = config.frequency_scale;
+            mock_range = config.frequency_range;
+            mock_frequencies = dsp::bin_center_frequencies(
+                mock_bin_count,
+                ANALYZER_WINDOW_SIZE,
+                MOCK_SAMPLE_RATE,
+                mock_scale,
+                mock_range,
+            );
+            let _ = app.emit(BIN_FREQUENCIES_EVENT, &mock_frequencies);
+        }
+
         phase += 0.09;
-        let bins = (0..64)
-            .map(|index| {
-                let energy = ((phase + index as f32 * 0.2).sin() * 0.5 + 0.5) * 1023.0;
-                energy.round() as u16
-            })
-            .collect::<Vec<_>>();
+        let levels = screensaver::sweep_levels(phase, mock_bin_count, 1.0);
 
         let now_ts = now_timestamp_ms();
-        let frame = AnalysisFrame {
+        let rms = ((phase * 1.2).sin() * 0.5 + 0.5).clamp(0.0, 1.0);
+        let peak = ((phase * 0.7).cos() * 0.5 + 0.5).clamp(0.0, 1.0);
+        history.push(now_ts, &dsp::quantize_levels(&levels));
+        latest_level.record(now_ts, rms);
+        level_meter.record(now_ts, rms, peak);
+        level_history.record(now_ts, rms, peak);
+        let mut frame = AnalysisFrame {
             timestamp_ms: now_ts,
-            device_id: "mock-device".to_string(),
-            bins,
-            rms: ((phase * 1.2).sin() * 0.5 + 0.5).clamp(0.0, 1.0),
-            peak: ((phase * 0.7).cos() * 0.5 + 0.5).clamp(0.0, 1.0),
+            device_id: MOCK_DEVICE_ID.to_string(),
+            source: FrameSource::Mock,
+            bins: config.bin_format.encode(&levels),
+            rms,
+            peak,
+            centroid: dsp::spectral_centroid(
+                &mock_frequencies,
+                &levels,
+                MOCK_SAMPLE_RATE as f32 * 0.5,
+            ),
             latency_estimate_ms: emit_interval_ms as f32 + 4.0,
+            silent: false,
+            clip_count: 0,
+            bins_left: None,
+            bins_right: None,
+            peak_bins: None,
+            pitch_hz: None,
+            chroma: None,
+            lufs: None,
+            bin_colors: color_cache.resolve(&theme_state, &mock_frequencies),
         };
+        let governed_interval_ms = budget.apply(
+            &app,
+            &mut frame,
+            config.ipc_byte_budget_per_sec,
+            emit_interval_ms,
+        );
 
-        let _ = app.emit("audio:analysis_frame", frame);
-        thread::sleep(Duration::from_millis(emit_interval_ms));
+        delivery::emit_analysis_frame(
+            &app,
+            &frame_delivery,
+            frame,
+            config.ipc_byte_budget_per_sec,
+            governed_interval_ms,
+        );
+        thread::sleep(Duration::from_millis(governed_interval_ms));
     }
 }
 
+/// 按当前采集采样率、分析采样率转换与频段数计算各频段中心频率（Hz）；采集尚未启动时返回错误。
+pub fn bin_frequencies(
+    capture_status: &CaptureStatusState,
+    config: &RuntimeDspConfig,
+) -> Result<Vec<f32>, AppError> {
+    let status = capture_status
+        .get()
+        .ok_or_else(|| AppError::Capture("audio capture is not running".to_string()))?;
+    // 关键行：与分析线程一致使用转换后的有效采样率；模拟链路不经过采样率转换。
+    let sample_rate = if status.is_mock {
+        status.sample_rate
+    } else {
+        resample::analysis_rate(
+            status.sample_rate,
+            config.max_analysis_sample_rate,
+            config.analysis_sample_rate,
+        )
+    };
+    // 关键行：模拟链路不做 FFT，频率轴不受补零倍数影响。
+    let grid = if status.is_mock {
+        ANALYZER_WINDOW_SIZE
+    } else {
+        ANALYZER_WINDOW_SIZE * config.zero_pad_factor.max(1)
+    };
+    Ok(dsp::bin_center_frequencies(
+        config.bin_count,
+        grid,
+        sample_rate,
+        config.frequency_scale,
+        config.frequency_range,
+    ))
+}
+
+/// 用独立分析器对固定合成信号重复分析，统计单帧耗时；不触碰实时链路的分析器状态。
+pub fn benchmark_dsp(
+    config: RuntimeDspConfig,
+    iterations: usize,
+) -> Result<DspBenchmarkReport, AppError> {
+    if !(MIN_BENCHMARK_ITERATIONS..=MAX_BENCHMARK_ITERATIONS).contains(&iterations) {
+        return Err(AppError::InvalidArgument(format!(
+            "iterations must be within {MIN_BENCHMARK_ITERATIONS}..={MAX_BENCHMARK_ITERATIONS}, got {iterations}"
+        )));
+    }
+
+    let mut analyzer = SpectrumAnalyzer::new(
+        config.bin_count,
+        ANALYZER_WINDOW_SIZE,
+        BENCHMARK_SAMPLE_RATE as u32,
+        config.dsp_params(),
+    );
+    let samples = benchmark_signal(analyzer.required_samples());
+
+    let mut min_us = f64::MAX;
+    let mut max_us = 0.0f64;
+    let mut total_us = 0.0f64;
+    for _ in 0..iterations {
+        let started = Instant::now();
+        // 关键行：black_box 防止编译器把未使用的分析结果整体优化掉。
+        std::hint::black_box(analyzer.analyze(std::hint::black_box(&samples)));
+        let elapsed_us = started.elapsed().as_secs_f64() * 1_000_000.0;
+        min_us = min_us.min(elapsed_us);
+        max_us = max_us.max(elapsed_us);
+        total_us += elapsed_us;
+    }
+
+    let avg_us = total_us / iterations as f64;
+    Ok(DspBenchmarkReport {
+        iterations,
+        bin_count: config.bin_count,
+        window_size: ANALYZER_WINDOW_SIZE,
+        min_us,
+        avg_us,
+        max_us,
+        frames_per_second: if avg_us > 0.0 {
+            1_000_000.0 / avg_us
+        } else {
+            0.0
+        },
+    })
+}
+
+/// 生成可复现的合成信号：低/中/高三个正弦叠加确定性伪噪声，覆盖多数频段。
+fn benchmark_signal(len: usize) -> Vec<f32> {
+    let mut noise_state = 0x2545_f491u32;
+    (0..len)
+        .map(|index| {
+            let t = index as f32 / BENCHMARK_SAMPLE_RATE;
+            noise_state ^= noise_state << 13;
+            noise_state ^= noise_state >> 17;
+            noise_state ^= noise_state << 5;
+            let noise = (noise_state as f32 / u32::MAX as f32) * 2.0 - 1.0;
+            0.4 * (2.0 * std::f32::consts::PI * 110.0 * t).sin()
+                + 0.25 * (2.0 * std::f32::consts::PI * 1_000.0 * t).sin()
+                + 0.15 * (2.0 * std::f32::consts::PI * 6_000.0 * t).sin()
+                + 0.05 * noise
+        })
+        .collect()
+}
+
 /// 统一毫秒时间戳函数，避免多处实现不一致。
 fn now_timestamp_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map_or(0, |duration| duration.as_millis() as u64)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn real_frame() -> SpectrumFrame {
+        SpectrumFrame {
+            levels: vec![0.2, 0.6, 0.9],
+            rms: 0.3,
+            peak: 0.8,
+            centroid: 0.4,
+            pitch_hz: Some(220.0),
+            chroma: None,
+        }
+    }
+
+    #[test]
+    fn short_gap_repeats_the_last_real_frame() {
+        let mut hold = DropoutHold::default();
+        assert!(hold.held_frame(200, 300, false).is_none());
+        hold.remember(&real_frame());
+
+        // 关键行：200ms 断流仍在保持期内，重复最近一帧真实频谱而不是切到模拟数据。
+        let held = hold.held_frame(200, 300, false).expect("within hold");
+        assert_eq!(held.levels, real_frame().levels);
+        assert_eq!((held.rms, held.peak), (0.3, 0.8));
+        assert_eq!(held.pitch_hz, None);
+
+        // 未超过断流判定阈值时照常分析；超过保持时长后交还实时分析。
+        assert!(hold.held_frame(DROPOUT_DETECT_MS, 300, false).is_none());
+        assert!(hold
+            .held_frame(DROPOUT_DETECT_MS + 301, 300, false)
+            .is_none());
+    }
+
+    #[test]
+    fn held_frame_fades_out_across_the_hold_when_decay_is_on() {
+        let mut hold = DropoutHold::default();
+        hold.remember(&real_frame());
+        let halfway = hold
+            .held_frame(DROPOUT_DETECT_MS + 150, 300, true)
+            .expect("within hold");
+        assert!((halfway.levels[2] - 0.45).abs() < 1e-6);
+        assert!((halfway.rms - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn session_source_is_never_mock_for_real_devices() {
+        // 断流保持只替换频谱内容，帧来源在会话开始时按设备确定，保持期间不会变成 `mock`。
+        assert_eq!(
+            FrameSource::for_device(&format!("{}default", capture::LOOPBACK_ID_PREFIX)),
+            FrameSource::Loopback
+        );
+        assert_eq!(FrameSource::for_device("input:mic"), FrameSource::Realtime);
+    }
+
+    /// 一个发帧周期（约 16ms）内采集到的 48kHz 样本数。
+    const ONE_INTERVAL_SAMPLES: usize = 768;
+
+    fn sine(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|index| 0.5 * (index as f32 * 0.05).sin())
+            .collect()
+    }
+
+    #[test]
+    fn prefill_allows_the_first_emit_within_one_interval() {
+        let mut analyzer =
+            SpectrumAnalyzer::new(32, ANALYZER_WINDOW_SIZE, 48_000, DspParams::default());
+        let required = analyzer.required_samples();
+        let mut buffer = RingBuffer::<f32>::new(required * 8);
+        let mut cursor = OverlapCursor::new(required);
+        prefill_with_silence(&mut buffer, &mut cursor, &vec![0.0; required]);
+
+        // 关键行：只到达一个发帧周期的真实样本，缓存已足一窗，首帧不必等满一窗。
+        let chunk = sine(ONE_INTERVAL_SAMPLES);
+        buffer.extend_from_slice(&chunk);
+        cursor.advance(chunk.len());
+        assert!(buffer.len() >= required);
+        let windows = cursor.windows(&buffer, required, required, required / 2);
+        assert!(!windows.is_empty());
+        assert!(analyzer.analyze_windows(&windows).rms > 0.0);
+    }
+
+    #[test]
+    fn zero_prefill_does_not_bias_levels_after_one_window() {
+        let required = ANALYZER_WINDOW_SIZE;
+        let signal = sine(required);
+        let mut expected = SpectrumAnalyzer::new(32, required, 48_000, DspParams::default());
+        let expected = expected.analyze(&signal);
+
+        let mut buffer = RingBuffer::<f32>::new(required * 8);
+        let mut cursor = OverlapCursor::new(required);
+        prefill_with_silence(&mut buffer, &mut cursor, &vec![0.0; required]);
+        buffer.extend_from_slice(&signal);
+        cursor.advance(signal.len());
+
+        // 关键行：真实样本满一窗后静音已被挤出末尾窗口，RMS/峰值与不预填充时一致。
+        let mut latest = vec![0.0; required];
+        buffer.copy_latest(required, &mut latest);
+        let mut analyzer = SpectrumAnalyzer::new(32, required, 48_000, DspParams::default());
+        let frame = analyzer.analyze(&latest);
+        assert_eq!(frame.rms, expected.rms);
+        assert_eq!(frame.peak, expected.peak);
+    }
+
+    fn encoded_frame(format: BinFormat) -> AnalysisFrame {
+        let levels = [0.0, 0.5, 1.5];
+        AnalysisFrame {
+            timestamp_ms: 0,
+            device_id: "mic".to_string(),
+            source: FrameSource::Realtime,
+            bins: format.encode(&levels),
+            rms: 0.1,
+            peak: 0.2,
+            centroid: 0.3,
+            latency_estimate_ms: 10.0,
+            silent: false,
+            clip_count: 0,
+            bins_left: None,
+            bins_right: None,
+            peak_bins: Some(format.encode(&levels)),
+            pitch_hz: None,
+            chroma: None,
+            lufs: None,
+            bin_colors: None,
+        }
+    }
+
+    #[test]
+    fn f32_format_serializes_bins_f32_only() {
+        let json = serde_json::to_value(encoded_frame(BinFormat::F32)).unwrap();
+        // 关键行：f32 格式只发送 `binsF32`（夹到 0..1），不会同时附带量化的 `bins`。
+        assert_eq!(json["binsF32"], serde_json::json!([0.0, 0.5, 1.0]));
+        assert!(json.get("bins").is_none());
+        assert_eq!(json["peakBins"], serde_json::json!([0.0, 0.5, 1.0]));
+
+        let json = serde_json::to_value(encoded_frame(BinFormat::U16)).unwrap();
+        assert!(json["bins"].is_array());
+        assert!(json.get("binsF32").is_none());
+    }
+
+    #[test]
+    fn benchmark_reports_plausible_non_zero_timings() {
+        let config = runtime_config_from_settings(&settings::AppSettings::default());
+        let report = benchmark_dsp(config, 20).expect("valid iteration count");
+        assert_eq!(report.iterations, 20);
+        assert_eq!(report.bin_count, config.bin_count);
+        assert_eq!(report.window_size, ANALYZER_WINDOW_SIZE);
+
+        // 关键行：单次分析必有可测耗时，且远低于一秒；统计量之间保持有序。
+        assert!(report.min_us > 0.0, "{report:?}");
+        assert!(report.min_us <= report.avg_us && report.avg_us <= report.max_us);
+        assert!(report.max_us < 1_000_000.0, "{report:?}");
+        assert!((report.frames_per_second * report.avg_us - 1_000_000.0).abs() < 1.0);
+
+        assert!(matches!(
+            benchmark_dsp(config, 0),
+            Err(AppError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn mix_loopback_follows_the_configured_output_device() {
+        let mut settings = settings::AppSettings {
+            mix_default_input: true,
+            output_device_id: "output:Headphones".to_string(),
+            ..settings::AppSettings::default()
+        };
+        // 关键行：混合采集且未选择采集设备时，主来源回环设置中的输出设备而不是默认输出。
+        assert_eq!(capture_target_device_id(&settings), "output:Headphones");
+
+        settings.capture_device_id = "input:Microphone".to_string();
+        assert_eq!(capture_target_device_id(&settings), "input:Microphone");
+
+        settings.capture_device_id.clear();
+        settings.mix_default_input = false;
+        assert_eq!(capture_target_device_id(&settings), "");
+    }
+}
@@ -0,0 +1,96 @@
+use crate::audio::ring_buffer::RingBuffer;
+
+/// 单帧最多分析的重叠窗口数；积压更多时跳过最老的窗口，限制单帧 FFT 开销。
+const MAX_OVERLAP_WINDOWS: u64 = 8;
+/// hop 下限为窗长的 1/8，再小只会增加开销而不再改善时间分辨率。
+const MIN_HOP_DIVISOR: usize = 8;
+
+/// 按设置中的 hop 比例换算 hop 样本数；比例不大于 0 时关闭重叠分析。
+pub(super) fn hop_samples(hop_fraction: f32, window_size: usize) -> Option<usize> {
+    if hop_fraction <= 0.0 {
+        return None;
+    }
+    let min_hop = (window_size / MIN_HOP_DIVISOR).max(1);
+    Some(((window_size as f32 * hop_fraction).round() as usize).clamp(min_hop, window_size.max(1)))
+}
+
+/// 重叠 STFT 的读游标：记录已写入样本总数与下一个待分析窗口的结束位置（绝对样本序号），
+/// 每次发帧取出自上次以来按 hop 推进的全部窗口，而不是只截取缓存末尾。
+pub(super) struct OverlapCursor {
+    written: u64,
+    next_end: u64,
+    scratch: Vec<f32>,
+}
+
+impl OverlapCursor {
+    pub(super) fn new(window_size: usize) -> Self {
+        Self {
+            written: 0,
+            next_end: window_size as u64,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// 记录新写入样本缓存的样本数。
+    pub(super) fn advance(&mut self, samples: usize) {
+        self.written += samples as u64;
+    }
+
+    /// 关闭重叠分析期间让游标跟随缓存末尾，重新开启时从最新窗口开始。
+    pub(super) fn follow_tail(&mut self) {
+        self.next_end = self.written;
+    }
+
+    /// 取出本帧待分析的窗口（按时间先后，相邻窗口相隔 `hop`）。
+    ///
+    /// 自上次以来不足一个 hop 时重复最近一个窗口，保证按发帧节奏持续出帧；
+    /// 积压超过缓存或 `MAX_OVERLAP_WINDOWS` 时从仍可用的最老窗口开始。调用方须保证缓存中至少有一窗样本。
+    /// 最后一个窗口在缓存允许时向前延伸到 `latest_size` 个样本，供音高检测使用更长的历史。
+    pub(super) fn windows(
+        &mut self,
+        buffer: &RingBuffer<f32>,
+        window_size: usize,
+        latest_size: usize,
+        hop: usize,
+    ) -> Vec<&[f32]> {
+        let hop = hop as u64;
+        let window = window_size as u64;
+        let max_lag = (buffer.len() as u64)
+            .saturating_sub(window)
+            .min((MAX_OVERLAP_WINDOWS - 1) * hop);
+        let earliest_end = self.written - max_lag;
+
+        let (first_end, count) = if self.next_end > self.written {
+            let latest_end = self
+                .next_end
+                .saturating_sub(hop)
+                .clamp(earliest_end, self.written);
+            (latest_end, 1)
+        } else {
+            let first_end = self.next_end.max(earliest_end);
+            let count = (self.written - first_end) / hop + 1;
+            self.next_end = first_end + count * hop;
+            (first_end, count)
+        };
+
+        // 关键行：一次复制覆盖全部窗口的连续区间，各窗口是其中按 hop 错开的切片。
+        let span = self.written - first_end + window;
+        let lookback = (latest_size.saturating_sub(window_size) as u64)
+            .min((buffer.len() as u64).saturating_sub(span)) as usize;
+        let span = span as usize + lookback;
+        self.scratch.resize(span, 0.0);
+        buffer.copy_latest(span, &mut self.scratch);
+        let last = count as usize - 1;
+        (0..count as usize)
+            .map(|index| {
+                let start = lookback + index * hop as usize;
+                let from = if index == last {
+                    start - lookback
+                } else {
+                    start
+                };
+                &self.scratch[from..start + window_size]
+            })
+            .collect()
+    }
+}
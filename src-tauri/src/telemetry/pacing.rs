@@ -0,0 +1,94 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// 发帧节奏调整事件名：实际发帧间隔因耗时过载而放慢或恢复时推送一次。
+pub const FRAME_RATE_ADJUSTED_EVENT: &str = "audio:frame_rate_adjusted";
+/// 单帧耗时滑动平均的新样本权重。
+const FRAME_COST_SMOOTHING: f32 = 0.2;
+/// 平均耗时超过当前间隔的该比例视为过载，低于该比例视为已恢复余量。
+const OVERLOAD_RATIO: f32 = 0.9;
+const RECOVER_RATIO: f32 = 0.5;
+/// 连续多少帧过载或有余量才调整一次，避免偶发卡顿引起节奏抖动。
+const ADJUST_AFTER_FRAMES: u32 = 8;
+/// 放慢后的发帧间隔上限（毫秒），约 10 Hz，再慢画面就失去跟手感。
+const MAX_PACED_INTERVAL_MS: u64 = 100;
+
+/// `audio:frame_rate_adjusted` 事件负载。
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FrameRateAdjusted {
+    /// 当前实际发帧间隔与对应帧率。
+    emit_interval_ms: u64,
+    frame_rate_hz: f32,
+    /// 触发调整时单帧“分析 + 推送”的平均耗时（毫秒）。
+    average_frame_ms: f32,
+    /// 是否仍慢于设置的发帧间隔。
+    backed_off: bool,
+}
+
+/// 发帧节奏调节器：统计每帧分析与推送的耗时，持续超过发帧间隔时逐步放慢，耗时回落后逐步回到设置值。
+///
+/// 只影响分析线程的节流间隔，不修改持久化的画质档位。
+#[derive(Default)]
+pub(super) struct FramePacer {
+    average_frame_ms: f32,
+    /// 放慢后的间隔；`None` 表示按设置间隔发帧。
+    paced_interval_ms: Option<u64>,
+    overloaded_frames: u32,
+    relaxed_frames: u32,
+}
+
+impl FramePacer {
+    /// 按调节结果返回实际发帧间隔，不会快于 `target_ms`。
+    pub(super) fn interval(&self, target_ms: u64) -> u64 {
+        self.paced_interval_ms
+            .map_or(target_ms, |paced| paced.max(target_ms))
+    }
+
+    /// 记录一帧的分析与推送耗时，并按滑动平均决定是否放慢或恢复；间隔变化时推送事件。
+    pub(super) fn record(&mut self, app: &AppHandle, frame_ms: f32, target_ms: u64) {
+        self.average_frame_ms = if self.average_frame_ms > 0.0 {
+            self.average_frame_ms * (1.0 - FRAME_COST_SMOOTHING) + frame_ms * FRAME_COST_SMOOTHING
+        } else {
+            frame_ms
+        };
+
+        let current = self.interval(target_ms);
+        let average = self.average_frame_ms;
+        if average > current as f32 * OVERLOAD_RATIO {
+            self.overloaded_frames += 1;
+            self.relaxed_frames = 0;
+        } else if average < current as f32 * RECOVER_RATIO && current > target_ms {
+            self.relaxed_frames += 1;
+            self.overloaded_frames = 0;
+        } else {
+            self.overloaded_frames = 0;
+            self.relaxed_frames = 0;
+        }
+
+        let next = if self.overloaded_frames >= ADJUST_AFTER_FRAMES {
+            // 关键行：每次放慢约四分之一，逐级逼近能跟上的节奏，而不是一次跳到上限。
+            (current + current.div_ceil(4)).min(MAX_PACED_INTERVAL_MS.max(target_ms))
+        } else if self.relaxed_frames >= ADJUST_AFTER_FRAMES {
+            (current - current / 5).max(target_ms)
+        } else {
+            return;
+        };
+        self.overloaded_frames = 0;
+        self.relaxed_frames = 0;
+        if next == current {
+            return;
+        }
+
+        self.paced_interval_ms = (next > target_ms).then_some(next);
+        let _ = app.emit(
+            FRAME_RATE_ADJUSTED_EVENT,
+            FrameRateAdjusted {
+                emit_interval_ms: next,
+                frame_rate_hz: 1000.0 / next.max(1) as f32,
+                average_frame_ms: average,
+                backed_off: next > target_ms,
+            },
+        );
+    }
+}
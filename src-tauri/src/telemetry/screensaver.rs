@@ -0,0 +1,104 @@
+use crate::audio::dsp::SpectrumFrame;
+use std::time::{Duration, Instant};
+
+/// 低于该 RMS 视为静音；采集正常但无声时真实 RMS 通常远低于此值。
+const SILENCE_RMS_THRESHOLD: f32 = 0.002;
+/// 进入屏保时从实时画面淡入的时长。
+const SCREENSAVER_FADE_IN: Duration = Duration::from_millis(1500);
+/// 屏保图案每帧的相位推进量，约为模拟波形速度的四分之一。
+const SCREENSAVER_PHASE_STEP: f32 = 0.022;
+/// 屏保图案幅度，保持画面“活着”但不抢眼。
+const SCREENSAVER_AMPLITUDE: f32 = 0.35;
+
+/// 生成与模拟链路同形的 0..1 正弦扫频频段，`amplitude` 为相对满幅的比例。
+pub(super) fn sweep_levels(phase: f32, bin_count: usize, amplitude: f32) -> Vec<f32> {
+    (0..bin_count)
+        .map(|index| ((phase + index as f32 * 0.2).sin() * 0.5 + 0.5) * amplitude)
+        .collect()
+}
+
+/// 静音屏保：采集正常但持续无声时，逐渐过渡到缓慢扫频图案；真实音频恢复时立即切回。
+///
+/// 与模拟回退不同，这里只替换展示的频段，RMS/峰值仍保留实时值。
+#[derive(Default)]
+pub(super) struct SilenceScreensaver {
+    silent_since: Option<Instant>,
+    phase: f32,
+}
+
+impl SilenceScreensaver {
+    /// 根据当前帧的 RMS 更新静音计时，并在需要时把屏保图案混入频段。
+    pub(super) fn apply(&mut self, frame: &mut SpectrumFrame, enabled: bool, after_ms: u64) {
+        if frame.rms > SILENCE_RMS_THRESHOLD || !enabled {
+            // 关键行：一旦检测到声音立即清零计时，下一帧就是纯实时数据。
+            self.silent_since = None;
+            return;
+        }
+
+        let silent_for = self.silent_since.get_or_insert_with(Instant::now).elapsed();
+        let Some(in_screensaver) = silent_for.checked_sub(Duration::from_millis(after_ms)) else {
+            return;
+        };
+
+        let mix = (in_screensaver.as_secs_f32() / SCREENSAVER_FADE_IN.as_secs_f32()).min(1.0);
+        self.phase += SCREENSAVER_PHASE_STEP;
+        let pattern = sweep_levels(self.phase, frame.levels.len(), SCREENSAVER_AMPLITUDE);
+        for (level, generated) in frame.levels.iter_mut().zip(pattern) {
+            *level = *level * (1.0 - mix) + generated * mix;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const AFTER_MS: u64 = 30;
+
+    fn frame(rms: f32) -> SpectrumFrame {
+        SpectrumFrame {
+            levels: vec![0.0; 16],
+            rms,
+            peak: rms,
+            centroid: 0.0,
+            pitch_hz: None,
+            chroma: None,
+        }
+    }
+
+    fn is_untouched(frame: &SpectrumFrame) -> bool {
+        frame.levels.iter().all(|level| *level == 0.0)
+    }
+
+    #[test]
+    fn screensaver_starts_after_timeout_and_stops_on_sound() {
+        let mut screensaver = SilenceScreensaver::default();
+        let mut silent = frame(0.0);
+        screensaver.apply(&mut silent, true, AFTER_MS);
+        assert!(is_untouched(&silent), "pattern before the timeout");
+
+        thread::sleep(Duration::from_millis(AFTER_MS * 3));
+        let mut silent = frame(0.0);
+        screensaver.apply(&mut silent, true, AFTER_MS);
+        assert!(!is_untouched(&silent), "no pattern after the timeout");
+
+        // 关键行：声音恢复的这一帧保持实时数据，之后的静音重新计时。
+        let mut loud = frame(0.2);
+        screensaver.apply(&mut loud, true, AFTER_MS);
+        assert!(is_untouched(&loud));
+        let mut silent = frame(0.0);
+        screensaver.apply(&mut silent, true, AFTER_MS);
+        assert!(is_untouched(&silent), "silence timer was not restarted");
+    }
+
+    #[test]
+    fn disabled_screensaver_never_replaces_levels() {
+        let mut screensaver = SilenceScreensaver::default();
+        let mut silent = frame(0.0);
+        screensaver.apply(&mut silent, false, 0);
+        thread::sleep(Duration::from_millis(AFTER_MS));
+        screensaver.apply(&mut silent, false, 0);
+        assert!(is_untouched(&silent));
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+
+/// 本次分析结果的发帧方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SilenceAction {
+    /// 正常发送分析结果。
+    Emit,
+    /// 刚进入静音暂停：发送一帧归零帧并标记 `silent`，让前端画面落到静止状态。
+    EmitSilent,
+    /// 静音暂停中：不发帧，省去 IPC 与前端渲染开销。
+    Skip,
+}
+
+/// 静音自动暂停：RMS 持续低于阈值超过设定时长后停止发帧，能量恢复的第一帧立即继续。
+#[derive(Default)]
+pub(super) struct SilencePause {
+    silent_since: Option<Instant>,
+    paused: bool,
+}
+
+impl SilencePause {
+    /// 根据本帧 RMS 更新静音计时并返回发帧方式；未开启时始终正常发送。
+    pub(super) fn check(
+        &mut self,
+        rms: f32,
+        enabled: bool,
+        threshold: f32,
+        timeout_ms: u64,
+    ) -> SilenceAction {
+        if !enabled || rms > threshold {
+            self.silent_since = None;
+            self.paused = false;
+            return SilenceAction::Emit;
+        }
+
+        let silent_for = self.silent_since.get_or_insert_with(Instant::now).elapsed();
+        if silent_for < Duration::from_millis(timeout_ms) {
+            return SilenceAction::Emit;
+        }
+        if self.paused {
+            SilenceAction::Skip
+        } else {
+            self.paused = true;
+            SilenceAction::EmitSilent
+        }
+    }
+}
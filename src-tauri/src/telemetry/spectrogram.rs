@@ -0,0 +1,218 @@
+use crate::audio::dsp::BIN_FULL_SCALE;
+use crate::audio::ring_buffer::RingBuffer;
+use crate::error::AppError;
+use image::{ImageFormat, Rgb, RgbImage};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// 滚动历史容量：按 Ultra 档位约 120 帧/秒估算，可覆盖最长导出时长。
+const HISTORY_CAPACITY: usize = 4096;
+/// 单次导出允许的录制时长范围（毫秒）。
+pub const MIN_EXPORT_DURATION_MS: u64 = 100;
+pub const MAX_EXPORT_DURATION_MS: u64 = 30_000;
+
+/// 频谱图导出格式：CSV 便于表格分析，PNG 便于文档展示。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrogramFormat {
+    Csv,
+    Png,
+}
+
+impl SpectrogramFormat {
+    /// 解析前端传入的格式字符串，非法值直接报错而不是静默回退。
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "png" => Ok(Self::Png),
+            other => Err(AppError::InvalidArgument(format!(
+                "unsupported spectrogram format: {other}"
+            ))),
+        }
+    }
+}
+
+/// 频谱历史中的单帧快照。
+#[derive(Debug, Clone)]
+struct SpectrogramEntry {
+    timestamp_ms: u64,
+    bins: Vec<u16>,
+}
+
+/// 频谱滚动历史：分析线程在发帧点写入，命令层按时间窗口读取导出。
+#[derive(Clone)]
+pub struct SpectrogramHistory {
+    inner: Arc<Mutex<RingBuffer<SpectrogramEntry>>>,
+}
+
+impl Default for SpectrogramHistory {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RingBuffer::new(HISTORY_CAPACITY))),
+        }
+    }
+}
+
+impl SpectrogramHistory {
+    /// 记录一帧已发送给前端的频谱，满时自动覆盖最老数据。
+    pub fn push(&self, timestamp_ms: u64, bins: &[u16]) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.push(SpectrogramEntry {
+                timestamp_ms,
+                bins: bins.to_vec(),
+            });
+        }
+    }
+
+    /// 取出指定时间窗口内的帧（含边界），按时间先后排列。
+    fn frames_between(&self, start_ms: u64, end_ms: u64) -> Vec<SpectrogramEntry> {
+        self.inner
+            .lock()
+            .map(|guard| {
+                guard
+                    .iter()
+                    .filter(|entry| entry.timestamp_ms >= start_ms && entry.timestamp_ms <= end_ms)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// 录制指定时长的频谱并写入文件，返回最终写入路径。
+///
+/// 该函数会阻塞调用线程直到录制窗口结束，命令层需在后台线程调用。
+pub fn record_and_export(
+    history: &SpectrogramHistory,
+    duration_ms: u64,
+    path: &str,
+    format: SpectrogramFormat,
+) -> Result<String, AppError> {
+    if !(MIN_EXPORT_DURATION_MS..=MAX_EXPORT_DURATION_MS).contains(&duration_ms) {
+        return Err(AppError::InvalidArgument(format!(
+            "duration must be within {MIN_EXPORT_DURATION_MS}..={MAX_EXPORT_DURATION_MS} ms, got {duration_ms}"
+        )));
+    }
+
+    let trimmed_path = path.trim();
+    if trimmed_path.is_empty() {
+        return Err(AppError::InvalidArgument(
+            "export path must not be empty".to_string(),
+        ));
+    }
+
+    let start_ms = super::now_timestamp_ms();
+    thread::sleep(Duration::from_millis(duration_ms));
+    let end_ms = super::now_timestamp_ms();
+
+    let frames = history.frames_between(start_ms, end_ms);
+    if frames.is_empty() {
+        return Err(AppError::Export(
+            "no frames captured during recording window (visualization paused?)".to_string(),
+        ));
+    }
+
+    let output_path = Path::new(trimmed_path);
+    if let Some(parent) = output_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|err| AppError::Export(format!("failed to create export directory: {err}")))?;
+    }
+
+    match format {
+        SpectrogramFormat::Csv => write_csv(output_path, &frames, start_ms)?,
+        SpectrogramFormat::Png => write_png(output_path, &frames)?,
+    }
+
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+/// CSV 布局：每行一帧（相对录制起点的毫秒偏移），每列一个频段。
+fn write_csv(path: &Path, frames: &[SpectrogramEntry], start_ms: u64) -> Result<(), AppError> {
+    let bin_count = frames
+        .iter()
+        .map(|entry| entry.bins.len())
+        .max()
+        .unwrap_or(0);
+    let mut content = String::with_capacity(frames.len() * (bin_count * 5 + 8));
+
+    content.push_str("offset_ms");
+    for index in 0..bin_count {
+        content.push_str(&format!(",bin_{index}"));
+    }
+    content.push('\n');
+
+    for entry in frames {
+        content.push_str(&entry.timestamp_ms.saturating_sub(start_ms).to_string());
+        for index in 0..bin_count {
+            // 关键行：运行时切换频段数时，缺失列补 0，保证 CSV 列数一致。
+            let value = entry.bins.get(index).copied().unwrap_or(0);
+            content.push(',');
+            content.push_str(&value.to_string());
+        }
+        content.push('\n');
+    }
+
+    fs::write(path, content)
+        .map_err(|err| AppError::Export(format!("failed to write spectrogram csv: {err}")))
+}
+
+/// PNG 布局：横轴为时间，纵轴为频段（低频在底部），像素颜色由幅值查色表得到。
+fn write_png(path: &Path, frames: &[SpectrogramEntry]) -> Result<(), AppError> {
+    let bin_count = frames
+        .iter()
+        .map(|entry| entry.bins.len())
+        .max()
+        .unwrap_or(0);
+    if bin_count == 0 {
+        return Err(AppError::Export(
+            "captured frames contain no bins".to_string(),
+        ));
+    }
+
+    let width = frames.len() as u32;
+    let height = bin_count as u32;
+    let mut image = RgbImage::new(width, height);
+
+    for (x, entry) in frames.iter().enumerate() {
+        for index in 0..bin_count {
+            let value = entry.bins.get(index).copied().unwrap_or(0) as f32 / BIN_FULL_SCALE;
+            let y = height - 1 - index as u32;
+            image.put_pixel(x as u32, y, heatmap_color(value));
+        }
+    }
+
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(|err| AppError::Export(format!("failed to write spectrogram png: {err}")))
+}
+
+/// 热力色表：黑 -> 深蓝 -> 紫 -> 橙 -> 浅黄，低幅值保持暗色以突出频谱结构。
+fn heatmap_color(value: f32) -> Rgb<u8> {
+    const STOPS: [(f32, [f32; 3]); 5] = [
+        (0.0, [0.0, 0.0, 0.0]),
+        (0.25, [20.0, 24.0, 110.0]),
+        (0.5, [140.0, 30.0, 150.0]),
+        (0.75, [240.0, 120.0, 30.0]),
+        (1.0, [255.0, 250.0, 200.0]),
+    ];
+
+    let value = value.clamp(0.0, 1.0);
+    for pair in STOPS.windows(2) {
+        let (low_pos, low_color) = pair[0];
+        let (high_pos, high_color) = pair[1];
+        if value <= high_pos {
+            let t = (value - low_pos) / (high_pos - low_pos);
+            let channel =
+                |c: usize| (low_color[c] + (high_color[c] - low_color[c]) * t).round() as u8;
+            return Rgb([channel(0), channel(1), channel(2)]);
+        }
+    }
+
+    let [r, g, b] = STOPS[STOPS.len() - 1].1;
+    Rgb([r as u8, g as u8, b as u8])
+}
@@ -0,0 +1,153 @@
+use crate::audio::dsp::{DcBlocker, DspParams, SpectrumAnalyzer};
+use crate::audio::resample::AnalysisRateConverter;
+
+/// 单个声道的采样率转换器、直流阻断、样本缓存与分析器，平滑与频段基线按声道独立维护。
+struct ChannelAnalysis {
+    rate_converter: AnalysisRateConverter,
+    dc_blocker: DcBlocker,
+    buffer: Vec<f32>,
+    analyzer: SpectrumAnalyzer,
+}
+
+/// 立体声分析：左右声道各自转换采样率、缓存与分析，单声道主链路保持不变。
+/// 中侧模式下两路分别承载中置 `(L+R)/2` 与侧向 `(L-R)/2`。
+pub(super) struct StereoAnalysis {
+    channels: [ChannelAnalysis; 2],
+    mid_side: bool,
+    /// 中侧变换的复用缓冲，避免每个数据块分配。
+    mid_side_scratch: [Vec<f32>; 2],
+}
+
+impl StereoAnalysis {
+    pub(super) fn new(
+        device_rate: u32,
+        max_analysis_rate: u32,
+        target_rate: u32,
+        bin_count: usize,
+        window_size: usize,
+        params: DspParams,
+        mid_side: bool,
+    ) -> Self {
+        let params = channel_params(params);
+        let channel = || {
+            let rate_converter =
+                AnalysisRateConverter::new(device_rate, max_analysis_rate, target_rate);
+            ChannelAnalysis {
+                dc_blocker: DcBlocker::new(rate_converter.output_rate()),
+                analyzer: SpectrumAnalyzer::new(
+                    bin_count,
+                    window_size,
+                    rate_converter.output_rate(),
+                    params,
+                ),
+                rate_converter,
+                buffer: Vec::with_capacity(8192),
+            }
+        };
+        Self {
+            channels: [channel(), channel()],
+            mid_side,
+            mid_side_scratch: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// 追加一个数据块的左右声道样本，直流阻断开关与缓存上限均与单声道链路一致。
+    pub(super) fn push(&mut self, left: &[f32], right: &[f32], dc_block: bool) {
+        let [mid, side] = &mut self.mid_side_scratch;
+        let (first, second) = if self.mid_side {
+            mid.clear();
+            side.clear();
+            for (&l, &r) in left.iter().zip(right) {
+                mid.push((l + r) * 0.5);
+                side.push((l - r) * 0.5);
+            }
+            (mid.as_slice(), side.as_slice())
+        } else {
+            (left, right)
+        };
+
+        for (channel, samples) in self.channels.iter_mut().zip([first, second]) {
+            let appended_from = channel.buffer.len();
+            channel.rate_converter.process(samples, &mut channel.buffer);
+            if dc_block {
+                channel
+                    .dc_blocker
+                    .process(&mut channel.buffer[appended_from..]);
+            } else {
+                channel.dc_blocker.reset();
+            }
+            let required = channel.analyzer.required_samples();
+            if channel.buffer.len() > required * 8 {
+                let drain_count = channel.buffer.len() - required * 4;
+                channel.buffer.drain(0..drain_count);
+            }
+        }
+    }
+
+    /// 分析采样率上限或固定分析采样率变化时重建采样率转换器。
+    pub(super) fn set_rate_conversion(
+        &mut self,
+        device_rate: u32,
+        max_analysis_rate: u32,
+        target_rate: u32,
+    ) {
+        for channel in &mut self.channels {
+            channel.rate_converter =
+                AnalysisRateConverter::new(device_rate, max_analysis_rate, target_rate);
+            channel.dc_blocker = DcBlocker::new(channel.rate_converter.output_rate());
+            channel
+                .analyzer
+                .set_sample_rate(channel.rate_converter.output_rate());
+        }
+    }
+
+    pub(super) fn set_params(&mut self, params: DspParams) {
+        let params = channel_params(params);
+        for channel in &mut self.channels {
+            channel.analyzer.set_params(params);
+        }
+    }
+
+    pub(super) fn set_bin_count(&mut self, bin_count: usize) {
+        for channel in &mut self.channels {
+            channel.analyzer.set_bin_count(bin_count);
+        }
+    }
+
+    pub(super) fn set_frame_interval(&mut self, interval_ms: u64) {
+        for channel in &mut self.channels {
+            channel.analyzer.set_frame_interval(interval_ms);
+        }
+    }
+
+    pub(super) fn reset_state(&mut self) {
+        for channel in &mut self.channels {
+            channel.analyzer.reset_state();
+        }
+    }
+
+    /// 分析两路末尾整窗，返回 `[左, 右]`（中侧模式为 `[中置, 侧向]`）频段；任一路样本不足一窗时返回 `None`。
+    pub(super) fn analyze(&mut self) -> Option<[Vec<f32>; 2]> {
+        if self
+            .channels
+            .iter()
+            .any(|channel| channel.buffer.len() < channel.analyzer.required_samples())
+        {
+            return None;
+        }
+
+        Some(self.channels.each_mut().map(|channel| {
+            let start = channel.buffer.len() - channel.analyzer.required_samples();
+            channel.analyzer.analyze(&channel.buffer[start..]).levels
+        }))
+    }
+}
+
+/// 分声道只输出频段，音高与色度由单声道主链路负责，避免每帧重复计算。
+fn channel_params(params: DspParams) -> DspParams {
+    DspParams {
+        pitch_detection: false,
+        chroma: false,
+        ..params
+    }
+}
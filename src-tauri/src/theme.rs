@@ -0,0 +1,213 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// 单个主题频段允许的最大数量，避免前端传入异常大数组拖慢逐帧查色。
+const MAX_THEME_BANDS: usize = 32;
+
+/// 主题频段：`[min_hz, max_hz)` 区间内的频段统一使用同一颜色。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeBand {
+    pub min_hz: f32,
+    pub max_hz: f32,
+    pub color: [u8; 3],
+}
+
+/// 频段配色主题：由后端统一维护，保证多个前端渲染颜色一致。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Theme {
+    pub bands: Vec<ThemeBand>,
+    /// 未落入任何频段（频段之间的空隙）时使用的颜色。
+    pub fallback_color: [u8; 3],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            bands: vec![
+                ThemeBand {
+                    min_hz: 0.0,
+                    max_hz: 250.0,
+                    color: [255, 92, 92],
+                },
+                ThemeBand {
+                    min_hz: 250.0,
+                    max_hz: 2000.0,
+                    color: [255, 196, 92],
+                },
+                ThemeBand {
+                    min_hz: 2000.0,
+                    max_hz: 6000.0,
+                    color: [92, 200, 255],
+                },
+                ThemeBand {
+                    min_hz: 6000.0,
+                    max_hz: 24000.0,
+                    color: [176, 124, 255],
+                },
+            ],
+            fallback_color: [200, 200, 200],
+        }
+    }
+}
+
+impl Theme {
+    /// 校验频段覆盖：至少一个频段、区间合法且互不重叠。
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.bands.is_empty() {
+            return Err(AppError::InvalidArgument(
+                "theme must contain at least one band".to_string(),
+            ));
+        }
+        if self.bands.len() > MAX_THEME_BANDS {
+            return Err(AppError::InvalidArgument(format!(
+                "theme has too many bands: {} (max {MAX_THEME_BANDS})",
+                self.bands.len()
+            )));
+        }
+
+        for band in &self.bands {
+            if !band.min_hz.is_finite() || !band.max_hz.is_finite() {
+                return Err(AppError::InvalidArgument(
+                    "theme band bounds must be finite".to_string(),
+                ));
+            }
+            if band.min_hz < 0.0 || band.min_hz >= band.max_hz {
+                return Err(AppError::InvalidArgument(format!(
+                    "invalid theme band range: {}..{} Hz",
+                    band.min_hz, band.max_hz
+                )));
+            }
+        }
+
+        let mut sorted = self.bands.iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.min_hz.total_cmp(&b.min_hz));
+        for pair in sorted.windows(2) {
+            // 关键行：重叠区间会让同一频段命中多个颜色，直接拒绝而不是按顺序覆盖。
+            if pair[0].max_hz > pair[1].min_hz {
+                return Err(AppError::InvalidArgument(format!(
+                    "theme bands overlap: {}..{} Hz and {}..{} Hz",
+                    pair[0].min_hz, pair[0].max_hz, pair[1].min_hz, pair[1].max_hz
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 按中心频率查找频段颜色，未命中时返回兜底颜色。
+    pub fn color_for_frequency(&self, frequency_hz: f32) -> [u8; 3] {
+        self.bands
+            .iter()
+            .find(|band| frequency_hz >= band.min_hz && frequency_hz < band.max_hz)
+            .map_or(self.fallback_color, |band| band.color)
+    }
+
+    /// 为每个频段中心频率生成颜色提示，长度与频率数组一致。
+    pub fn bin_colors(&self, frequencies_hz: &[f32]) -> Vec<[u8; 3]> {
+        frequencies_hz
+            .iter()
+            .map(|frequency| self.color_for_frequency(*frequency))
+            .collect()
+    }
+}
+
+/// 主题运行时快照：当前主题 + 是否在分析帧中附带逐频段颜色。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeSnapshot {
+    pub theme: Theme,
+    pub emit_bin_colors: bool,
+}
+
+/// 主题运行时状态：命令层写入，分析线程每次发帧前读取。
+#[derive(Clone)]
+pub struct ThemeState {
+    inner: Arc<Mutex<ThemeSnapshot>>,
+}
+
+impl ThemeState {
+    /// 创建主题状态容器，初始值由持久化设置注入。
+    pub fn new(theme: Theme, emit_bin_colors: bool) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ThemeSnapshot {
+                theme,
+                emit_bin_colors,
+            })),
+        }
+    }
+
+    /// 读取当前主题快照。
+    pub fn get(&self) -> ThemeSnapshot {
+        self.inner
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or(ThemeSnapshot {
+                theme: Theme::default(),
+                emit_bin_colors: false,
+            })
+    }
+
+    /// 替换当前主题，调用方需先完成校验。
+    pub fn set_theme(&self, theme: Theme) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.theme = theme;
+        }
+    }
+
+    /// 更新是否在分析帧中附带颜色提示。
+    pub fn set_emit_bin_colors(&self, enabled: bool) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.emit_bin_colors = enabled;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOW: [u8; 3] = [255, 0, 0];
+    const HIGH: [u8; 3] = [0, 0, 255];
+    const FALLBACK: [u8; 3] = [9, 9, 9];
+
+    /// 两个频段之间留出 500..1000 Hz 的空隙。
+    fn gapped_theme() -> Theme {
+        Theme {
+            bands: vec![
+                ThemeBand {
+                    min_hz: 0.0,
+                    max_hz: 500.0,
+                    color: LOW,
+                },
+                ThemeBand {
+                    min_hz: 1000.0,
+                    max_hz: 8000.0,
+                    color: HIGH,
+                },
+            ],
+            fallback_color: FALLBACK,
+        }
+    }
+
+    #[test]
+    fn bins_take_their_band_color_or_the_fallback() {
+        let theme = gapped_theme();
+        assert!(theme.validate().is_ok());
+
+        let colors = theme.bin_colors(&[50.0, 499.0, 500.0, 750.0, 1000.0, 7999.0, 12000.0]);
+        // 关键行：区间左闭右开，落在空隙或超出全部频段的频率使用兜底颜色。
+        assert_eq!(colors, [LOW, LOW, FALLBACK, FALLBACK, HIGH, HIGH, FALLBACK]);
+    }
+
+    #[test]
+    fn overlapping_bands_are_rejected() {
+        let mut theme = gapped_theme();
+        theme.bands[1].min_hz = 400.0;
+        assert!(matches!(
+            theme.validate(),
+            Err(AppError::InvalidArgument(_))
+        ));
+    }
+}
@@ -1,8 +1,25 @@
-﻿import type { AppSettings, QualityTier, WindowMode } from "@/types";
+﻿import type { AppSettings, BinFormat, ChannelMode, DownmixMode, FrequencyScale, QualityTier, Theme, Weighting, WindowFunction, WindowMode } from "@/types";
 
 const SETTINGS_KEY = "tt-audio-lab.settings";
 const QUALITY_SET: QualityTier[] = ["ultra", "high", "balanced"];
 const WINDOW_MODE_SET: WindowMode[] = ["normal", "desktopWidget", "overlay"];
+const BIN_FORMAT_SET: BinFormat[] = ["u16", "f32"];
+const WINDOW_FUNCTION_SET: WindowFunction[] = ["hann", "hamming", "blackmanHarris", "flatTop"];
+const FREQUENCY_SCALE_SET: FrequencyScale[] = ["mixedLogLinear", "mel", "linear", "logarithmic"];
+const CHANNEL_MODE_SET: ChannelMode[] = ["mono", "stereo", "midSide"];
+const WEIGHTING_SET: Weighting[] = ["none", "aWeighting", "cWeighting"];
+const DOWNMIX_MODE_SET: DownmixMode[] = ["average", "sum", "max", "rms"];
+
+// 与后端 `Theme::default` 保持一致。
+export const defaultTheme: Theme = {
+  bands: [
+    { minHz: 0, maxHz: 250, color: [255, 92, 92] },
+    { minHz: 250, maxHz: 2000, color: [255, 196, 92] },
+    { minHz: 2000, maxHz: 6000, color: [92, 200, 255] },
+    { minHz: 6000, maxHz: 24000, color: [176, 124, 255] }
+  ],
+  fallbackColor: [200, 200, 200]
+};
 
 export const defaultSettings: AppSettings = {
   quality: "ultra",
@@ -11,7 +28,70 @@ export const defaultSettings: AppSettings = {
   clickThrough: false,
   launchAtStartup: false,
   windowMode: "normal",
-  targetMonitorId: ""
+  targetMonitorId: "",
+  theme: defaultTheme,
+  emitBinColors: false,
+  captureHoldMs: 300,
+  captureHoldDecay: true,
+  globalMotionRmsWeight: 0.8,
+  globalMotionPeakWeight: 0.6,
+  globalMotionGamma: 1,
+  modeTransitionFade: false,
+  maxAnalysisSampleRate: 96000,
+  animateTray: false,
+  silenceScreensaver: false,
+  silenceScreensaverAfterMs: 10000,
+  binFormat: "u16",
+  outputDeviceId: "",
+  prefillSampleBuffer: true,
+  recentDevices: [],
+  windowFade: false,
+  windowFadeMs: 150,
+  fftAverages: 1,
+  ipcByteBudgetPerSec: 0,
+  captureDeviceId: "",
+  captureSampleRate: 0,
+  captureBufferFrames: 0,
+  binCount: 64,
+  channelMode: "mono",
+  downmixWeights: [],
+  windowFunction: "hann",
+  silenceAutoPause: false,
+  silenceThreshold: 0.002,
+  silenceTimeoutMs: 5000,
+  attack: null,
+  release: null,
+  scaleMode: "adaptive",
+  dbFloor: -80,
+  analysisSampleRate: 0,
+  beatSensitivity: 0.5,
+  mixDefaultInput: false,
+  mixPrimaryGain: 1,
+  mixInputGain: 1,
+  windowGeometry: {},
+  pauseHotkey: "",
+  dcBlocker: true,
+  presets: {},
+  activePreset: "",
+  weighting: "none",
+  frequencyScale: "mixedLogLinear",
+  peakHold: false,
+  peakDecay: 0.8,
+  noiseGateThreshold: 0,
+  noiseGateHoldMs: 500,
+  eqGains: [],
+  pitchDetection: false,
+  chroma: false,
+  diffusion: 0.64,
+  hopFraction: 0,
+  overlayOpacity: 1,
+  loudness: false,
+  minHz: 20,
+  maxHz: 20000,
+  binNoiseGate: 0,
+  tiltDbPerOctave: 0,
+  downmixMode: "average",
+  zeroPadFactor: 1
 };
 
 function clamp(value: number, min: number, max: number): number {
@@ -32,6 +112,57 @@ function normalizeWindowMode(value: unknown): WindowMode {
   return WINDOW_MODE_SET.includes(value as WindowMode) ? (value as WindowMode) : defaultSettings.windowMode;
 }
 
+/**
+ * 校验频段格式，非法值回退 u16。
+ */
+function normalizeBinFormat(value: unknown): BinFormat {
+  return BIN_FORMAT_SET.includes(value as BinFormat) ? (value as BinFormat) : defaultSettings.binFormat;
+}
+
+/**
+ * 校验窗函数，非法值回退 Hann。
+ */
+function normalizeWindowFunction(value: unknown): WindowFunction {
+  return WINDOW_FUNCTION_SET.includes(value as WindowFunction)
+    ? (value as WindowFunction)
+    : defaultSettings.windowFunction;
+}
+
+function normalizeFrequencyScale(value: unknown): FrequencyScale {
+  return FREQUENCY_SCALE_SET.includes(value as FrequencyScale)
+    ? (value as FrequencyScale)
+    : defaultSettings.frequencyScale;
+}
+
+function normalizeChannelMode(value: unknown): ChannelMode {
+  return CHANNEL_MODE_SET.includes(value as ChannelMode)
+    ? (value as ChannelMode)
+    : defaultSettings.channelMode;
+}
+
+function normalizeDownmixMode(value: unknown): DownmixMode {
+  return DOWNMIX_MODE_SET.includes(value as DownmixMode)
+    ? (value as DownmixMode)
+    : defaultSettings.downmixMode;
+}
+
+function normalizeWeighting(value: unknown): Weighting {
+  return WEIGHTING_SET.includes(value as Weighting)
+    ? (value as Weighting)
+    : defaultSettings.weighting;
+}
+
+/**
+ * 校验主题结构，详细的频段覆盖校验由后端负责。
+ */
+function normalizeTheme(value: unknown): Theme {
+  const candidate = value as Partial<Theme> | null | undefined;
+  if (!candidate || !Array.isArray(candidate.bands) || !Array.isArray(candidate.fallbackColor)) {
+    return defaultTheme;
+  }
+  return { bands: candidate.bands, fallbackColor: candidate.fallbackColor };
+}
+
 /**
  * 统一收敛设置范围，防止异常值影响可视化稳定性。
  */
@@ -43,7 +174,70 @@ export function normalizeSettings(input: Partial<AppSettings> | null | undefined
     clickThrough: Boolean(input?.clickThrough ?? defaultSettings.clickThrough),
     launchAtStartup: Boolean(input?.launchAtStartup ?? defaultSettings.launchAtStartup),
     windowMode: normalizeWindowMode(input?.windowMode),
-    targetMonitorId: typeof input?.targetMonitorId === "string" ? input.targetMonitorId : ""
+    targetMonitorId: typeof input?.targetMonitorId === "string" ? input.targetMonitorId : "",
+    theme: normalizeTheme(input?.theme),
+    emitBinColors: Boolean(input?.emitBinColors ?? defaultSettings.emitBinColors),
+    captureHoldMs: clamp(Number(input?.captureHoldMs ?? defaultSettings.captureHoldMs), 0, 5000),
+    captureHoldDecay: Boolean(input?.captureHoldDecay ?? defaultSettings.captureHoldDecay),
+    globalMotionRmsWeight: clamp(Number(input?.globalMotionRmsWeight ?? defaultSettings.globalMotionRmsWeight), 0, 2),
+    globalMotionPeakWeight: clamp(Number(input?.globalMotionPeakWeight ?? defaultSettings.globalMotionPeakWeight), 0, 2),
+    globalMotionGamma: clamp(Number(input?.globalMotionGamma ?? defaultSettings.globalMotionGamma), 0.2, 5),
+    modeTransitionFade: Boolean(input?.modeTransitionFade ?? defaultSettings.modeTransitionFade),
+    maxAnalysisSampleRate: clamp(Number(input?.maxAnalysisSampleRate ?? defaultSettings.maxAnalysisSampleRate), 32000, 384000),
+    animateTray: Boolean(input?.animateTray ?? defaultSettings.animateTray),
+    silenceScreensaver: Boolean(input?.silenceScreensaver ?? defaultSettings.silenceScreensaver),
+    silenceScreensaverAfterMs: clamp(Number(input?.silenceScreensaverAfterMs ?? defaultSettings.silenceScreensaverAfterMs), 1000, 600000),
+    binFormat: normalizeBinFormat(input?.binFormat),
+    outputDeviceId: typeof input?.outputDeviceId === "string" ? input.outputDeviceId : "",
+    prefillSampleBuffer: Boolean(input?.prefillSampleBuffer ?? defaultSettings.prefillSampleBuffer),
+    recentDevices: Array.isArray(input?.recentDevices) ? input.recentDevices : [],
+    windowFade: Boolean(input?.windowFade ?? defaultSettings.windowFade),
+    windowFadeMs: clamp(Number(input?.windowFadeMs ?? defaultSettings.windowFadeMs), 50, 1000),
+    fftAverages: clamp(Math.round(Number(input?.fftAverages ?? defaultSettings.fftAverages)), 1, 16),
+    ipcByteBudgetPerSec: clamp(Number(input?.ipcByteBudgetPerSec ?? defaultSettings.ipcByteBudgetPerSec), 0, 20000000),
+    captureDeviceId: typeof input?.captureDeviceId === "string" ? input.captureDeviceId : "",
+    captureSampleRate: clamp(Math.round(Number(input?.captureSampleRate ?? defaultSettings.captureSampleRate)), 0, 384000),
+    captureBufferFrames: clamp(Math.round(Number(input?.captureBufferFrames ?? defaultSettings.captureBufferFrames)), 0, 8192),
+    binCount: clamp(Math.round(Number(input?.binCount ?? defaultSettings.binCount)), 16, 512),
+    channelMode: normalizeChannelMode(input?.channelMode),
+    downmixWeights: Array.isArray(input?.downmixWeights) ? input.downmixWeights.map(Number).filter(Number.isFinite) : [],
+    windowFunction: normalizeWindowFunction(input?.windowFunction),
+    silenceAutoPause: Boolean(input?.silenceAutoPause ?? defaultSettings.silenceAutoPause),
+    silenceThreshold: clamp(Number(input?.silenceThreshold ?? defaultSettings.silenceThreshold), 0, 0.1),
+    silenceTimeoutMs: clamp(Number(input?.silenceTimeoutMs ?? defaultSettings.silenceTimeoutMs), 500, 600000),
+    attack: input?.attack == null ? null : clamp(Number(input.attack), 0, 0.99),
+    release: input?.release == null ? null : clamp(Number(input.release), 0, 0.99),
+    scaleMode: input?.scaleMode === "decibel" ? "decibel" : "adaptive",
+    dbFloor: clamp(Number(input?.dbFloor ?? defaultSettings.dbFloor), -140, -20),
+    analysisSampleRate: clamp(Math.round(Number(input?.analysisSampleRate ?? defaultSettings.analysisSampleRate)), 0, 192000),
+    beatSensitivity: clamp(Number(input?.beatSensitivity ?? defaultSettings.beatSensitivity), 0, 1),
+    mixDefaultInput: Boolean(input?.mixDefaultInput ?? defaultSettings.mixDefaultInput),
+    mixPrimaryGain: clamp(Number(input?.mixPrimaryGain ?? defaultSettings.mixPrimaryGain), 0, 4),
+    mixInputGain: clamp(Number(input?.mixInputGain ?? defaultSettings.mixInputGain), 0, 4),
+    windowGeometry: typeof input?.windowGeometry === "object" && input.windowGeometry !== null ? input.windowGeometry : {},
+    pauseHotkey: typeof input?.pauseHotkey === "string" ? input.pauseHotkey.trim() : "",
+    dcBlocker: Boolean(input?.dcBlocker ?? defaultSettings.dcBlocker),
+    presets: typeof input?.presets === "object" && input.presets !== null ? input.presets : {},
+    activePreset: typeof input?.activePreset === "string" ? input.activePreset : "",
+    weighting: normalizeWeighting(input?.weighting),
+    frequencyScale: normalizeFrequencyScale(input?.frequencyScale),
+    peakHold: typeof input?.peakHold === "boolean" ? input.peakHold : false,
+    peakDecay: clamp(Number(input?.peakDecay ?? defaultSettings.peakDecay), 0.05, 5),
+    noiseGateThreshold: clamp(Number(input?.noiseGateThreshold ?? defaultSettings.noiseGateThreshold), 0, 0.1),
+    noiseGateHoldMs: clamp(Number(input?.noiseGateHoldMs ?? defaultSettings.noiseGateHoldMs), 0, 10000),
+    eqGains: Array.isArray(input?.eqGains) ? input.eqGains.map(Number).filter(Number.isFinite).map((gain: number) => clamp(gain, 0, 4)).slice(0, 32) : [],
+    pitchDetection: typeof input?.pitchDetection === "boolean" ? input.pitchDetection : false,
+    chroma: typeof input?.chroma === "boolean" ? input.chroma : false,
+    diffusion: clamp(Number(input?.diffusion ?? defaultSettings.diffusion), 0.34, 1),
+    hopFraction: clamp(Number(input?.hopFraction ?? defaultSettings.hopFraction), 0, 1),
+    overlayOpacity: clamp(Number(input?.overlayOpacity ?? defaultSettings.overlayOpacity), 0.1, 1),
+    loudness: typeof input?.loudness === "boolean" ? input.loudness : false,
+    minHz: clamp(Number(input?.minHz ?? defaultSettings.minHz), 1, 2000),
+    maxHz: clamp(Number(input?.maxHz ?? defaultSettings.maxHz), 1000, 96000),
+    binNoiseGate: clamp(Number(input?.binNoiseGate ?? defaultSettings.binNoiseGate), 0, 0.01),
+    tiltDbPerOctave: clamp(Number(input?.tiltDbPerOctave ?? defaultSettings.tiltDbPerOctave), -6, 6),
+    downmixMode: normalizeDownmixMode(input?.downmixMode),
+    zeroPadFactor: clamp(Math.round(Number(input?.zeroPadFactor ?? defaultSettings.zeroPadFactor)), 1, 8)
   };
 }
 
@@ -3,6 +3,61 @@ export type QualityTier = "ultra" | "high" | "balanced";
 
 export type WindowMode = "normal" | "desktopWidget" | "overlay";
 
+// 分析帧频段格式：u16 为 0..1023 量化值，f32 为 0..1 浮点（精度更高、负载更大）。
+export type BinFormat = "u16" | "f32";
+export type WindowFunction = "hann" | "hamming" | "blackmanHarris" | "flatTop";
+export type ScaleMode = "adaptive" | "decibel";
+export type FrequencyScale = "mixedLogLinear" | "mel" | "linear" | "logarithmic";
+export type ChannelMode = "mono" | "stereo" | "midSide";
+export type Weighting = "none" | "aWeighting" | "cWeighting";
+export type DownmixMode = "average" | "sum" | "max" | "rms";
+
+// 频段配色主题由后端统一维护，前端仅透传和渲染。
+export interface ThemeBand {
+  minHz: number;
+  maxHz: number;
+  color: [number, number, number];
+}
+
+export interface Theme {
+  bands: ThemeBand[];
+  fallbackColor: [number, number, number];
+}
+
+// 后端命令错误结构：按 code 分支处理，message 仅用于兜底展示。
+export type AppErrorCode =
+  | "DeviceNotFound"
+  | "MonitorNotFound"
+  | "PresetNotFound"
+  | "WindowNotFound"
+  | "SettingsIo"
+  | "SettingsParse"
+  | "Capture"
+  | "Window"
+  | "InvalidArgument"
+  | "Unsupported"
+  | "Export"
+  | "Internal";
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+}
+
+// 最近成功采集过的设备，由后端维护。
+export interface RecentDevice {
+  id: string;
+  name: string;
+}
+
+// 命名 DSP 预设：由 `save_preset` 从当前设置截取，`load_preset` 应用。
+export interface DspPreset {
+  quality: QualityTier;
+  smoothing: number;
+  gain: number;
+  binCount: number;
+}
+
 export interface AppSettings {
   quality: QualityTier;
   smoothing: number;
@@ -11,21 +66,203 @@ export interface AppSettings {
   launchAtStartup: boolean;
   windowMode: WindowMode;
   targetMonitorId: string;
+  theme: Theme;
+  emitBinColors: boolean;
+  captureHoldMs: number;
+  captureHoldDecay: boolean;
+  globalMotionRmsWeight: number;
+  globalMotionPeakWeight: number;
+  globalMotionGamma: number;
+  modeTransitionFade: boolean;
+  maxAnalysisSampleRate: number;
+  animateTray: boolean;
+  silenceScreensaver: boolean;
+  silenceScreensaverAfterMs: number;
+  binFormat: BinFormat;
+  outputDeviceId: string;
+  prefillSampleBuffer: boolean;
+  recentDevices: RecentDevice[];
+  windowFade: boolean;
+  windowFadeMs: number;
+  fftAverages: number;
+  ipcByteBudgetPerSec: number;
+  captureDeviceId: string;
+  captureSampleRate: number;
+  captureBufferFrames: number;
+  binCount: number;
+  channelMode: ChannelMode;
+  downmixWeights: number[];
+  windowFunction: WindowFunction;
+  silenceAutoPause: boolean;
+  silenceThreshold: number;
+  silenceTimeoutMs: number;
+  attack: number | null;
+  release: number | null;
+  scaleMode: ScaleMode;
+  dbFloor: number;
+  analysisSampleRate: number;
+  beatSensitivity: number;
+  mixDefaultInput: boolean;
+  mixPrimaryGain: number;
+  mixInputGain: number;
+  windowGeometry: Partial<Record<WindowMode, WindowGeometry>>;
+  pauseHotkey: string;
+  dcBlocker: boolean;
+  presets: Record<string, DspPreset>;
+  activePreset: string;
+  weighting: Weighting;
+  frequencyScale: FrequencyScale;
+  peakHold: boolean;
+  peakDecay: number;
+  noiseGateThreshold: number;
+  noiseGateHoldMs: number;
+  eqGains: number[];
+  pitchDetection: boolean;
+  chroma: boolean;
+  diffusion: number;
+  hopFraction: number;
+  overlayOpacity: number;
+  loudness: boolean;
+  minHz: number;
+  maxHz: number;
+  binNoiseGate: number;
+  tiltDbPerOctave: number;
+  downmixMode: DownmixMode;
+  zeroPadFactor: number;
 }
 
+export type FrameSource = "realtime" | "loopback" | "mock";
+
 export interface AnalysisFrame {
   timestampMs: number;
   deviceId: string;
-  bins: number[];
+  source: FrameSource;
+  bins?: number[];
+  binsF32?: number[];
   rms: number;
   peak: number;
+  // 频谱质心（0..1，相对奈奎斯特频率），静音时为 0。
+  centroid: number;
   latencyEstimateMs: number;
+  // 静音自动暂停前的最后一帧（已归零），之后直到有声音前不再推送。
+  silent: boolean;
+  // 自上一帧以来输入端削波的样本数，持续非零说明输入电平过高。
+  clipCount: number;
+  // 立体声模式下的左右声道频段（中侧模式下为中置、侧向），编码与 bins/binsF32 一致。
+  binsLeft?: number[];
+  binsRight?: number[];
+  // 开启峰值保持时的逐频段峰值，编码与 bins/binsF32 一致。
+  peakBins?: number[];
+  // 开启音高检测时估计的基频（Hz），置信度不足或静音时省略。
+  pitchHz?: number;
+  // 开启色度输出时的十二音级能量（C、C#、…、B），按最强音级归一化到 0..1。
+  chroma?: number[];
+  // 开启响度测量时的 K 计权积分响度（LUFS），约每 400ms 刷新。
+  lufs?: number;
+  binColors?: [number, number, number][];
+}
+
+// `audio:source_changed` 事件负载：真实采集失败转入模拟数据时推送一次。
+export interface SourceChange {
+  source: FrameSource;
+  error: string;
+}
+
+// `audio:capture_reconnecting` 事件负载：设备断开后每次重连尝试前推送。
+export interface CaptureReconnecting {
+  attempt: number;
+  maxAttempts: number;
+  delayMs: number;
+}
+
+// `audio:capture_recovered` 事件负载：断开重连成功、采集重新建立后推送一次。
+export interface CaptureRecovered {
+  attempt: number;
+  deviceId: string;
+}
+
+// `audio:beat` 事件负载：谱通量起音检测命中时推送，strength 为 0..1。
+export interface BeatEvent {
+  timestampMs: number;
+  strength: number;
+}
+
+// `audio:ipc_budget_adapted` 事件负载，null 表示已恢复完整帧。
+// 预算不足时按此顺序丢弃的可选字段；channelBins 对应 binsLeft/binsRight。
+export type IpcOptionalField = "binColors" | "peakBins" | "channelBins" | "chroma" | "lufs" | "pitchHz";
+
+export interface IpcBudgetAdaptation {
+  droppedFields: IpcOptionalField[];
+  binCount: number | null;
+  emitIntervalMs: number;
+  frameBytes: number;
+}
+
+// `audio:frame_rate_adjusted` 事件负载：分析与推送耗时过载导致发帧放慢或恢复时推送。
+export interface FrameRateAdjusted {
+  emitIntervalMs: number;
+  frameRateHz: number;
+  averageFrameMs: number;
+  backedOff: boolean;
+}
+
+// `get_level_history` 返回的单条电平记录（每个采集数据块一条，按时间先后排列）。
+export interface LevelEntry {
+  timestampMs: number;
+  rms: number;
+  peak: number;
+}
+
+// `set_interactive_regions` 的热区矩形：逻辑像素（CSS 像素），原点为窗口客户区左上角。
+export interface Rect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
 }
 
 export interface AudioDeviceInfo {
   id: string;
   name: string;
   direction: "input" | "output" | string;
+  isDefault: boolean;
+  // 设备默认流格式；读取失败时为 0。
+  defaultSampleRate: number;
+  channels: number;
+}
+
+// `get_capture_status` 返回值：当前实际生效的采集设备与格式。
+export interface CaptureStatus {
+  deviceId: string;
+  deviceName: string;
+  sampleRate: number;
+  channels: number;
+  isMock: boolean;
+  source: FrameSource;
+  // 采集回调缓冲时长估计（毫秒），尚未收到数据时为 0。
+  estimatedBufferMs: number;
+}
+
+// `start_recording` 返回值：写入路径与实际录制的样本数和时长（秒）。
+export interface RecordingSummary {
+  path: string;
+  sampleRate: number;
+  samples: number;
+  durationSeconds: number;
+}
+
+// `audio:devices_changed` 事件负载：设备插拔后的完整列表与当前采集设备。
+export interface DevicesChanged {
+  devices: AudioDeviceInfo[];
+  capturingDeviceId: string | null;
+}
+
+// 窗口外框位置与尺寸（物理像素），按窗口模式分别持久化。
+export interface WindowGeometry {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
 }
 
 export interface MonitorInfo {